@@ -0,0 +1,5 @@
+// Rebuilds the binaries when a migration changes, as they embed the migrations.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=migrations_postgres");
+}
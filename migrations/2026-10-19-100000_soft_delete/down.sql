@@ -0,0 +1,2 @@
+DELETE FROM weights WHERE deleted_at IS NOT NULL;
+ALTER TABLE weights DROP COLUMN deleted_at;
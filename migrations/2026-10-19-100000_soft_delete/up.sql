@@ -0,0 +1 @@
+ALTER TABLE weights ADD COLUMN deleted_at TIMESTAMP;
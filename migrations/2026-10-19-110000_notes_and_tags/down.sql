@@ -0,0 +1,3 @@
+DROP TABLE weight_tags;
+DROP TABLE tags;
+ALTER TABLE weights DROP COLUMN note;
@@ -0,0 +1,9 @@
+CREATE TABLE events (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    title TEXT NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE,
+    note TEXT
+);
+CREATE INDEX events_start_date ON events (start_date);
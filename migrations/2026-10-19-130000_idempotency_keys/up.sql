@@ -0,0 +1,8 @@
+CREATE TABLE idempotency_keys (
+    idempotency_key TEXT NOT NULL PRIMARY KEY,
+    request_hash TEXT NOT NULL,
+    status_code INTEGER,
+    response_body TEXT,
+    created_at TIMESTAMP NOT NULL
+);
+CREATE INDEX idempotency_keys_created_at ON idempotency_keys (created_at);
@@ -0,0 +1 @@
+ALTER TABLE weights ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
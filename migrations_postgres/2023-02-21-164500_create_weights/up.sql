@@ -0,0 +1,5 @@
+CREATE TABLE weights (
+    id SERIAL PRIMARY KEY,
+    weight DOUBLE PRECISION NOT NULL,
+    measurement_date TIMESTAMP NOT NULL
+);
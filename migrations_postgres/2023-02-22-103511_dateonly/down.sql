@@ -0,0 +1,3 @@
+ALTER TABLE weights DROP CONSTRAINT weights_measurement_date_key;
+ALTER TABLE weights ALTER COLUMN measurement_date TYPE TIMESTAMP;
+ALTER TABLE weights RENAME COLUMN weight_value TO weight;
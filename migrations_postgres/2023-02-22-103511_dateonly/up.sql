@@ -0,0 +1,3 @@
+ALTER TABLE weights RENAME COLUMN weight TO weight_value;
+ALTER TABLE weights ALTER COLUMN measurement_date TYPE DATE;
+ALTER TABLE weights ADD CONSTRAINT weights_measurement_date_key UNIQUE (measurement_date);
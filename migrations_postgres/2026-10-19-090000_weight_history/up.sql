@@ -0,0 +1,11 @@
+CREATE TABLE weight_history (
+    id SERIAL PRIMARY KEY,
+    measurement_date DATE NOT NULL,
+    action TEXT NOT NULL,
+    old_value DOUBLE PRECISION,
+    new_value DOUBLE PRECISION,
+    changed_at TIMESTAMP NOT NULL,
+    source TEXT NOT NULL,
+    actor TEXT NOT NULL
+);
+CREATE INDEX weight_history_measurement_date ON weight_history (measurement_date);
@@ -0,0 +1,11 @@
+ALTER TABLE weights ADD COLUMN note TEXT;
+CREATE TABLE tags (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+CREATE TABLE weight_tags (
+    weight_id INTEGER NOT NULL REFERENCES weights (id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (weight_id, tag_id)
+);
+CREATE INDEX weight_tags_tag_id ON weight_tags (tag_id);
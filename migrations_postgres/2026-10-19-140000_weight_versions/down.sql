@@ -0,0 +1 @@
+ALTER TABLE weights DROP COLUMN version;
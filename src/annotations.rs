@@ -0,0 +1,194 @@
+//! Free-text notes and tags on the weights of days, e.g. "after holiday" or "new scale".
+//!
+//! The note is a column of `weights`; tags are shared between weights through `weight_tags`.
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::db::{on_backend, DbConnection};
+
+/// The longest tag accepted, in characters.
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// The note and tags of a day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotation {
+    pub note: Option<String>,
+    /// Sorted by name.
+    pub tags: Vec<String>,
+}
+
+/// Tags are compared without case and surrounding whitespace, so they are stored trimmed and in
+/// lower case. Returns an error for an empty or too long tag.
+pub fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err(String::from("tags must not be empty"));
+    }
+    if tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(format!(
+            "tags must not be longer than {} characters",
+            MAX_TAG_LENGTH
+        ));
+    }
+    Ok(tag)
+}
+
+/// Why the note or the tags of a weight were not stored.
+#[derive(Debug)]
+pub enum AnnotationError {
+    /// A tag is empty or too long; holds the tag as given and the reason.
+    InvalidTag {
+        tag: String,
+        message: String,
+    },
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for AnnotationError {
+    fn from(error: diesel::result::Error) -> Self {
+        AnnotationError::Database(error)
+    }
+}
+
+impl fmt::Display for AnnotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnnotationError::InvalidTag { tag, message } => write!(f, "tag {:?}: {}", tag, message),
+            AnnotationError::Database(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AnnotationError {}
+
+/// The notes and tags of the weights between two dates (inclusive), for the days that have any.
+/// Weights in the trash are left out.
+pub fn annotations_between_dates(
+    conn: &mut DbConnection,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> QueryResult<BTreeMap<NaiveDate, Annotation>> {
+    use crate::schema::{tags, weight_tags, weights};
+
+    let in_interval = weights::deleted_at
+        .is_null()
+        .and(weights::measurement_date.ge(start_date))
+        .and(weights::measurement_date.le(end_date));
+
+    let notes = weights::table
+        .filter(in_interval)
+        .filter(weights::note.is_not_null())
+        .select((weights::measurement_date, weights::note))
+        .load::<(NaiveDate, Option<String>)>(conn)?;
+    let day_tags = weights::table
+        .inner_join(weight_tags::table.inner_join(tags::table))
+        .filter(in_interval)
+        .select((weights::measurement_date, tags::name))
+        .order((weights::measurement_date.asc(), tags::name.asc()))
+        .load::<(NaiveDate, String)>(conn)?;
+
+    let mut annotations: BTreeMap<NaiveDate, Annotation> = BTreeMap::new();
+    for (date, note) in notes {
+        annotations.entry(date).or_default().note = note;
+    }
+    for (date, tag) in day_tags {
+        annotations.entry(date).or_default().tags.push(tag);
+    }
+    Ok(annotations)
+}
+
+/// Sets the note of a weight; an empty note removes it. Returns whether the note changed.
+pub(crate) fn set_note(
+    conn: &mut DbConnection,
+    weight_id: i32,
+    note: Option<&str>,
+) -> QueryResult<bool> {
+    use crate::schema::weights;
+
+    let note = note.map(str::trim).filter(|note| !note.is_empty());
+    let current = weights::table
+        .find(weight_id)
+        .select(weights::note)
+        .first::<Option<String>>(conn)?;
+    if current.as_deref() == note {
+        return Ok(false);
+    }
+    diesel::update(weights::table.find(weight_id))
+        .set(weights::note.eq(note))
+        .execute(conn)?;
+    Ok(true)
+}
+
+/// Replaces the tags of a weight, creating the tags that do not exist yet. Nothing is changed
+/// when one of the tags is invalid. Returns whether the tags changed.
+pub(crate) fn set_tags(
+    conn: &mut DbConnection,
+    weight_id: i32,
+    new_tags: &[String],
+) -> Result<bool, AnnotationError> {
+    use crate::schema::{tags, weight_tags};
+
+    let mut names = new_tags
+        .iter()
+        .map(|tag| {
+            normalize_tag(tag).map_err(|message| AnnotationError::InvalidTag {
+                tag: tag.clone(),
+                message,
+            })
+        })
+        .collect::<Result<Vec<String>, _>>()?;
+    names.sort();
+    names.dedup();
+
+    let current = weight_tags::table
+        .inner_join(tags::table)
+        .filter(weight_tags::weight_id.eq(weight_id))
+        .select(tags::name)
+        .order(tags::name.asc())
+        .load::<String>(conn)?;
+    if current == names {
+        return Ok(false);
+    }
+
+    diesel::delete(weight_tags::table.filter(weight_tags::weight_id.eq(weight_id)))
+        .execute(conn)?;
+    if names.is_empty() {
+        return Ok(true);
+    }
+
+    for name in &names {
+        let insert = diesel::insert_into(tags::table)
+            .values(tags::name.eq(name))
+            .on_conflict_do_nothing();
+        on_backend!(conn, |conn| insert.execute(conn))?;
+    }
+    let tag_ids = tags::table
+        .filter(tags::name.eq_any(&names))
+        .select(tags::id)
+        .load::<i32>(conn)?;
+    let rows: Vec<_> = tag_ids
+        .into_iter()
+        .map(|tag_id| {
+            (
+                weight_tags::weight_id.eq(weight_id),
+                weight_tags::tag_id.eq(tag_id),
+            )
+        })
+        .collect();
+    let insert = diesel::insert_into(weight_tags::table).values(&rows);
+    on_backend!(conn, |conn| insert.execute(conn))?;
+    Ok(true)
+}
+
+/// Removes the note and the tags of a weight.
+pub(crate) fn clear(conn: &mut DbConnection, weight_id: i32) -> QueryResult<()> {
+    use crate::schema::weight_tags;
+
+    set_note(conn, weight_id, None)?;
+    diesel::delete(weight_tags::table.filter(weight_tags::weight_id.eq(weight_id)))
+        .execute(conn)?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Entity tags and conditional requests: `ETag` on the responses of the reading routes, `304 Not
+//! Modified` for the clients that already have the data, and the `If-Match` and `If-None-Match`
+//! preconditions of the writes to a weight, see [`crate::versions`].
+
+use axum::body::{self, Body, Full};
+use axum::http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+
+use super::ApiError;
+use crate::versions::{Precondition, WeightVersion};
+
+/// The entity tag of a response body: the start of its SHA-256 digest.
+pub fn body_etag(body: &[u8]) -> String {
+    let digest: String = Sha256::digest(body)
+        .iter()
+        .take(16)
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("\"{}\"", digest)
+}
+
+/// The entity tags listed in an `If-Match` or `If-None-Match` header, or `None` for `*`.
+fn listed_tags(value: &HeaderValue) -> Result<Option<Vec<&str>>, ApiError> {
+    let value = value.to_str().map_err(|_| {
+        ApiError::bad_request("invalid_precondition", "Entity tags must be ASCII text")
+    })?;
+    match value.trim() {
+        "*" => Ok(None),
+        tags => Ok(Some(
+            tags.split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        )),
+    }
+}
+
+/// Whether an `If-None-Match` header matches an entity tag. The comparison is weak, as required
+/// for conditional GETs: `W/"x"` matches `"x"`.
+pub(crate) fn none_match_matches(value: &HeaderValue, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    match listed_tags(value) {
+        Ok(None) => true,
+        Ok(Some(tags)) => tags.into_iter().any(|tag| opaque(tag) == opaque(etag)),
+        Err(_) => false,
+    }
+}
+
+/// Middleware for the reading routes: tags every successful response with an `ETag`, computed
+/// from its body unless the handler set one, and answers `304 Not Modified` without a body when
+/// the client sent that tag in `If-None-Match`.
+pub async fn conditional_get(request: Request<Body>, next: Next<Body>) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, response_body) = response.into_parts();
+    let response_body = match hyper::body::to_bytes(response_body).await {
+        Ok(bytes) => bytes,
+        Err(err) => return ApiError::internal(err.to_string()).into_response(),
+    };
+    let etag = match parts.headers.get(header::ETAG) {
+        Some(etag) => etag.clone(),
+        None => {
+            let etag = HeaderValue::from_str(&body_etag(&response_body))
+                .expect("Hex digits are a valid header value");
+            parts.headers.insert(header::ETAG, etag.clone());
+            etag
+        }
+    };
+
+    let not_modified = if_none_match.is_some_and(|value| {
+        etag.to_str()
+            .is_ok_and(|etag| none_match_matches(&value, etag))
+    });
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+    Response::from_parts(parts, body::boxed(Full::from(response_body)))
+}
+
+/// The precondition of a write to a weight, from its `If-Match` or `If-None-Match` header.
+/// Entity tags that are not versions of a weight never match.
+pub fn precondition(headers: &HeaderMap) -> Result<Option<Precondition>, ApiError> {
+    let versions = |tags: Vec<&str>| -> Vec<WeightVersion> {
+        tags.into_iter()
+            .filter_map(WeightVersion::from_etag)
+            .collect()
+    };
+    match (
+        headers.get(header::IF_MATCH),
+        headers.get(header::IF_NONE_MATCH),
+    ) {
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => Err(ApiError::bad_request(
+            "invalid_precondition",
+            "If-Match and If-None-Match cannot be combined",
+        )),
+        (Some(value), None) => Ok(Some(match listed_tags(value)? {
+            None => Precondition::Exists,
+            Some(tags) => Precondition::OneOf(versions(tags)),
+        })),
+        (None, Some(value)) => Ok(Some(match listed_tags(value)? {
+            None => Precondition::Absent,
+            Some(tags) => Precondition::NoneOf(versions(tags)),
+        })),
+    }
+}
+
+/// The `ETag` header of the version of a weight.
+pub fn etag_header(version: WeightVersion) -> [(header::HeaderName, String); 1] {
+    [(header::ETAG, version.etag())]
+}
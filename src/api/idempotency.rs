@@ -0,0 +1,216 @@
+//! The `Idempotency-Key` header of write requests, see [`crate::idempotency`].
+
+use axum::body::{self, Body, Full};
+use axum::extract::OriginalUri;
+use axum::http::{header, HeaderValue, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use chrono::Duration;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use super::{with_connection, ApiError};
+use crate::idempotency::{self, Reservation, StoredResponse};
+
+/// The header in which clients send the key of a request they may retry.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Set on the responses sent again for a retried request.
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// The headers of a response stored with its body, to send them again for a retried request.
+const STORED_HEADERS: [header::HeaderName; 3] =
+    [header::CONTENT_TYPE, header::ETAG, header::LOCATION];
+
+/// How long the responses of the requests with a key are kept, as an `Extension`.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyRetention(pub Duration);
+
+/// How long a key stays reserved by a request that never completed, as an `Extension`.
+#[derive(Debug, Clone, Copy)]
+pub struct PendingTimeout(pub Duration);
+
+/// The keys of the requests being handled by this server. Their reservations are held for as long
+/// as the requests run, however long that is: the pending timeout only applies to the
+/// reservations left behind by a server that stopped.
+static IN_FLIGHT: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// A key of [`IN_FLIGHT`], removed when the request is done with it, whether it completed,
+/// failed or was cancelled.
+struct InFlight(String);
+
+impl InFlight {
+    /// Marks a key as in flight, unless another request holds it already.
+    fn hold(key: &str) -> Option<Self> {
+        let mut keys = IN_FLIGHT.lock().unwrap_or_else(|err| err.into_inner());
+        keys.insert(key.to_owned())
+            .then(|| InFlight(key.to_owned()))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut keys = IN_FLIGHT.lock().unwrap_or_else(|err| err.into_inner());
+        keys.remove(&self.0);
+    }
+}
+
+/// Middleware for the write routes: a request with an `Idempotency-Key` that was already handled
+/// gets the stored response of the first request instead of being handled again. Only successful
+/// responses are stored, so that a request that failed can be retried with the same key.
+pub async fn idempotent(
+    Extension(KeyRetention(retention)): Extension<KeyRetention>,
+    Extension(PendingTimeout(pending_timeout)): Extension<PendingTimeout>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let Some(key) = key
+        .to_str()
+        .ok()
+        .filter(|key| idempotency::is_valid_key(key))
+        .map(String::from)
+    else {
+        return ApiError::bad_request(
+            "invalid_idempotency_key",
+            format!(
+                "Idempotency-Key must be printable ASCII text of at most {} characters",
+                idempotency::MAX_KEY_LENGTH
+            ),
+        )
+        .into_response();
+    };
+
+    let Some(_in_flight) = InFlight::hold(&key) else {
+        return key_in_use();
+    };
+
+    let (parts, request_body) = request.into_parts();
+    let request_body = match hyper::body::to_bytes(request_body).await {
+        Ok(bytes) => bytes,
+        Err(err) => return ApiError::bad_request("invalid_body", err.to_string()).into_response(),
+    };
+    // Nested routers only see the end of the path.
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map_or(parts.uri.path(), |OriginalUri(uri)| uri.path());
+    let hash = idempotency::request_hash(parts.method.as_str(), path, &request_body);
+
+    let reserved = {
+        let key = key.clone();
+        with_connection(move |conn| {
+            Ok(idempotency::reserve(
+                conn,
+                &key,
+                &hash,
+                retention,
+                pending_timeout,
+            )?)
+        })
+        .await
+    };
+    match reserved {
+        Ok(Reservation::Reserved) => {}
+        Ok(Reservation::Completed(stored)) => return replay(stored),
+        Ok(Reservation::InProgress) => return key_in_use(),
+        Ok(Reservation::Mismatch) => {
+            return ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "idempotency_key_reused",
+                "This Idempotency-Key was already used for a different request",
+            )
+            .into_response()
+        }
+        Err(err) => return err.into_response(),
+    }
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(request_body)))
+        .await;
+    if !response.status().is_success() {
+        release(key).await;
+        return response;
+    }
+
+    let (parts, response_body) = response.into_parts();
+    let response_body = match hyper::body::to_bytes(response_body).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            release(key).await;
+            return ApiError::internal(err.to_string()).into_response();
+        }
+    };
+    let stored = StoredResponse {
+        status_code: parts.status.as_u16(),
+        body: String::from_utf8_lossy(&response_body).into_owned(),
+        headers: STORED_HEADERS
+            .iter()
+            .filter_map(|name| {
+                let value = parts.headers.get(name)?.to_str().ok()?;
+                Some((name.as_str().to_owned(), value.to_owned()))
+            })
+            .collect(),
+    };
+    let completed = {
+        let key = key.clone();
+        with_connection(move |conn| Ok(idempotency::complete(conn, &key, &stored)?)).await
+    };
+    if let Err(err) = completed {
+        tracing::error!("Failed to store the response of {}: {}", key, err.message);
+    }
+    Response::from_parts(parts, body::boxed(Full::from(response_body)))
+}
+
+/// Frees the key of a request that failed.
+async fn release(key: String) {
+    let released = {
+        let key = key.clone();
+        with_connection(move |conn| Ok(idempotency::release(conn, &key)?)).await
+    };
+    if let Err(err) = released {
+        tracing::error!(
+            "Failed to release the idempotency key {}: {}",
+            key,
+            err.message
+        );
+    }
+}
+
+/// The answer to a request whose key is reserved by a request still being handled.
+fn key_in_use() -> Response {
+    ApiError::new(
+        StatusCode::CONFLICT,
+        "idempotency_key_in_use",
+        "A request with this Idempotency-Key is still being handled, retry it later",
+    )
+    .into_response()
+}
+
+/// The stored response of a request that was already handled, with its stored headers. The
+/// write routes answer in JSON, which is assumed for the responses stored without headers.
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status_code).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (
+            header::HeaderName::try_from(name),
+            HeaderValue::try_from(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert(
+        header::HeaderName::from_static(REPLAYED_HEADER),
+        HeaderValue::from_static("true"),
+    );
+    response
+}
@@ -0,0 +1,276 @@
+//! The versioned JSON API of the back-end.
+//!
+//! Every version lives in its own module with its own router, nested under `/api/<version>`.
+//! A published version is frozen except for additive changes: new endpoints, new optional query
+//! parameters and new response fields. Renaming or removing a field, changing its type or meaning,
+//! or making a parameter mandatory requires a new version (`/api/v2`), served next to the old one
+//! until its clients have moved. The unversioned `/api/...` routes used by the bundled frontend
+//! predate this scheme and are kept as they are.
+
+pub mod conditional;
+pub mod idempotency;
+pub mod v1;
+
+use axum::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use utoipa::ToSchema;
+
+use crate::annotations::AnnotationError;
+use crate::db::DbConnection;
+use crate::validation::{self, ValidationError};
+use crate::versions::ConditionalError;
+use crate::Origin;
+
+use v1::{
+    AnnotationEntry, BatchResult, EventEntry, HistoryEntry, RollingAveragePoint, StoredWeight,
+    TrashedWeight, WeightEntry,
+};
+
+/// The unit of every weight in the API.
+pub const WEIGHT_UNIT: &str = "kg";
+
+/// How the data of a response was produced.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Meta {
+    /// The API version that produced the response.
+    #[schema(example = "v1")]
+    pub api_version: &'static str,
+    /// The unit of the weights in `data`.
+    #[schema(example = "kg")]
+    pub unit: &'static str,
+    /// Whether `data` contains values interpolated for days without a measurement.
+    pub interpolated: bool,
+    /// The size of the rolling average window in days, for averaged data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 7)]
+    pub window_days: Option<u32>,
+}
+
+/// The body of every successful response: the data and how it was produced.
+#[derive(Debug, Serialize, ToSchema)]
+#[aliases(
+    RollingAverageResponse = Envelope<Vec<RollingAveragePoint>>,
+    WeightsResponse = Envelope<Vec<WeightEntry>>,
+    StoredWeightResponse = Envelope<StoredWeight>,
+    BatchResponse = Envelope<BatchResult>,
+    HistoryResponse = Envelope<Vec<HistoryEntry>>,
+    WeightResponse = Envelope<WeightEntry>,
+    TrashResponse = Envelope<Vec<TrashedWeight>>,
+    AnnotationsResponse = Envelope<Vec<AnnotationEntry>>,
+    EventsResponse = Envelope<Vec<EventEntry>>,
+    EventResponse = Envelope<EventEntry>
+)]
+pub struct Envelope<T> {
+    pub data: T,
+    pub meta: Meta,
+}
+
+/// The body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: ErrorDetail,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorDetail {
+    /// A stable, machine readable error code.
+    #[schema(example = "invalid_parameter")]
+    pub code: &'static str,
+    #[schema(example = "start_date must be a date in the YYYY-MM-DD format")]
+    pub message: String,
+    /// The plausibility rules broken by a weight, for a `validation_failed` error.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<ValidationIssue>,
+}
+
+/// A plausibility rule broken by a weight.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ValidationIssue {
+    /// `weight_not_finite`, `weight_too_low`, `weight_too_high`, `date_in_future` or
+    /// `change_too_large`.
+    #[schema(example = "weight_too_low")]
+    pub code: &'static str,
+    /// The input the rule is about: `weight` or `date`.
+    #[schema(example = "weight")]
+    pub field: &'static str,
+    #[schema(example = "19 kg is below the minimum of 30 kg")]
+    pub message: String,
+}
+
+impl From<&ValidationError> for ValidationIssue {
+    fn from(error: &ValidationError) -> Self {
+        ValidationIssue {
+            code: error.code(),
+            field: error.field(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// An error returned by an API handler, sent as an [`ErrorBody`].
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Vec<ValidationIssue>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            code,
+            message: message.into(),
+            details: Vec::new(),
+        }
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+    }
+
+    /// The database cannot be reached, sent with `503 Service Unavailable`: the client can retry
+    /// the request later.
+    pub fn database_unavailable() -> Self {
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "The database is unavailable, retry later",
+        )
+    }
+
+    /// A weight that breaks plausibility rules, sent with `422 Unprocessable Entity` and the
+    /// broken rules as details. The client can store it anyway by setting `force`.
+    pub fn validation_failed(errors: &[ValidationError]) -> Self {
+        ApiError {
+            details: errors.iter().map(ValidationIssue::from).collect(),
+            ..ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation_failed",
+                format!(
+                    "{}; set force to store the weight anyway",
+                    validation::describe(errors)
+                ),
+            )
+        }
+    }
+}
+
+impl From<diesel::result::Error> for ApiError {
+    fn from(err: diesel::result::Error) -> Self {
+        ApiError::internal(err.to_string())
+    }
+}
+
+impl From<ConditionalError> for ApiError {
+    fn from(err: ConditionalError) -> Self {
+        match err {
+            ConditionalError::PreconditionFailed(_) => ApiError::new(
+                StatusCode::PRECONDITION_FAILED,
+                "precondition_failed",
+                "The weight does not match If-Match or If-None-Match: it was changed in the meantime",
+            ),
+            ConditionalError::Implausible(errors) => ApiError::validation_failed(&errors),
+            err @ ConditionalError::InvalidTag { .. } => {
+                ApiError::bad_request("invalid_parameter", err.to_string())
+            }
+            ConditionalError::Database(err) => ApiError::from(err),
+        }
+    }
+}
+
+impl From<AnnotationError> for ApiError {
+    fn from(err: AnnotationError) -> Self {
+        match err {
+            err @ AnnotationError::InvalidTag { .. } => {
+                ApiError::bad_request("invalid_parameter", err.to_string())
+            }
+            AnnotationError::Database(err) => ApiError::from(err),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: ErrorDetail {
+                code: self.code,
+                message: self.message,
+                details: self.details,
+            },
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Runs the database work of a handler on the blocking threads of the runtime, with a connection
+/// of its own, so that the queries do not hold up the other requests. A database that cannot be
+/// reached is answered with [`ApiError::database_unavailable`].
+pub async fn with_connection<T, F>(f: F) -> Result<T, ApiError>
+where
+    F: FnOnce(&mut DbConnection) -> Result<T, ApiError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let database_url = &crate::config::current().database_url;
+        let mut conn = crate::connect(database_url).map_err(|err| {
+            tracing::error!("Failed to connect to the database: {}", err);
+            ApiError::database_unavailable()
+        })?;
+        f(&mut conn)
+    })
+    .await
+    .map_err(|err| ApiError::internal(err.to_string()))?
+}
+
+/// The header in which clients can name who makes a change, for the history.
+pub const ACTOR_HEADER: &str = "x-actor";
+
+/// Extracts the [`Origin`] of a write request: the actor is taken from the `X-Actor` header,
+/// or else is the address of the client.
+pub struct ApiOrigin(pub Origin);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ApiOrigin {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = parts
+            .headers
+            .get(ACTOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        let actor = match header {
+            Some(actor) => actor.to_string(),
+            None => parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map_or(String::from("anonymous"), |ConnectInfo(address)| {
+                    address.ip().to_string()
+                }),
+        };
+        Ok(ApiOrigin(Origin::api(actor)))
+    }
+}
+
+/// JSON cannot represent NaN or infinity, so such values are sent as `null`.
+pub(crate) fn finite(value: f64) -> Option<f64> {
+    value.is_finite().then_some(value)
+}
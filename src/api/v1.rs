@@ -0,0 +1,1108 @@
+//! Version 1 of the JSON API, served under `/api/v1`.
+
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query};
+use axum::handler::Handler;
+use axum::http::{HeaderMap, HeaderName, StatusCode};
+use axum::middleware::from_fn;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::{IntoParams, ToSchema};
+
+use super::conditional::{etag_header, precondition};
+use super::idempotency::idempotent;
+use super::{
+    finite, with_connection, ApiError, ApiOrigin, Envelope, Meta, ValidationIssue, WEIGHT_UNIT,
+};
+use crate::annotations::{annotations_between_dates, normalize_tag, Annotation};
+use crate::db::{read_transaction, write_transaction, DbConnection};
+use crate::events::EventKind;
+use crate::stats::PhaseSummary;
+use crate::validation::ValidationRules;
+use crate::versions;
+use crate::{Event, NewEvent, NewMeasurement, UpsertOutcome, Weight, WeightChange};
+
+pub const VERSION: &str = "v1";
+
+/// The routes of this version, to be nested under `/api/v1`. The handlers that store weights
+/// check them against the [`ValidationRules`] of an `Extension` layer, and accept an
+/// `Idempotency-Key` kept for the [`KeyRetention`](super::idempotency::KeyRetention) of another
+/// one. The writes to a weight honour `If-Match` and `If-None-Match`; the `ETag` of the reads is
+/// set by the [`conditional_get`](super::conditional::conditional_get) middleware, layered by the
+/// caller around these routes.
+pub fn router() -> Router {
+    Router::new()
+        .route(
+            "/weights",
+            get(list_weights).post(store_weight.layer(from_fn(idempotent))),
+        )
+        .route(
+            "/weights/batch",
+            post(store_weights_batch).layer(from_fn(idempotent)),
+        )
+        .route("/weights/:date", get(get_weight).delete(delete_weight))
+        .route("/weights/:date/restore", post(restore_weight))
+        .route("/weights/:date/history", get(weight_history))
+        .route("/annotations", get(list_annotations))
+        .route("/events", get(list_events).post(create_event))
+        .route("/events/:id", delete(delete_event))
+        .route("/trash", get(list_trash))
+        .route("/trash/undo", post(undo_delete))
+        .route("/rolling_average", get(rolling_average))
+}
+
+fn meta(interpolated: bool, window_days: Option<u32>) -> Meta {
+    Meta {
+        api_version: VERSION,
+        unit: WEIGHT_UNIT,
+        interpolated,
+        window_days,
+    }
+}
+
+fn parse_date_param(name: &str, value: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        ApiError::bad_request(
+            "invalid_parameter",
+            format!("{} must be a date in the YYYY-MM-DD format", name),
+        )
+    })
+}
+
+fn parse_interval(start_date: &str, end_date: &str) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let start_date = parse_date_param("start_date", start_date)?;
+    let end_date = parse_date_param("end_date", end_date)?;
+    if start_date > end_date {
+        return Err(ApiError::bad_request(
+            "invalid_parameter",
+            "start_date must not be after end_date",
+        ));
+    }
+    Ok((start_date, end_date))
+}
+
+fn query_error(rejection: QueryRejection) -> ApiError {
+    ApiError::bad_request("invalid_parameter", rejection.body_text())
+}
+
+fn body_error(rejection: JsonRejection) -> ApiError {
+    ApiError::bad_request("invalid_body", rejection.body_text())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WeightsQuery {
+    /// The first day of the interval, as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-01-01")]
+    pub start_date: String,
+    /// The last day of the interval (inclusive), as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-02-28")]
+    pub end_date: String,
+    /// Fill the days without a measurement with linearly interpolated weights (default false).
+    pub interpolate: Option<bool>,
+    /// Only list the weights with this tag. Cannot be combined with `interpolate`.
+    #[param(example = "holiday")]
+    pub tag: Option<String>,
+}
+
+/// A weight of one day.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WeightEntry {
+    #[schema(value_type = String, format = Date, example = "2023-01-20")]
+    pub date: NaiveDate,
+    /// `null` when the stored value is not a finite number.
+    #[schema(example = 81.4)]
+    pub weight_kg: Option<f64>,
+    /// Whether the weight was interpolated rather than measured.
+    pub interpolated: bool,
+    #[schema(example = "after holiday")]
+    pub note: Option<String>,
+    #[schema(example = json!(["holiday"]))]
+    pub tags: Vec<String>,
+}
+
+impl WeightEntry {
+    fn new(weight: Weight, interpolated: bool, annotation: Option<Annotation>) -> Self {
+        let annotation = annotation.unwrap_or_default();
+        WeightEntry {
+            date: weight.measurement_date,
+            weight_kg: finite(weight.weight_value),
+            interpolated,
+            note: annotation.note,
+            tags: annotation.tags,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/weights",
+    tag = "v1",
+    params(WeightsQuery),
+    responses(
+        (status = 200, description = "The weights of the interval, oldest first", body = WeightsResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorBody)
+    )
+)]
+pub async fn list_weights(
+    query: Result<Query<WeightsQuery>, QueryRejection>,
+) -> Result<Json<Envelope<Vec<WeightEntry>>>, ApiError> {
+    let Query(query) = query.map_err(query_error)?;
+    let (start_date, end_date) = parse_interval(&query.start_date, &query.end_date)?;
+    let interpolate = query.interpolate.unwrap_or(false);
+
+    if query.tag.is_some() && interpolate {
+        return Err(ApiError::bad_request(
+            "invalid_parameter",
+            "tag cannot be combined with interpolate",
+        ));
+    }
+
+    let (weights, mut annotations) = with_connection(move |conn| {
+        let weights: Vec<(Weight, bool)> = match &query.tag {
+            Some(tag) => crate::weights_between_dates_with_tag(conn, start_date, end_date, tag)?
+                .into_iter()
+                .map(|weight| (weight, false))
+                .collect(),
+            None if interpolate => {
+                crate::weights_between_dates_with_interpolation(conn, start_date, end_date)?
+            }
+            None => crate::weights_between_dates(conn, start_date, end_date)?
+                .into_iter()
+                .map(|weight| (weight, false))
+                .collect(),
+        };
+        let annotations = annotations_between_dates(conn, start_date, end_date)?;
+        Ok((weights, annotations))
+    })
+    .await?;
+
+    let interpolated = weights.iter().any(|(_, interpolated)| *interpolated);
+    let data = weights
+        .into_iter()
+        .map(|(weight, interpolated)| {
+            let annotation = match interpolated {
+                true => None,
+                false => annotations.remove(&weight.measurement_date),
+            };
+            WeightEntry::new(weight, interpolated, annotation)
+        })
+        .collect();
+
+    Ok(Json(Envelope {
+        data,
+        meta: meta(interpolated, None),
+    }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewWeightPayload {
+    /// The day of the measurement, as YYYY-MM-DD.
+    #[schema(format = Date, example = "2023-01-20")]
+    pub date: String,
+    #[schema(example = 81.4)]
+    pub weight_kg: f64,
+    /// Replaces the note of the day when present; an empty note removes it.
+    #[schema(example = "after holiday")]
+    pub note: Option<String>,
+    /// Replaces the tags of the day when present. Tags are stored in lower case.
+    #[schema(example = json!(["holiday"]))]
+    pub tags: Option<Vec<String>>,
+    /// Store the weight even if it breaks the plausibility rules (default false).
+    pub force: Option<bool>,
+}
+
+impl NewWeightPayload {
+    /// Checks the payload, returning the measurement to store.
+    fn validate(&self) -> Result<NewMeasurement, ApiError> {
+        let date = parse_date_param("date", &self.date)?;
+        if !self.weight_kg.is_finite() || self.weight_kg <= 0.0 {
+            return Err(ApiError::bad_request(
+                "invalid_parameter",
+                "weight_kg must be a positive number",
+            ));
+        }
+        if self
+            .note
+            .as_ref()
+            .is_some_and(|note| note.chars().count() > MAX_NOTE_LENGTH)
+        {
+            return Err(ApiError::bad_request(
+                "invalid_parameter",
+                format!(
+                    "note must not be longer than {} characters",
+                    MAX_NOTE_LENGTH
+                ),
+            ));
+        }
+        let tags = self
+            .tags
+            .as_ref()
+            .map(|tags| tags.iter().map(|tag| normalize_tag(tag)).collect())
+            .transpose()
+            .map_err(|message| ApiError::bad_request("invalid_parameter", message))?;
+
+        Ok(NewMeasurement {
+            measurement_date: date,
+            weight_value: self.weight_kg,
+            note: self.note.clone(),
+            tags,
+        })
+    }
+}
+
+/// The weight stored for a day.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StoredWeight {
+    #[schema(value_type = String, format = Date, example = "2023-01-20")]
+    pub date: NaiveDate,
+    #[schema(example = 81.4)]
+    pub weight_kg: f64,
+    /// The number of rows inserted or updated.
+    #[schema(example = 1)]
+    pub rows: usize,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/weights",
+    tag = "v1",
+    request_body = NewWeightPayload,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "A key chosen by the client, e.g. a UUID, to retry the request safely: a retry with the same key gets the response of the first request"),
+        ("If-Match" = Option<String>, Header, description = "Only store the weight if the weight of the day is still at this ETag, or exists at all for `*`"),
+        ("If-None-Match" = Option<String>, Header, description = "`*` to only store the weight if the day has none yet")
+    ),
+    responses(
+        (status = 201, description = "The weight was stored, replacing any weight already recorded for the date; the ETag header holds its new version", body = StoredWeightResponse),
+        (status = 400, description = "Invalid payload, Idempotency-Key or precondition", body = ErrorBody),
+        (status = 409, description = "A request with the same Idempotency-Key is still being handled", body = ErrorBody),
+        (status = 412, description = "The weight of the day does not match If-Match or If-None-Match", body = ErrorBody),
+        (status = 422, description = "The weight breaks plausibility rules, listed in the details (set force to store it anyway), or the Idempotency-Key was used for a different request", body = ErrorBody)
+    )
+)]
+pub async fn store_weight(
+    ApiOrigin(origin): ApiOrigin,
+    Extension(rules): Extension<ValidationRules>,
+    headers: HeaderMap,
+    payload: Result<Json<NewWeightPayload>, JsonRejection>,
+) -> Result<
+    (
+        StatusCode,
+        [(HeaderName, String); 1],
+        Json<Envelope<StoredWeight>>,
+    ),
+    ApiError,
+> {
+    let Json(payload) = payload.map_err(body_error)?;
+    let measurement = payload.validate()?;
+    let condition = precondition(&headers)?;
+    let date = measurement.measurement_date;
+
+    let rules = (!payload.force.unwrap_or(false)).then_some(rules);
+    let (outcome, version) = with_connection(move |conn| {
+        Ok(versions::upsert_measurement_if(
+            conn,
+            &measurement,
+            &origin,
+            condition.as_ref(),
+            rules.as_ref(),
+        )?)
+    })
+    .await?;
+    let rows = outcome.rows();
+
+    Ok((
+        StatusCode::CREATED,
+        etag_header(version),
+        Json(Envelope {
+            data: StoredWeight {
+                date,
+                weight_kg: payload.weight_kg,
+                rows,
+            },
+            meta: meta(false, None),
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RollingAverageQuery {
+    /// The first day of the interval, as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-01-01")]
+    pub start_date: String,
+    /// The last day of the interval (inclusive), as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-02-28")]
+    pub end_date: String,
+    /// The size of the rolling window, in days.
+    #[param(minimum = 1, maximum = 7, example = 7)]
+    pub days: u32,
+}
+
+/// The rolling average for one day.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RollingAveragePoint {
+    #[schema(value_type = String, format = Date, example = "2023-01-20")]
+    pub date: NaiveDate,
+    /// The average over the window ending on `date`; `null` when there is no finite average.
+    #[schema(example = 81.25)]
+    pub average_kg: Option<f64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rolling_average",
+    tag = "v1",
+    params(RollingAverageQuery),
+    responses(
+        (status = 200, description = "The rolling average for every day of the interval that has data", body = RollingAverageResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorBody)
+    )
+)]
+pub async fn rolling_average(
+    query: Result<Query<RollingAverageQuery>, QueryRejection>,
+) -> Result<Json<Envelope<Vec<RollingAveragePoint>>>, ApiError> {
+    let Query(query) = query.map_err(query_error)?;
+    let (start_date, end_date) = parse_interval(&query.start_date, &query.end_date)?;
+    if !(1..=7).contains(&query.days) {
+        return Err(ApiError::bad_request(
+            "invalid_parameter",
+            "days must be between 1 and 7",
+        ));
+    }
+
+    let days = query.days;
+    let (averages, interpolated) = with_connection(move |conn| {
+        let averages = crate::rolling_average_between_dates(conn, start_date, end_date, days)?;
+        // The averages are computed over the interpolated weights, starting with the window of
+        // the first day.
+        let window_start = start_date - Duration::days(days as i64 - 1);
+        let interpolated =
+            crate::weights_between_dates_with_interpolation(conn, window_start, end_date)?
+                .iter()
+                .any(|(_, interpolated)| *interpolated);
+        Ok((averages, interpolated))
+    })
+    .await?;
+
+    let data = averages
+        .into_iter()
+        .map(|(date, average)| RollingAveragePoint {
+            date,
+            average_kg: finite(average),
+        })
+        .collect();
+
+    Ok(Json(Envelope {
+        data,
+        meta: meta(interpolated, Some(query.days)),
+    }))
+}
+
+/// The longest note accepted, in characters.
+pub const MAX_NOTE_LENGTH: usize = 1000;
+
+/// The largest number of weights accepted by one batch request.
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// What happened to one entry of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchItemStatus {
+    Inserted,
+    Updated,
+    Unchanged,
+    Rejected,
+}
+
+impl From<UpsertOutcome> for BatchItemStatus {
+    fn from(outcome: UpsertOutcome) -> Self {
+        match outcome {
+            UpsertOutcome::Inserted => BatchItemStatus::Inserted,
+            UpsertOutcome::Updated => BatchItemStatus::Updated,
+            UpsertOutcome::Unchanged => BatchItemStatus::Unchanged,
+        }
+    }
+}
+
+/// The result for one entry of a batch, in the order of the request.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchItemResult {
+    /// The position of the entry in the request.
+    pub index: usize,
+    /// The date as sent in the request.
+    #[schema(example = "2023-01-20")]
+    pub date: String,
+    pub status: BatchItemStatus,
+    /// Why the entry was rejected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The plausibility rules broken by the entry, when it was rejected for them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<ValidationIssue>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct BatchResult {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub rejected: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/weights/batch",
+    tag = "v1",
+    request_body = Vec<NewWeightPayload>,
+    params(("Idempotency-Key" = Option<String>, Header, description = "A key chosen by the client, e.g. a UUID, to retry the request safely: a retry with the same key gets the response of the first request")),
+    responses(
+        (status = 200, description = "The valid entries were stored in one transaction; the invalid ones were rejected", body = BatchResponse),
+        (status = 400, description = "The body is not an array of weights, or it is too large, or the Idempotency-Key is invalid", body = ErrorBody),
+        (status = 409, description = "A request with the same Idempotency-Key is still being handled", body = ErrorBody),
+        (status = 422, description = "The Idempotency-Key was used for a different request", body = ErrorBody)
+    )
+)]
+pub async fn store_weights_batch(
+    ApiOrigin(origin): ApiOrigin,
+    Extension(rules): Extension<ValidationRules>,
+    payload: Result<Json<Vec<NewWeightPayload>>, JsonRejection>,
+) -> Result<Json<Envelope<BatchResult>>, ApiError> {
+    let Json(payload) = payload.map_err(body_error)?;
+    if payload.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::bad_request(
+            "batch_too_large",
+            format!("a batch can contain at most {} weights", MAX_BATCH_SIZE),
+        ));
+    }
+
+    let (payload, stored) = with_connection(move |conn| {
+        // Every entry is validated and written in turn in one transaction, so that the plausibility
+        // rules see the weights already stored and the entries of the batch accepted before it,
+        // and no other writer can change them in between. A date that appears more than once is
+        // ambiguous, so its later entries are rejected.
+        write_transaction(conn, |conn| {
+            let mut seen_dates = HashSet::new();
+            let mut stored: Vec<Result<UpsertOutcome, ApiError>> =
+                Vec::with_capacity(payload.len());
+            for entry in &payload {
+                let measurement = entry.validate().and_then(|measurement| {
+                    if !seen_dates.insert(measurement.measurement_date) {
+                        return Err(ApiError::bad_request(
+                            "invalid_parameter",
+                            "duplicate date in the batch",
+                        ));
+                    }
+                    Ok(measurement)
+                });
+                let measurement = match measurement {
+                    Ok(measurement) if !entry.force.unwrap_or(false) => {
+                        let errors = rules.check(
+                            conn,
+                            measurement.weight_value,
+                            measurement.measurement_date,
+                        )?;
+                        if errors.is_empty() {
+                            Ok(measurement)
+                        } else {
+                            Err(ApiError::validation_failed(&errors))
+                        }
+                    }
+                    measurement => measurement,
+                };
+                let outcome = match measurement {
+                    Ok(measurement) => {
+                        Ok(crate::store_in_transaction(conn, &measurement, &origin)?)
+                    }
+                    Err(err) => Err(err),
+                };
+                stored.push(outcome);
+            }
+            Ok((payload, stored))
+        })
+    })
+    .await?;
+
+    let mut result = BatchResult::default();
+    for (index, (stored, entry)) in stored.into_iter().zip(payload).enumerate() {
+        let (status, error, details) = match stored {
+            Ok(outcome) => (BatchItemStatus::from(outcome), None, Vec::new()),
+            Err(err) => (BatchItemStatus::Rejected, Some(err.message), err.details),
+        };
+        match status {
+            BatchItemStatus::Inserted => result.inserted += 1,
+            BatchItemStatus::Updated => result.updated += 1,
+            BatchItemStatus::Unchanged => result.unchanged += 1,
+            BatchItemStatus::Rejected => result.rejected += 1,
+        }
+        result.results.push(BatchItemResult {
+            index,
+            date: entry.date,
+            status,
+            error,
+            details,
+        });
+    }
+
+    Ok(Json(Envelope {
+        data: result,
+        meta: meta(false, None),
+    }))
+}
+
+/// A change of the weight of a day.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryEntry {
+    /// The id of the change, used to revert to it.
+    pub id: i32,
+    #[schema(value_type = String, format = Date, example = "2023-01-20")]
+    pub date: NaiveDate,
+    /// `insert`, `update`, `delete`, `restore` or `purge`.
+    #[schema(example = "update")]
+    pub action: String,
+    /// The weight before the change; `null` for an insert or a restore.
+    #[schema(example = 81.4)]
+    pub old_value_kg: Option<f64>,
+    /// The weight after the change; `null` for a delete or a purge.
+    #[schema(example = 80.4)]
+    pub new_value_kg: Option<f64>,
+    #[schema(value_type = String, format = DateTime)]
+    pub changed_at: DateTime<Utc>,
+    /// The tool that made the change: `api`, `cli:<command>` or `import:<file name>`.
+    #[schema(example = "api")]
+    pub source: String,
+    /// Who made the change: the `X-Actor` header or the client address for the API, the local
+    /// user for the command line tools.
+    #[schema(example = "127.0.0.1")]
+    pub actor: String,
+}
+
+impl From<WeightChange> for HistoryEntry {
+    fn from(change: WeightChange) -> Self {
+        HistoryEntry {
+            id: change.id,
+            date: change.measurement_date,
+            action: change.action,
+            old_value_kg: change.old_value.and_then(finite),
+            new_value_kg: change.new_value.and_then(finite),
+            changed_at: change.changed_at.and_utc(),
+            source: change.source,
+            actor: change.actor,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/weights/{date}/history",
+    tag = "v1",
+    params(("date" = String, Path, format = Date, description = "The day, as YYYY-MM-DD")),
+    responses(
+        (status = 200, description = "Every insert, update and delete of the weight of the day, oldest first", body = HistoryResponse),
+        (status = 400, description = "Invalid date", body = ErrorBody)
+    )
+)]
+pub async fn weight_history(
+    Path(date): Path<String>,
+) -> Result<Json<Envelope<Vec<HistoryEntry>>>, ApiError> {
+    let date = parse_date_param("date", &date)?;
+
+    let data = with_connection(move |conn| Ok(crate::history::history_for_date(conn, date)?))
+        .await?
+        .into_iter()
+        .map(HistoryEntry::from)
+        .collect();
+
+    Ok(Json(Envelope {
+        data,
+        meta: meta(false, None),
+    }))
+}
+
+/// The entry of a weight that was just restored, with its note and tags.
+fn restored_entry(conn: &mut DbConnection, weight: Weight) -> Result<WeightEntry, ApiError> {
+    let date = weight.measurement_date;
+    let mut annotations = annotations_between_dates(conn, date, date)?;
+    Ok(WeightEntry::new(weight, false, annotations.remove(&date)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/weights/{date}",
+    tag = "v1",
+    params(
+        ("date" = String, Path, format = Date, description = "The day, as YYYY-MM-DD"),
+        ("If-None-Match" = Option<String>, Header, description = "The ETag of the weight the client already has")
+    ),
+    responses(
+        (status = 200, description = "The weight of the day; the ETag header holds its version, to send in If-Match when changing it", body = WeightResponse),
+        (status = 304, description = "The weight is still at the ETag sent in If-None-Match"),
+        (status = 400, description = "Invalid date", body = ErrorBody),
+        (status = 404, description = "There is no weight for the day", body = ErrorBody)
+    )
+)]
+pub async fn get_weight(
+    Path(date): Path<String>,
+) -> Result<([(HeaderName, String); 1], Json<Envelope<WeightEntry>>), ApiError> {
+    let date = parse_date_param("date", &date)?;
+
+    let not_found = move || {
+        ApiError::not_found(format!(
+            "There is no weight for {}",
+            date.format("%Y-%m-%d")
+        ))
+    };
+    // One transaction, so that the ETag is the version of the weight sent with it.
+    let (version, weight, mut annotations) = with_connection(move |conn| {
+        read_transaction(conn, |conn| {
+            let version = versions::current_version(conn, date)?.ok_or_else(not_found)?;
+            let weight = crate::weights_between_dates(conn, date, date)?
+                .pop()
+                .ok_or_else(not_found)?;
+            let annotations = annotations_between_dates(conn, date, date)?;
+            Ok((version, weight, annotations))
+        })
+    })
+    .await?;
+
+    Ok((
+        etag_header(version),
+        Json(Envelope {
+            data: WeightEntry::new(weight, false, annotations.remove(&date)),
+            meta: meta(false, None),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/weights/{date}",
+    tag = "v1",
+    params(
+        ("date" = String, Path, format = Date, description = "The day, as YYYY-MM-DD"),
+        ("If-Match" = Option<String>, Header, description = "Only delete the weight if it is still at this ETag")
+    ),
+    responses(
+        (status = 204, description = "The weight was moved to the trash"),
+        (status = 400, description = "Invalid date or precondition", body = ErrorBody),
+        (status = 404, description = "There is no weight for the day", body = ErrorBody),
+        (status = 412, description = "The weight of the day does not match If-Match", body = ErrorBody)
+    )
+)]
+pub async fn delete_weight(
+    ApiOrigin(origin): ApiOrigin,
+    Path(date): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    let date = parse_date_param("date", &date)?;
+    let condition = precondition(&headers)?;
+
+    let deleted = with_connection(move |conn| {
+        Ok(versions::delete_weight_if(
+            conn,
+            date,
+            &origin,
+            condition.as_ref(),
+        )?)
+    })
+    .await?;
+    match deleted {
+        0 => Err(ApiError::not_found(format!(
+            "There is no weight for {}",
+            date.format("%Y-%m-%d")
+        ))),
+        _ => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/weights/{date}/restore",
+    tag = "v1",
+    params(("date" = String, Path, format = Date, description = "The day, as YYYY-MM-DD")),
+    responses(
+        (status = 200, description = "The weight was taken out of the trash", body = WeightResponse),
+        (status = 400, description = "Invalid date", body = ErrorBody),
+        (status = 404, description = "There is no weight for the day in the trash", body = ErrorBody)
+    )
+)]
+pub async fn restore_weight(
+    ApiOrigin(origin): ApiOrigin,
+    Path(date): Path<String>,
+) -> Result<Json<Envelope<WeightEntry>>, ApiError> {
+    let date = parse_date_param("date", &date)?;
+
+    let data = with_connection(move |conn| {
+        let weight = crate::trash::restore_weight(conn, date, &origin)?.ok_or_else(|| {
+            ApiError::not_found(format!(
+                "There is no weight for {} in the trash",
+                date.format("%Y-%m-%d")
+            ))
+        })?;
+        restored_entry(conn, weight)
+    })
+    .await?;
+
+    Ok(Json(Envelope {
+        data,
+        meta: meta(false, None),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/trash/undo",
+    tag = "v1",
+    responses(
+        (status = 200, description = "The most recently deleted weight was restored", body = WeightResponse),
+        (status = 404, description = "The trash is empty", body = ErrorBody)
+    )
+)]
+pub async fn undo_delete(
+    ApiOrigin(origin): ApiOrigin,
+) -> Result<Json<Envelope<WeightEntry>>, ApiError> {
+    let data = with_connection(move |conn| {
+        let weight = crate::trash::restore_last_deleted(conn, &origin)?
+            .ok_or_else(|| ApiError::not_found("The trash is empty"))?;
+        restored_entry(conn, weight)
+    })
+    .await?;
+
+    Ok(Json(Envelope {
+        data,
+        meta: meta(false, None),
+    }))
+}
+
+/// A weight in the trash.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrashedWeight {
+    #[schema(value_type = String, format = Date, example = "2023-01-20")]
+    pub date: NaiveDate,
+    #[schema(example = 81.4)]
+    pub weight_kg: Option<f64>,
+    #[schema(value_type = String, format = DateTime)]
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/trash",
+    tag = "v1",
+    responses(
+        (status = 200, description = "The weights in the trash, most recently deleted first. They are purged after the retention period", body = TrashResponse)
+    )
+)]
+pub async fn list_trash() -> Result<Json<Envelope<Vec<TrashedWeight>>>, ApiError> {
+    let data = with_connection(|conn| Ok(crate::trash::deleted_weights(conn)?))
+        .await?
+        .into_iter()
+        .map(|(weight, deleted_at)| TrashedWeight {
+            date: weight.measurement_date,
+            weight_kg: finite(weight.weight_value),
+            deleted_at: deleted_at.and_utc(),
+        })
+        .collect();
+
+    Ok(Json(Envelope {
+        data,
+        meta: meta(false, None),
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnnotationsQuery {
+    /// The first day of the interval, as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-01-01")]
+    pub start_date: String,
+    /// The last day of the interval (inclusive), as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-02-28")]
+    pub end_date: String,
+}
+
+/// The note and tags of a day.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnnotationEntry {
+    #[schema(value_type = String, format = Date, example = "2023-01-20")]
+    pub date: NaiveDate,
+    #[schema(example = "after holiday")]
+    pub note: Option<String>,
+    #[schema(example = json!(["holiday"]))]
+    pub tags: Vec<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/annotations",
+    tag = "v1",
+    params(AnnotationsQuery),
+    responses(
+        (status = 200, description = "The days of the interval with a note or tags, oldest first, e.g. to mark them on a chart", body = AnnotationsResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorBody)
+    )
+)]
+pub async fn list_annotations(
+    query: Result<Query<AnnotationsQuery>, QueryRejection>,
+) -> Result<Json<Envelope<Vec<AnnotationEntry>>>, ApiError> {
+    let Query(query) = query.map_err(query_error)?;
+    let (start_date, end_date) = parse_interval(&query.start_date, &query.end_date)?;
+
+    let data =
+        with_connection(move |conn| Ok(annotations_between_dates(conn, start_date, end_date)?))
+            .await?
+            .into_iter()
+            .map(|(date, annotation)| AnnotationEntry {
+                date,
+                note: annotation.note,
+                tags: annotation.tags,
+            })
+            .collect();
+
+    Ok(Json(Envelope {
+        data,
+        meta: meta(false, None),
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventsQuery {
+    /// Only list the events overlapping the interval from this day, as YYYY-MM-DD. Requires
+    /// `end_date`.
+    #[param(format = Date, example = "2023-01-01")]
+    pub start_date: Option<String>,
+    /// The last day of the interval (inclusive), as YYYY-MM-DD. Requires `start_date`.
+    #[param(format = Date, example = "2023-02-28")]
+    pub end_date: Option<String>,
+}
+
+/// The weights measured during an event.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PhaseSummaryEntry {
+    /// The length of the event in days, up to today for an event still going on.
+    #[schema(example = 28)]
+    pub duration_days: i64,
+    /// The number of weights measured during the event.
+    #[schema(example = 25)]
+    pub measurements: usize,
+    /// The first weight measured during the event.
+    #[schema(example = 84.2)]
+    pub start_weight_kg: Option<f64>,
+    /// The last weight measured during the event.
+    #[schema(example = 81.4)]
+    pub end_weight_kg: Option<f64>,
+    #[schema(example = -2.8)]
+    pub change_kg: Option<f64>,
+    /// The trend in kg per week; `null` with a single measurement.
+    #[schema(example = -0.7)]
+    pub rate_kg_per_week: Option<f64>,
+}
+
+impl From<PhaseSummary> for PhaseSummaryEntry {
+    fn from(summary: PhaseSummary) -> Self {
+        PhaseSummaryEntry {
+            duration_days: summary.duration_days,
+            measurements: summary.count,
+            start_weight_kg: finite(summary.start_weight),
+            end_weight_kg: finite(summary.end_weight),
+            change_kg: finite(summary.change()),
+            rate_kg_per_week: summary.rate_per_week.and_then(finite),
+        }
+    }
+}
+
+/// An event of the timeline: a period such as a diet phase, or a one-day marker.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EventEntry {
+    /// The id of the event, used to delete it.
+    pub id: i32,
+    /// `diet`, `bulk`, `cut`, `injury`, `medication` or `other`.
+    #[schema(example = "cut")]
+    pub kind: String,
+    #[schema(example = "Spring cut")]
+    pub title: String,
+    #[schema(value_type = String, format = Date, example = "2023-01-02")]
+    pub start_date: NaiveDate,
+    /// The last day of the event (inclusive); `null` while it is still going on.
+    #[schema(value_type = Option<String>, format = Date, example = "2023-01-29")]
+    pub end_date: Option<NaiveDate>,
+    pub note: Option<String>,
+    /// `null` when no weight was measured during the event.
+    pub summary: Option<PhaseSummaryEntry>,
+}
+
+impl EventEntry {
+    fn new(event: Event, summary: Option<PhaseSummary>) -> Self {
+        EventEntry {
+            id: event.id,
+            kind: event.kind,
+            title: event.title,
+            start_date: event.start_date,
+            end_date: event.end_date,
+            note: event.note,
+            summary: summary.map(PhaseSummaryEntry::from),
+        }
+    }
+}
+
+/// The events with the summary of the weights measured during each of them.
+fn event_entries(conn: &mut DbConnection, events: Vec<Event>) -> Result<Vec<EventEntry>, ApiError> {
+    let today = Local::now().date_naive();
+    let summaries = crate::events::phase_summaries(conn, &events, today)?;
+    Ok(events
+        .into_iter()
+        .zip(summaries)
+        .map(|(event, summary)| EventEntry::new(event, summary))
+        .collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "v1",
+    params(EventsQuery),
+    responses(
+        (status = 200, description = "The events, by start date, with a summary of the weights measured during each of them", body = EventsResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorBody)
+    )
+)]
+pub async fn list_events(
+    query: Result<Query<EventsQuery>, QueryRejection>,
+) -> Result<Json<Envelope<Vec<EventEntry>>>, ApiError> {
+    let Query(query) = query.map_err(query_error)?;
+
+    let interval = match (&query.start_date, &query.end_date) {
+        (None, None) => None,
+        (Some(start_date), Some(end_date)) => Some(parse_interval(start_date, end_date)?),
+        _ => {
+            return Err(ApiError::bad_request(
+                "invalid_parameter",
+                "start_date and end_date must be given together",
+            ))
+        }
+    };
+
+    let data = with_connection(move |conn| {
+        let events = match interval {
+            None => crate::events::all_events(conn)?,
+            Some((start_date, end_date)) => {
+                crate::events::events_between_dates(conn, start_date, end_date)?
+            }
+        };
+        event_entries(conn, events)
+    })
+    .await?;
+
+    Ok(Json(Envelope {
+        data,
+        meta: meta(false, None),
+    }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewEventPayload {
+    /// `diet`, `bulk`, `cut`, `injury`, `medication` or `other`.
+    #[schema(example = "cut")]
+    pub kind: String,
+    #[schema(example = "Spring cut")]
+    pub title: String,
+    /// The first day of the event, as YYYY-MM-DD.
+    #[schema(format = Date, example = "2023-01-02")]
+    pub start_date: String,
+    /// The last day of the event (inclusive), as YYYY-MM-DD. Leave it out for an event that is
+    /// still going on, or set it to `start_date` for a one-day marker.
+    #[schema(format = Date, example = "2023-01-29")]
+    pub end_date: Option<String>,
+    pub note: Option<String>,
+}
+
+impl NewEventPayload {
+    /// Checks the payload, returning the event to store.
+    fn validate(&self) -> Result<NewEvent, ApiError> {
+        let invalid = |message| ApiError::bad_request("invalid_parameter", message);
+
+        let kind = self.kind.parse::<EventKind>().map_err(invalid)?;
+        let start_date = parse_date_param("start_date", &self.start_date)?;
+        let end_date = self
+            .end_date
+            .as_deref()
+            .map(|end_date| parse_date_param("end_date", end_date))
+            .transpose()?;
+        if self
+            .note
+            .as_ref()
+            .is_some_and(|note| note.chars().count() > MAX_NOTE_LENGTH)
+        {
+            return Err(invalid(format!(
+                "note must not be longer than {} characters",
+                MAX_NOTE_LENGTH
+            )));
+        }
+
+        let event = NewEvent {
+            note: self
+                .note
+                .as_deref()
+                .map(str::trim)
+                .filter(|note| !note.is_empty())
+                .map(String::from),
+            ..NewEvent::new(kind, &self.title, start_date, end_date)
+        };
+        event.validate().map_err(invalid)?;
+        Ok(event)
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/events",
+    tag = "v1",
+    request_body = NewEventPayload,
+    responses(
+        (status = 201, description = "The event was stored", body = EventResponse),
+        (status = 400, description = "Invalid payload", body = ErrorBody)
+    )
+)]
+pub async fn create_event(
+    payload: Result<Json<NewEventPayload>, JsonRejection>,
+) -> Result<(StatusCode, Json<Envelope<EventEntry>>), ApiError> {
+    let Json(payload) = payload.map_err(body_error)?;
+    let event = payload.validate()?;
+
+    let entry = with_connection(move |conn| {
+        let event = crate::events::add_event(conn, &event)?;
+        Ok(event_entries(conn, vec![event])?.remove(0))
+    })
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Envelope {
+            data: entry,
+            meta: meta(false, None),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/events/{id}",
+    tag = "v1",
+    params(("id" = i32, Path, description = "The id of the event")),
+    responses(
+        (status = 204, description = "The event was deleted"),
+        (status = 404, description = "There is no event with this id", body = ErrorBody)
+    )
+)]
+pub async fn delete_event(Path(id): Path<i32>) -> Result<StatusCode, ApiError> {
+    match with_connection(move |conn| Ok(crate::events::delete_event(conn, id)?)).await? {
+        0 => Err(ApiError::not_found(format!("There is no event {}", id))),
+        _ => Ok(StatusCode::NO_CONTENT),
+    }
+}
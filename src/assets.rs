@@ -0,0 +1,318 @@
+//! The pages, styles and images of the frontend, embedded in the binaries at build time so that
+//! the server works from any directory.
+//!
+//! Every file is served with an `ETag` from the SHA-256 of its content, and compressed once at
+//! startup with brotli and gzip for the clients that accept them. The files other than the pages
+//! are also served under a name containing their hash, such as `style.0123abcd.css`, which the
+//! pages link to: those can be cached for good, as a change of the file changes its name, while
+//! the pages themselves are revalidated on every visit.
+//!
+//! For the development of the frontend, the files can be served from a directory instead, see
+//! [`ServerConfig::static_dir`](crate::config::ServerConfig::static_dir).
+
+use axum::body::{Bytes, Full};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::middleware::map_response;
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use rust_embed::RustEmbed;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use tower_http::services::ServeDir;
+
+use crate::api::conditional::none_match_matches;
+
+#[derive(RustEmbed)]
+#[folder = "static/"]
+struct StaticFiles;
+
+/// How long the browsers keep the files with a hash in their name.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// The pages and the files without a hash in their name are revalidated with their `ETag`.
+const REVALIDATE: &str = "no-cache";
+
+/// How many characters of the hash go in the hashed names.
+const HASH_LENGTH: usize = 8;
+
+/// A compressed copy is kept only when it saves at least this fraction of the size.
+const MIN_COMPRESSION_SAVING: f64 = 0.1;
+
+/// A file ready to be served.
+#[derive(Debug)]
+pub struct Asset {
+    pub content_type: String,
+    /// The SHA-256 of the content, in hexadecimal.
+    pub hash: String,
+    pub content: Bytes,
+    pub gzip: Option<Bytes>,
+    pub brotli: Option<Bytes>,
+}
+
+/// The encodings of a response body, from the most to the least preferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Brotli,
+    Gzip,
+    Identity,
+}
+
+impl Encoding {
+    fn token(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+            Encoding::Identity => "identity",
+        }
+    }
+}
+
+impl Asset {
+    fn new(content_type: &str, hash: String, content: Vec<u8>) -> Self {
+        let worth_it = |compressed: Vec<u8>| {
+            let saving = 1.0 - compressed.len() as f64 / content.len().max(1) as f64;
+            (saving >= MIN_COMPRESSION_SAVING).then(|| Bytes::from(compressed))
+        };
+        let gzip = worth_it(gzip(&content));
+        let brotli = worth_it(brotli(&content));
+        Asset {
+            content_type: content_type.to_owned(),
+            hash,
+            content: Bytes::from(content),
+            gzip,
+            brotli,
+        }
+    }
+
+    /// The body in the preferred encoding accepted by the client.
+    fn encoded(&self, accepted: &[Encoding]) -> (Encoding, Bytes) {
+        for encoding in accepted {
+            match encoding {
+                Encoding::Brotli if self.brotli.is_some() => {
+                    return (Encoding::Brotli, self.brotli.clone().unwrap())
+                }
+                Encoding::Gzip if self.gzip.is_some() => {
+                    return (Encoding::Gzip, self.gzip.clone().unwrap())
+                }
+                _ => {}
+            }
+        }
+        (Encoding::Identity, self.content.clone())
+    }
+
+    /// The `ETag` of the body in an encoding: each encoding is a different representation.
+    fn etag(&self, encoding: Encoding) -> String {
+        match encoding {
+            Encoding::Identity => format!("\"{}\"", self.hash),
+            encoding => format!("\"{}-{}\"", self.hash, encoding.token()),
+        }
+    }
+}
+
+fn gzip(content: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder
+        .write_all(content)
+        .and_then(|()| encoder.finish())
+        .expect("Compressing in memory cannot fail")
+}
+
+fn brotli(content: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    brotli::BrotliCompress(
+        &mut &content[..],
+        &mut compressed,
+        &brotli::enc::BrotliEncoderParams::default(),
+    )
+    .expect("Compressing in memory cannot fail");
+    compressed
+}
+
+/// The name of a file with a hash in it: `style.css` becomes `style.0123abcd.css`.
+fn hashed_name(path: &str, hash: &str) -> String {
+    let hash = &hash[..HASH_LENGTH];
+    let file_start = path.rfind('/').map_or(0, |slash| slash + 1);
+    match path[file_start..].rfind('.') {
+        Some(dot) => format!(
+            "{}.{}{}",
+            &path[..file_start + dot],
+            hash,
+            &path[file_start + dot..]
+        ),
+        None => format!("{}.{}", path, hash),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn is_page(path: &str) -> bool {
+    path.ends_with(".html")
+}
+
+/// The embedded files, by path, with their `Cache-Control`.
+#[derive(Debug, Default)]
+pub struct Assets {
+    by_path: HashMap<String, (Arc<Asset>, &'static str)>,
+}
+
+impl Assets {
+    /// Prepares the embedded files: hashes, compressed copies and links of the pages to the hashed
+    /// names.
+    pub fn embedded() -> Self {
+        let files: Vec<(String, String, Vec<u8>, String)> = StaticFiles::iter()
+            .filter_map(|path| {
+                let file = StaticFiles::get(&path)?;
+                let hash = hex(&file.metadata.sha256_hash());
+                let content_type = file.metadata.mimetype().to_owned();
+                Some((
+                    path.into_owned(),
+                    content_type,
+                    file.data.into_owned(),
+                    hash,
+                ))
+            })
+            .collect();
+
+        // The links of the pages to the other files, e.g. href="./style.css".
+        let renames: Vec<(String, String)> = files
+            .iter()
+            .filter(|(path, ..)| !is_page(path))
+            .map(|(path, _, _, hash)| (path.clone(), hashed_name(path, hash)))
+            .collect();
+
+        let mut assets = Assets::default();
+        for (path, content_type, content, hash) in files {
+            if is_page(&path) {
+                let mut page = String::from_utf8_lossy(&content).into_owned();
+                for (name, hashed) in &renames {
+                    for quote in ['"', '\''] {
+                        for prefix in ["", "./"] {
+                            page = page.replace(
+                                &format!("{}{}{}{}", quote, prefix, name, quote),
+                                &format!("{}{}{}{}", quote, prefix, hashed, quote),
+                            );
+                        }
+                    }
+                }
+                // The page changes with the names it links to.
+                let hash = hex(&Sha256::digest(page.as_bytes()));
+                let asset = Arc::new(Asset::new(&content_type, hash, page.into()));
+                assets.by_path.insert(path, (asset, REVALIDATE));
+            } else {
+                let hashed = hashed_name(&path, &hash);
+                let asset = Arc::new(Asset::new(&content_type, hash, content));
+                assets.by_path.insert(hashed, (asset.clone(), IMMUTABLE));
+                assets.by_path.insert(path, (asset, REVALIDATE));
+            }
+        }
+        assets
+    }
+
+    /// The file at a path of a request, `index.html` for a directory, and its `Cache-Control`.
+    pub fn get(&self, path: &str) -> Option<(&Asset, &'static str)> {
+        let path = path.trim_start_matches('/');
+        let path = match path.is_empty() || path.ends_with('/') {
+            true => format!("{}index.html", path),
+            false => path.to_owned(),
+        };
+        self.by_path
+            .get(&path)
+            .map(|(asset, cache_control)| (asset.as_ref(), *cache_control))
+    }
+
+    /// The paths of the files, including the hashed names.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.by_path.keys().map(String::as_str)
+    }
+
+    /// Responds to a request for a file: `404` when there is none, `304 Not Modified` when the
+    /// client has it already, else the file in the best encoding the client accepts.
+    pub fn respond(&self, method: &Method, uri: &Uri, headers: &HeaderMap) -> Response {
+        if method != Method::GET && method != Method::HEAD {
+            return StatusCode::METHOD_NOT_ALLOWED.into_response();
+        }
+        let Some((asset, cache_control)) = self.get(uri.path()) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let (encoding, body) = asset.encoded(&accepted_encodings(headers));
+        let etag = asset.etag(encoding);
+
+        let mut response_headers = HeaderMap::new();
+        let mut set = |name, value: &str| {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                response_headers.insert(name, value);
+            }
+        };
+        set(header::ETAG, &etag);
+        set(header::CACHE_CONTROL, cache_control);
+        set(header::VARY, "accept-encoding");
+        let not_modified = headers
+            .get(header::IF_NONE_MATCH)
+            .is_some_and(|value| none_match_matches(value, &etag));
+        if not_modified {
+            return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+        }
+        set(header::CONTENT_TYPE, &asset.content_type);
+        if encoding != Encoding::Identity {
+            set(header::CONTENT_ENCODING, encoding.token());
+        }
+        (response_headers, Full::from(body)).into_response()
+    }
+}
+
+/// The compressed encodings accepted in an `Accept-Encoding` header, preferred first. The quality
+/// values only matter to refuse an encoding with `q=0`.
+fn accepted_encodings(headers: &HeaderMap) -> Vec<Encoding> {
+    let Some(accepted) = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return Vec::new();
+    };
+    let accepts = |token: &str| {
+        accepted.split(',').any(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let refused = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            name.eq_ignore_ascii_case(token) && !refused
+        })
+    };
+    [Encoding::Brotli, Encoding::Gzip]
+        .into_iter()
+        .filter(|encoding| accepts(encoding.token()))
+        .collect()
+}
+
+async fn no_cache<B>(mut response: Response<B>) -> Response<B> {
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static(REVALIDATE));
+    response
+}
+
+/// An app serving the frontend: the embedded files, or the files of a directory when one is given,
+/// read on every request so that changes show up without a restart.
+pub fn router(static_dir: Option<&Path>) -> Router {
+    match static_dir {
+        Some(dir) => Router::new()
+            .fallback_service(ServeDir::new(dir))
+            .layer(map_response(no_cache)),
+        None => {
+            let assets = Arc::new(Assets::embedded());
+            Router::new().fallback(move |method: Method, uri: Uri, headers: HeaderMap| {
+                let assets = assets.clone();
+                async move { assets.respond(&method, &uri, &headers) }
+            })
+        }
+    }
+}
@@ -1,10 +1,42 @@
+use rs_weight_tracker::annotations::normalize_tag;
+use rs_weight_tracker::{NewMeasurement, Origin};
 use std::{env, error::Error};
 
+const USAGE: &str = "Usage: add_weight [WEIGHT] [TIMESTAMP] [--note TEXT] [--tag TAG]... [--force]";
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let config = rs_weight_tracker::config::init_or_exit(&mut args);
+
+    let force = match args.iter().position(|arg| arg == "--force") {
+        Some(position) => {
+            args.remove(position);
+            true
+        }
+        None => false,
+    };
+
+    let mut note = None;
+    let mut tags: Option<Vec<String>> = None;
+    while let Some(position) = args
+        .iter()
+        .position(|arg| arg == "--note" || arg == "--tag")
+    {
+        let flag = args.remove(position);
+        if position >= args.len() {
+            return Err(format!("Missing value for {}", flag).into());
+        }
+        let value = args.remove(position);
+        if flag == "--note" {
+            note = Some(value);
+        } else {
+            tags.get_or_insert_with(Vec::new)
+                .push(normalize_tag(&value)?);
+        }
+    }
 
     if args.len() < 3 {
-        eprintln!("Usage: add_weight [WEIGHT] [TIMESTAMP]");
+        eprintln!("{}", USAGE);
         return Ok(());
     }
 
@@ -13,8 +45,29 @@ fn main() -> Result<(), Box<dyn Error>> {
     let date_as_text = args[2].clone();
     let mut conn = rs_weight_tracker::establish_connection();
 
-    let count = rs_weight_tracker::upsert_weight_for_date(&mut conn, weight, date_as_text)
-        .map_err(|err| err.to_string())?;
+    let measurement_date =
+        rs_weight_tracker::parse_date(&date_as_text).map_err(|err| err.to_string())?;
+    if !force {
+        let errors = config
+            .validation
+            .check(&mut conn, weight, measurement_date)?;
+        if !errors.is_empty() {
+            for error in &errors {
+                eprintln!("{}", error);
+            }
+            eprintln!("Use --force to store the weight anyway");
+            std::process::exit(1);
+        }
+    }
+    let measurement = NewMeasurement {
+        measurement_date,
+        weight_value: weight,
+        note,
+        tags,
+    };
+    let count =
+        rs_weight_tracker::upsert_measurement(&mut conn, &measurement, &Origin::cli("add_weight"))?
+            .rows();
     println!("Added {} new weight(s)", count);
     Ok(())
 }
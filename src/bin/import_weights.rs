@@ -1,6 +1,7 @@
-use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use chrono::{DateTime, Datelike, NaiveDate};
+use rs_weight_tracker::validation;
 use serde::Deserialize;
-use std::{env, error::Error, fs::File};
+use std::{env, error::Error, fs::File, path::Path};
 
 #[derive(Deserialize)]
 struct WeightData {
@@ -14,31 +15,55 @@ struct JsonWeight {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let config = rs_weight_tracker::config::init_or_exit(&mut args);
 
+    let force = match args.iter().position(|arg| arg == "--force") {
+        Some(position) => {
+            args.remove(position);
+            true
+        }
+        None => false,
+    };
     if args.len() != 2 {
-        eprintln!("Usage: import_weights [filename.json]");
+        eprintln!("Usage: import_weights [filename.json] [--force]");
         return Ok(());
     }
+    let rules = &config.validation;
 
     let filename = &args[1];
     let file = File::open(filename)?;
     let data: WeightData = serde_json::from_reader(file)?;
 
     let mut conn = rs_weight_tracker::establish_connection();
+    let file_name = Path::new(filename)
+        .file_name()
+        .map_or(filename.clone(), |name| name.to_string_lossy().into_owned());
+    let origin = rs_weight_tracker::Origin::import(&file_name);
 
     let mut total_count = 0;
+    let mut skipped_count = 0;
     for json_weight in data.weights {
-        let measurement_datetime = NaiveDateTime::from_timestamp_opt(json_weight.date / 1000, 0).ok_or("what timestamp is this?")?;
+        let measurement_datetime = DateTime::from_timestamp(json_weight.date / 1000, 0)
+            .ok_or("what timestamp is this?")?;
         if let Some(date_of_measurement) = NaiveDate::from_ymd_opt(
             measurement_datetime.year(),
             measurement_datetime.month(),
             measurement_datetime.day(),
         ) {
+            if !force {
+                let errors = rules.check(&mut conn, json_weight.weight, date_of_measurement)?;
+                if !errors.is_empty() {
+                    eprintln!("Skipped: {}", validation::describe(&errors));
+                    skipped_count += 1;
+                    continue;
+                }
+            }
             let count = rs_weight_tracker::upsert_weight(
                 &mut conn,
                 json_weight.weight,
                 date_of_measurement,
+                &origin,
             )?;
             total_count += count;
             println!("Added {} new weight(s)", count);
@@ -49,6 +74,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     println!("Added a total of {} new weight(s)", total_count);
+    if skipped_count > 0 {
+        println!(
+            "Skipped {} implausible weight(s); use --force to import them anyway",
+            skipped_count
+        );
+    }
 
     Ok(())
 }
@@ -40,10 +40,17 @@ fn parse_date(date_string: &str) -> Result<NaiveDate, LocalParseError> {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    rs_weight_tracker::config::init_or_exit(&mut args);
+
+    let show_chart = args.iter().any(|arg| arg == "--chart");
+    args.retain(|arg| arg != "--chart");
+    let format = rs_weight_tracker::output::take_format_arg(&mut args)?;
 
     if args.len() != 3 {
-        eprintln!("Usage: show_weight_interval [START_DATE] [END_DATE]");
+        eprintln!(
+            "Usage: show_weight_interval [START_DATE] [END_DATE] [--chart] [--format json|csv|tsv|table]"
+        );
         std::process::exit(1);
     }
 
@@ -61,10 +68,28 @@ fn main() -> Result<(), Box<dyn Error>> {
     };
 
     let mut conn = rs_weight_tracker::establish_connection();
+
+    if show_chart {
+        let options = rs_weight_tracker::chart::ChartOptions::for_terminal();
+        let output = rs_weight_tracker::chart::chart_between_dates(
+            &mut conn, start_date, end_date, 7, &options,
+        )?;
+        print!("{}", output);
+        return Ok(());
+    }
+
     let weights = rs_weight_tracker::weights_between_dates_with_interpolation(
         &mut conn, start_date, end_date,
     )?;
 
+    if let Some(format) = format {
+        let averages =
+            rs_weight_tracker::rolling_average_between_dates(&mut conn, start_date, end_date, 7)?;
+        let listing = rs_weight_tracker::output::interval_listing(&weights, &averages);
+        listing.write(format, &mut std::io::stdout().lock())?;
+        return Ok(());
+    }
+
     println!("Displaying {} weight(s)", weights.len());
     for weight in weights {
         println!(
@@ -3,18 +3,24 @@ use std::{env, error::Error};
 use diesel::prelude::*;
 
 fn main() -> Result<(), Box<dyn Error>> {
-  let args: Vec<String> = env::args().collect();
+  let mut args: Vec<String> = env::args().collect();
+  rs_weight_tracker::config::init_or_exit(&mut args);
+  let format = rs_weight_tracker::output::take_format_arg(&mut args)?;
 
   let limit = args.get(1).map_or(Ok(None), |s| s.parse::<i64>().map(Some))?;
   
-  use rs_weight_tracker::schema::weights::dsl::*;
-
   let mut conn = rs_weight_tracker::establish_connection();
   let results = match limit {
-      Some(limit) => weights.order(measurement_date.desc()).limit(limit).load::<Weight>(&mut conn)?,
-      None => weights.order(measurement_date.desc()).load::<Weight>(&mut conn)?,
+      Some(limit) => Weight::all().limit(limit).load::<Weight>(&mut conn)?,
+      None => Weight::all().load::<Weight>(&mut conn)?,
   };
 
+  if let Some(format) = format {
+      let listing = rs_weight_tracker::output::weights_listing(&results);
+      listing.write(format, &mut std::io::stdout().lock())?;
+      return Ok(());
+  }
+
   println!("Displaying {} weight(s)", results.len());
   for entry in results {
       println!(
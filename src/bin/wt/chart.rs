@@ -0,0 +1,84 @@
+use rs_weight_tracker::chart::{ChartData, ChartOptions, ChartStyle, ImageOptions, Theme};
+use std::{error::Error, fs, path::Path};
+
+use crate::{parse_flag_value, USAGE};
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut dates = Vec::new();
+    let mut days = 7;
+    let mut width: Option<u32> = None;
+    let mut height: Option<u32> = None;
+    let mut style = ChartStyle::Braille;
+    let mut theme = Theme::default();
+    let mut output: Option<String> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--days" => days = parse_flag_value(arg, args.next())?,
+            "--width" => width = Some(parse_flag_value(arg, args.next())?),
+            "--height" => height = Some(parse_flag_value(arg, args.next())?),
+            "--ascii" => style = ChartStyle::Ascii,
+            "--theme" => theme = parse_flag_value(arg, args.next())?,
+            "--output" | "-o" => output = Some(parse_flag_value(arg, args.next())?),
+            _ => dates.push(arg),
+        }
+    }
+
+    if dates.len() != 2 {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    if !(1..=7).contains(&days) {
+        return Err("--days must be between 1 and 7".into());
+    }
+
+    let start_date = rs_weight_tracker::parse_date(dates[0]).map_err(|err| err.to_string())?;
+    let end_date = rs_weight_tracker::parse_date(dates[1]).map_err(|err| err.to_string())?;
+
+    let mut conn = rs_weight_tracker::establish_connection();
+
+    if let Some(output) = output {
+        let defaults = ImageOptions::default();
+        let options = ImageOptions {
+            width: width.unwrap_or(defaults.width),
+            height: height.unwrap_or(defaults.height),
+            theme,
+        };
+        let data = ChartData::load(&mut conn, start_date, end_date, days)?;
+
+        let extension = Path::new(&output)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("png") => fs::write(
+                &output,
+                rs_weight_tracker::chart::render_png(&data, &options)?,
+            )?,
+            Some("svg") => fs::write(
+                &output,
+                rs_weight_tracker::chart::render_svg(&data, &options),
+            )?,
+            _ => return Err("--output must be a .png or .svg file".into()),
+        }
+        println!("Saved chart to {}", output);
+        return Ok(());
+    }
+
+    let mut options = ChartOptions::for_terminal();
+    options.style = style;
+    if let Some(width) = width {
+        options.width = width as usize;
+    }
+    if let Some(height) = height {
+        options.height = height as usize;
+    }
+
+    let output = rs_weight_tracker::chart::chart_between_dates(
+        &mut conn, start_date, end_date, days, &options,
+    )?;
+    print!("{}", output);
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use rs_weight_tracker::migrations::{self, MigrationResult};
+use std::error::Error;
+
+use crate::USAGE;
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    run_command(args).map_err(|err| err as Box<dyn Error>)
+}
+
+fn run_command(args: &[String]) -> MigrationResult<()> {
+    let mut conn = rs_weight_tracker::establish_connection();
+    match args {
+        [command] if command == "migrate" => {
+            let applied = migrations::migrate(&mut conn)?;
+            if applied.is_empty() {
+                println!("The database is up to date");
+            }
+            for name in applied {
+                println!("Applied {}", name);
+            }
+        }
+        [command] if command == "status" => {
+            for migration in migrations::status(&mut conn)? {
+                let state = if migration.applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!("{:<8} {}", state, migration.name);
+            }
+            for version in migrations::unknown(&mut conn)? {
+                println!(
+                    "{:<8} {} (newer than this version of wt)",
+                    "unknown", version
+                );
+            }
+        }
+        [command] if command == "rollback" => {
+            let name = migrations::rollback(&mut conn)?;
+            println!("Reverted {}", name);
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use chrono::Local;
+use rs_weight_tracker::events::{self, EventKind};
+use rs_weight_tracker::output::{events_listing, take_format_arg, OutputFormat};
+use rs_weight_tracker::NewEvent;
+use std::error::Error;
+
+use crate::USAGE;
+
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(1);
+}
+
+fn parse_date(value: &str) -> Result<chrono::NaiveDate, String> {
+    rs_weight_tracker::parse_date(value).map_err(|err| err.to_string())
+}
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut args = args.to_vec();
+    let format = take_format_arg(&mut args)?.unwrap_or(OutputFormat::Table);
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let events = match args.as_slice() {
+        [] => events::all_events(&mut conn)?,
+        [start_date, end_date] => {
+            events::events_between_dates(&mut conn, parse_date(start_date)?, parse_date(end_date)?)?
+        }
+        _ => usage_error(),
+    };
+    if events.is_empty() && format == OutputFormat::Table {
+        println!("No events");
+        return Ok(());
+    }
+
+    let summaries = events::phase_summaries(&mut conn, &events, Local::now().date_naive())?;
+    let rows: Vec<_> = events.into_iter().zip(summaries).collect();
+    events_listing(&rows).write(format, &mut std::io::stdout().lock())?;
+    Ok(())
+}
+
+pub fn run_event(args: &[String]) -> Result<(), Box<dyn Error>> {
+    match args.first().map(String::as_str) {
+        Some("add") => run_add(&args[1..]),
+        Some("delete") => run_delete(&args[1..]),
+        _ => usage_error(),
+    }
+}
+
+fn run_add(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut positional = Vec::new();
+    let mut note = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--note" => note = Some(args.next().ok_or("Missing value for --note")?.clone()),
+            _ if arg.starts_with("--") => usage_error(),
+            _ => positional.push(arg),
+        }
+    }
+    let (kind, title, start_date, end_date) = match positional.as_slice() {
+        [kind, title, start_date] => (kind, title, start_date, None),
+        [kind, title, start_date, end_date] => (kind, title, start_date, Some(end_date)),
+        _ => usage_error(),
+    };
+
+    let event = NewEvent {
+        note,
+        ..NewEvent::new(
+            kind.parse::<EventKind>()?,
+            title,
+            parse_date(start_date)?,
+            end_date.map(|end_date| parse_date(end_date)).transpose()?,
+        )
+    };
+    event.validate()?;
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let event = events::add_event(&mut conn, &event)?;
+    println!("Added event {} ({} {})", event.id, event.kind, event.title);
+    Ok(())
+}
+
+fn run_delete(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let [id] = args else { usage_error() };
+    let id = id
+        .parse::<i32>()
+        .map_err(|_| format!("Invalid event id {}", id))?;
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    if events::delete_event(&mut conn, id)? == 0 {
+        return Err(format!("No event {}", id).into());
+    }
+    println!("Deleted event {}", id);
+    Ok(())
+}
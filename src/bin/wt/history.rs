@@ -0,0 +1,62 @@
+use rs_weight_tracker::history::{history_for_date, revert_to_change};
+use rs_weight_tracker::output::{history_listing, take_format_arg, OutputFormat};
+use rs_weight_tracker::Origin;
+use std::error::Error;
+
+use crate::USAGE;
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut args = args.to_vec();
+    let format = take_format_arg(&mut args)?.unwrap_or(OutputFormat::Table);
+    if args.len() != 1 {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    let date = rs_weight_tracker::parse_date(&args[0]).map_err(|err| err.to_string())?;
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let changes = history_for_date(&mut conn, date)?;
+    if changes.is_empty() && format == OutputFormat::Table {
+        println!("No changes recorded for {}", date.format("%Y-%m-%d"));
+        return Ok(());
+    }
+    history_listing(&changes).write(format, &mut std::io::stdout().lock())?;
+
+    Ok(())
+}
+
+pub fn run_revert(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.len() != 2 {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    let date = rs_weight_tracker::parse_date(&args[0]).map_err(|err| err.to_string())?;
+    let change_id = args[1]
+        .parse::<i32>()
+        .map_err(|_| format!("Invalid change id {}", args[1]))?;
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let change = revert_to_change(&mut conn, date, change_id, &Origin::cli("wt revert"))?
+        .ok_or_else(|| {
+            format!(
+                "No change {} in the history of {}",
+                change_id,
+                date.format("%Y-%m-%d")
+            )
+        })?;
+
+    match change.new_value {
+        Some(value) => println!(
+            "Reverted {} to {:.1} kg (change {})",
+            date.format("%Y-%m-%d"),
+            value,
+            change.id
+        ),
+        None => println!(
+            "Reverted {} to deleted (change {})",
+            date.format("%Y-%m-%d"),
+            change.id
+        ),
+    }
+    Ok(())
+}
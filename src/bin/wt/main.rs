@@ -0,0 +1,89 @@
+// wt.rs
+
+mod chart;
+mod db;
+mod events;
+mod history;
+mod report;
+mod trash;
+mod tui;
+
+use std::{env, error::Error};
+
+pub const USAGE: &str = "Usage: wt <COMMAND> [ARGS]
+
+Commands:
+  chart [START_DATE] [END_DATE] [--days N] [--width N] [--height N] [--ascii]
+        [--output FILE.png|FILE.svg] [--theme light|dark]
+        Draw the weights, interpolated weights and rolling average in the terminal,
+        or save the chart as an image (width and height are then in pixels)
+  delete [DATE]
+        Move the weight of a day to the trash
+  restore [DATE]
+        Take the weight of a day out of the trash
+  undo  Restore the most recently deleted weight
+  trash [--format json|csv|tsv|table]
+        List the weights in the trash
+  purge [--older-than-days N]
+        Remove for good the weights deleted more than N days ago
+        (default: TRASH_RETENTION_DAYS, or 30)
+  events [START_DATE END_DATE] [--format json|csv|tsv|table]
+        List the events (diet phases, bulks and cuts, injuries, medication changes)
+        with the start and end weight, change and rate of each of them
+  event add KIND TITLE START_DATE [END_DATE] [--note TEXT]
+        Add an event; KIND is diet, bulk, cut, injury, medication or other.
+        Without END_DATE the event is still going on
+  event delete ID
+        Delete an event
+  history [DATE] [--format json|csv|tsv|table]
+        List every insert, update and delete of the weight of a day, with the change ids
+  revert [DATE] [CHANGE_ID]
+        Restore the weight of a day to what it was right after the given change
+  report [START_DATE] [END_DATE] [--days N] [--output FILE.html|FILE.pdf]
+        Write a progress report with statistics, weekly averages and a chart
+        (printed as HTML when no output file is given)
+  tui   Browse, add, edit and delete weights in a full-screen terminal UI
+  db migrate
+        Apply the pending migrations of the database schema
+  db status
+        List the migrations and whether they are applied
+  db rollback
+        Revert the last migration applied
+
+Every command also accepts --config FILE and the flags of the settings, such as
+--database-url URL (see weight-tracker.example.toml)";
+
+pub fn parse_flag_value<T: std::str::FromStr>(
+    flag: &str,
+    value: Option<&String>,
+) -> Result<T, String> {
+    value
+        .ok_or(format!("Missing value for {}", flag))?
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {}", flag))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args: Vec<String> = env::args().collect();
+    rs_weight_tracker::config::init_or_exit(&mut args);
+
+    match args.get(1).map(String::as_str) {
+        Some("chart") => chart::run(&args[2..]),
+        Some("delete") => trash::run_delete(&args[2..]),
+        Some("restore") => trash::run_restore(&args[2..]),
+        Some("undo") => trash::run_undo(&args[2..]),
+        Some("trash") => trash::run_trash(&args[2..]),
+        Some("purge") => trash::run_purge(&args[2..]),
+        Some("events") => events::run(&args[2..]),
+        Some("event") => events::run_event(&args[2..]),
+        Some("history") => history::run(&args[2..]),
+        Some("revert") => history::run_revert(&args[2..]),
+        Some("report") => report::run(&args[2..]),
+        Some("tui") => tui::run(),
+        Some("db") => db::run(&args[2..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use rs_weight_tracker::report::{render_html, render_pdf, Report};
+use std::{error::Error, fs, path::Path};
+
+use crate::{parse_flag_value, USAGE};
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut dates = Vec::new();
+    let mut days = 7;
+    let mut output: Option<String> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--days" => days = parse_flag_value(arg, args.next())?,
+            "--output" | "-o" => output = Some(parse_flag_value(arg, args.next())?),
+            _ => dates.push(arg),
+        }
+    }
+
+    if dates.len() != 2 {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    if !(1..=7).contains(&days) {
+        return Err("--days must be between 1 and 7".into());
+    }
+
+    let start_date = rs_weight_tracker::parse_date(dates[0]).map_err(|err| err.to_string())?;
+    let end_date = rs_weight_tracker::parse_date(dates[1]).map_err(|err| err.to_string())?;
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let report = Report::load(&mut conn, start_date, end_date, days)?;
+
+    let Some(output) = output else {
+        print!("{}", render_html(&report));
+        return Ok(());
+    };
+
+    let extension = Path::new(&output)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("html") | Some("htm") => fs::write(&output, render_html(&report))?,
+        Some("pdf") => fs::write(&output, render_pdf(&report))?,
+        _ => return Err("--output must be a .html or .pdf file".into()),
+    }
+    println!("Saved report to {}", output);
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use chrono::Duration;
+use rs_weight_tracker::output::{take_format_arg, Listing, OutputFormat};
+use rs_weight_tracker::trash::{self, deleted_weights, purge_deleted};
+use rs_weight_tracker::{Origin, Weight};
+use std::error::Error;
+
+use crate::{parse_flag_value, USAGE};
+
+fn parse_single_date(args: &[String]) -> Result<chrono::NaiveDate, String> {
+    let [date] = args else {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    };
+    rs_weight_tracker::parse_date(date).map_err(|err| err.to_string())
+}
+
+fn describe(weight: &Weight) -> String {
+    format!(
+        "{:.1} kg for {}",
+        weight.weight_value,
+        weight.measurement_date.format("%Y-%m-%d")
+    )
+}
+
+pub fn run_delete(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let date = parse_single_date(args)?;
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let count = rs_weight_tracker::delete_weight(&mut conn, date, &Origin::cli("wt delete"))?;
+    if count == 0 {
+        return Err(format!("No weight for {}", date.format("%Y-%m-%d")).into());
+    }
+    println!(
+        "Moved the weight of {} to the trash (undo with: wt undo)",
+        date.format("%Y-%m-%d")
+    );
+    Ok(())
+}
+
+pub fn run_restore(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let date = parse_single_date(args)?;
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let weight = trash::restore_weight(&mut conn, date, &Origin::cli("wt restore"))?
+        .ok_or_else(|| format!("No weight for {} in the trash", date.format("%Y-%m-%d")))?;
+    println!("Restored {}", describe(&weight));
+    Ok(())
+}
+
+pub fn run_undo(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if !args.is_empty() {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    match trash::restore_last_deleted(&mut conn, &Origin::cli("wt undo"))? {
+        Some(weight) => println!("Restored {}", describe(&weight)),
+        None => println!("The trash is empty"),
+    }
+    Ok(())
+}
+
+pub fn run_trash(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut args = args.to_vec();
+    let format = take_format_arg(&mut args)?.unwrap_or(OutputFormat::Table);
+    if !args.is_empty() {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let deleted = deleted_weights(&mut conn)?;
+    if deleted.is_empty() && format == OutputFormat::Table {
+        println!("The trash is empty");
+        return Ok(());
+    }
+
+    let mut listing = Listing::new(&["date", "weight_kg", "deleted_at"]);
+    for (weight, deleted_at) in deleted {
+        listing.push_row(vec![
+            weight.measurement_date.into(),
+            weight.weight_value.into(),
+            deleted_at.format("%Y-%m-%dT%H:%M:%SZ").to_string().into(),
+        ]);
+    }
+    listing.write(format, &mut std::io::stdout().lock())?;
+    Ok(())
+}
+
+pub fn run_purge(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut retention = rs_weight_tracker::config::current().trash_retention();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--older-than-days" => {
+                retention = Duration::days(parse_flag_value::<u32>(arg, args.next())? as i64)
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut conn = rs_weight_tracker::establish_connection();
+    let count = purge_deleted(&mut conn, retention, &Origin::cli("wt purge"))?;
+    println!(
+        "Purged {} weight(s) deleted more than {} day(s) ago",
+        count,
+        retention.num_days()
+    );
+    Ok(())
+}
@@ -0,0 +1,417 @@
+use chrono::{Duration, Local, NaiveDate};
+use diesel::RunQueryDsl;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols;
+use ratatui::text::Line;
+use ratatui::widgets::{
+    Axis, Block, Chart, Dataset, Gauge, GraphType, Paragraph, Row, Table, TableState,
+};
+use ratatui::{DefaultTerminal, Frame};
+use rs_weight_tracker::config;
+use rs_weight_tracker::db::DbConnection;
+use rs_weight_tracker::stats::{GoalProgress, Summary};
+use rs_weight_tracker::trash::restore_last_deleted;
+use rs_weight_tracker::validation::{self, ValidationRules};
+use rs_weight_tracker::{Origin, Weight};
+use std::error::Error;
+
+/// Number of days displayed in the chart, ending with the most recent entry.
+const CHART_DAYS: i64 = 60;
+const ROLLING_AVERAGE_DAYS: u32 = 7;
+
+enum Mode {
+    Browse,
+    Add,
+    Edit(NaiveDate),
+    ConfirmDelete(NaiveDate),
+}
+
+struct App {
+    conn: DbConnection,
+    origin: Origin,
+    rules: ValidationRules,
+    entries: Vec<Weight>,
+    table_state: TableState,
+    actual_points: Vec<(f64, f64)>,
+    average_points: Vec<(f64, f64)>,
+    chart_start: NaiveDate,
+    chart_end: NaiveDate,
+    goal: Option<f64>,
+    mode: Mode,
+    input: String,
+    message: String,
+}
+
+impl App {
+    fn new() -> Result<Self, Box<dyn Error>> {
+        let conn = rs_weight_tracker::establish_connection();
+        let today = Local::now().date_naive();
+        let mut app = App {
+            conn,
+            origin: Origin::cli("wt tui"),
+            rules: config::current().validation.clone(),
+            entries: Vec::new(),
+            table_state: TableState::default(),
+            actual_points: Vec::new(),
+            average_points: Vec::new(),
+            chart_start: today,
+            chart_end: today,
+            goal: config::current().goal_kg,
+            mode: Mode::Browse,
+            input: String::new(),
+            message: String::from(HELP),
+        };
+        app.reload()?;
+        Ok(app)
+    }
+
+    /// Reloads the entries and the chart data from the database.
+    fn reload(&mut self) -> Result<(), Box<dyn Error>> {
+        self.entries = Weight::all().load::<Weight>(&mut self.conn)?;
+
+        let selected = self.table_state.selected().unwrap_or(0);
+        self.table_state.select(if self.entries.is_empty() {
+            None
+        } else {
+            Some(selected.min(self.entries.len() - 1))
+        });
+
+        self.chart_end = self
+            .entries
+            .first()
+            .map(|weight| weight.measurement_date)
+            .unwrap_or_else(|| Local::now().date_naive());
+        self.chart_start = self.chart_end - Duration::days(CHART_DAYS - 1);
+
+        let to_x = |date: NaiveDate| (date - self.chart_start).num_days() as f64;
+        self.actual_points = rs_weight_tracker::weights_between_dates(
+            &mut self.conn,
+            self.chart_start,
+            self.chart_end,
+        )?
+        .iter()
+        .map(|weight| (to_x(weight.measurement_date), weight.weight_value))
+        .collect();
+        self.average_points = rs_weight_tracker::rolling_average_between_dates(
+            &mut self.conn,
+            self.chart_start,
+            self.chart_end,
+            ROLLING_AVERAGE_DAYS,
+        )?
+        .iter()
+        .map(|(date, average)| (to_x(*date), *average))
+        .collect();
+
+        Ok(())
+    }
+
+    fn selected_entry(&self) -> Option<&Weight> {
+        self.table_state
+            .selected()
+            .and_then(|index| self.entries.get(index))
+    }
+
+    /// Handles a key press. Returns `false` when the application should exit.
+    fn on_key(&mut self, code: KeyCode) -> Result<bool, Box<dyn Error>> {
+        match self.mode {
+            Mode::Browse => match code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                KeyCode::Down | KeyCode::Char('j') => self.table_state.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.table_state.select_previous(),
+                KeyCode::PageDown => self.table_state.scroll_down_by(10),
+                KeyCode::PageUp => self.table_state.scroll_up_by(10),
+                KeyCode::Home => self.table_state.select_first(),
+                KeyCode::End => self.table_state.select_last(),
+                KeyCode::Char('r') => {
+                    self.reload()?;
+                    self.message = String::from("Reloaded");
+                }
+                KeyCode::Char('u') => {
+                    self.message = match restore_last_deleted(&mut self.conn, &self.origin)? {
+                        Some(weight) => format!(
+                            "Restored {:.1} kg for {}",
+                            weight.weight_value,
+                            weight.measurement_date.format("%Y-%m-%d")
+                        ),
+                        None => String::from("The trash is empty"),
+                    };
+                    self.reload()?;
+                }
+                KeyCode::Char('a') => {
+                    self.input = format!("{} ", Local::now().date_naive().format("%Y-%m-%d"));
+                    self.message =
+                        String::from("Add: [DATE] [WEIGHT], Enter to save, Esc to cancel");
+                    self.mode = Mode::Add;
+                }
+                KeyCode::Char('e') | KeyCode::Enter => {
+                    if let Some(entry) = self.selected_entry() {
+                        let date = entry.measurement_date;
+                        self.input = format!("{:.1}", entry.weight_value);
+                        self.message = format!(
+                            "Edit {}: [WEIGHT], Enter to save, Esc to cancel",
+                            date.format("%Y-%m-%d")
+                        );
+                        self.mode = Mode::Edit(date);
+                    }
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    if let Some(entry) = self.selected_entry() {
+                        let date = entry.measurement_date;
+                        self.message =
+                            format!("Delete the weight of {}? (y/n)", date.format("%Y-%m-%d"));
+                        self.mode = Mode::ConfirmDelete(date);
+                    }
+                }
+                _ => {}
+            },
+            Mode::Add | Mode::Edit(_) => match code {
+                KeyCode::Esc => {
+                    self.mode = Mode::Browse;
+                    self.message = String::from(HELP);
+                }
+                KeyCode::Enter => {
+                    self.message = match self.save_input() {
+                        Ok(message) => {
+                            self.mode = Mode::Browse;
+                            self.reload()?;
+                            message
+                        }
+                        Err(error) => format!("Error: {}", error),
+                    };
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                _ => {}
+            },
+            Mode::ConfirmDelete(date) => {
+                self.mode = Mode::Browse;
+                self.message = if code == KeyCode::Char('y') {
+                    let count =
+                        rs_weight_tracker::delete_weight(&mut self.conn, date, &self.origin)?;
+                    self.reload()?;
+                    format!("Moved {} weight(s) to the trash, press u to undo", count)
+                } else {
+                    String::from(HELP)
+                };
+            }
+        }
+        Ok(true)
+    }
+
+    /// Saves the weight typed in the input line, returning a confirmation message. A weight
+    /// breaking the plausibility rules is only saved when the input ends with `!`.
+    fn save_input(&mut self) -> Result<String, Box<dyn Error>> {
+        let input = self.input.trim();
+        let (input, force) = match input.strip_suffix('!') {
+            Some(input) => (input, true),
+            None => (input, false),
+        };
+        let (date, value) = match self.mode {
+            Mode::Edit(date) => (date, input.trim()),
+            _ => {
+                let mut parts = input.split_whitespace();
+                let date = parts.next().ok_or("missing date")?;
+                let date = rs_weight_tracker::parse_date(date).map_err(|err| err.to_string())?;
+                (date, parts.next().ok_or("missing weight")?)
+            }
+        };
+        let value = value.parse::<f64>().map_err(|_| "invalid weight")?;
+
+        if !force {
+            let errors = self.rules.check(&mut self.conn, value, date)?;
+            if !errors.is_empty() {
+                return Err(format!(
+                    "{} (end the input with ! to save it anyway)",
+                    validation::describe(&errors)
+                )
+                .into());
+            }
+        }
+
+        let count = rs_weight_tracker::upsert_weight(&mut self.conn, value, date, &self.origin)?;
+        Ok(format!(
+            "Saved {:.1} kg for {} ({} row(s))",
+            value,
+            date.format("%Y-%m-%d"),
+            count
+        ))
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, status_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+        let [table_area, side_area] =
+            Layout::horizontal([Constraint::Length(32), Constraint::Min(0)]).areas(main_area);
+        let [chart_area, stats_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(9)]).areas(side_area);
+
+        self.draw_table(frame, table_area);
+        self.draw_chart(frame, chart_area);
+        self.draw_stats(frame, stats_area);
+
+        let status = match self.mode {
+            Mode::Add | Mode::Edit(_) => format!("> {}", self.input),
+            _ => String::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(self.message.as_str()), Line::from(status)])
+                .block(Block::new()),
+            status_area,
+        );
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.entries.iter().map(|weight| {
+            Row::new(vec![
+                weight.measurement_date.format("%Y-%m-%d").to_string(),
+                format!("{:>6.1} kg", weight.weight_value),
+            ])
+        });
+        let table = Table::new(rows, [Constraint::Length(12), Constraint::Length(10)])
+            .header(
+                Row::new(vec!["Date", "Weight"]).style(Style::new().add_modifier(Modifier::BOLD)),
+            )
+            .block(Block::bordered().title(format!(" Weights ({}) ", self.entries.len())))
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(table, area, &mut self.table_state);
+    }
+
+    fn draw_chart(&self, frame: &mut Frame, area: Rect) {
+        let (min, max) = self
+            .actual_points
+            .iter()
+            .chain(self.average_points.iter())
+            .map(|(_, value)| *value)
+            .chain(self.goal)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+        let (min, max) = if min.is_finite() {
+            ((min - 0.5).floor(), (max + 0.5).ceil())
+        } else {
+            (0.0, 1.0)
+        };
+
+        let x_max = (CHART_DAYS - 1) as f64;
+        let goal_points = self.goal.map(|goal| vec![(0.0, goal), (x_max, goal)]);
+
+        let mut datasets = vec![
+            Dataset::default()
+                .name("weight")
+                .marker(symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::new().fg(Color::Cyan))
+                .data(&self.actual_points),
+            Dataset::default()
+                .name(format!("{}-day average", ROLLING_AVERAGE_DAYS))
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(Color::Yellow))
+                .data(&self.average_points),
+        ];
+        if let Some(goal_points) = &goal_points {
+            datasets.push(
+                Dataset::default()
+                    .name("goal")
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::new().fg(Color::Green))
+                    .data(goal_points),
+            );
+        }
+
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title(format!(" Last {} days ", CHART_DAYS)))
+            .x_axis(Axis::default().bounds([0.0, x_max]).labels([
+                self.chart_start.format("%Y-%m-%d").to_string(),
+                self.chart_end.format("%Y-%m-%d").to_string(),
+            ]))
+            .y_axis(Axis::default().bounds([min, max]).labels([
+                format!("{:.1}", min),
+                format!("{:.1}", (min + max) / 2.0),
+                format!("{:.1}", max),
+            ]));
+        frame.render_widget(chart, area);
+    }
+
+    fn draw_stats(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Summary ");
+        let Some(summary) = Summary::from_weights(&self.entries) else {
+            frame.render_widget(Paragraph::new("No weights yet").block(block), area);
+            return;
+        };
+
+        let [text_area, gauge_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(block.inner(area));
+        frame.render_widget(block, area);
+
+        let lines = vec![
+            Line::from(format!(
+                "Entries: {}  ({} .. {})",
+                summary.count,
+                summary.first_date.format("%Y-%m-%d"),
+                summary.last_date.format("%Y-%m-%d")
+            )),
+            Line::from(format!(
+                "First: {:.1} kg  Last: {:.1} kg  Change: {:+.1} kg",
+                summary.first_weight,
+                summary.last_weight,
+                summary.change()
+            )),
+            Line::from(format!(
+                "Min: {:.1} kg  Max: {:.1} kg  Mean: {:.1} kg",
+                summary.min_weight, summary.max_weight, summary.mean_weight
+            )),
+        ];
+        frame.render_widget(Paragraph::new(lines), text_area);
+
+        let gauge = match self.goal {
+            Some(goal) => {
+                let progress = GoalProgress::new(goal, summary.first_weight, summary.last_weight);
+                Gauge::default()
+                    .block(Block::bordered().title(format!(" Goal: {:.1} kg ", goal)))
+                    .gauge_style(Style::new().fg(Color::Green))
+                    .ratio(progress.fraction())
+                    .label(format!(
+                        "{:.0}% ({:.1} kg to go)",
+                        progress.fraction() * 100.0,
+                        progress.remaining().abs()
+                    ))
+            }
+            None => Gauge::default()
+                .block(Block::bordered().title(" Goal "))
+                .ratio(0.0)
+                .label("Set WEIGHT_GOAL_KG to track a goal"),
+        };
+        frame.render_widget(gauge, gauge_area);
+    }
+}
+
+const HELP: &str =
+    "Up/Down: scroll  a: add  e: edit  d: delete  u: undo delete  r: reload  q: quit";
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<(), Box<dyn Error>> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !app.on_key(key.code)? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let mut app = App::new()?;
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+
+    result
+}
@@ -0,0 +1,228 @@
+mod font;
+mod png;
+mod svg;
+mod terminal;
+
+use chrono::NaiveDate;
+use diesel::QueryResult;
+use std::str::FromStr;
+
+use crate::db::DbConnection;
+use crate::Weight;
+
+pub use png::render_png;
+pub use svg::render_svg;
+pub use terminal::{chart_between_dates, render_chart, ChartOptions, ChartStyle};
+
+/// The color scheme of the SVG and PNG charts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            _ => Err(format!("Unknown theme '{}', expected light or dark", value)),
+        }
+    }
+}
+
+pub(crate) type Rgb = (u8, u8, u8);
+
+pub(crate) struct Palette {
+    pub background: Rgb,
+    pub grid: Rgb,
+    pub text: Rgb,
+    pub weight: Rgb,
+    pub average: Rgb,
+    pub goal: Rgb,
+    pub outlier: Rgb,
+}
+
+impl Theme {
+    pub(crate) fn palette(self) -> Palette {
+        match self {
+            Theme::Light => Palette {
+                background: (0xff, 0xff, 0xff),
+                grid: (0xe5, 0xe5, 0xe5),
+                text: (0x55, 0x55, 0x55),
+                weight: (0x4b, 0x9f, 0xd5),
+                average: (0x4b, 0xc0, 0xc0),
+                goal: (0x2e, 0x9d, 0x4f),
+                outlier: (0xd9, 0x53, 0x4f),
+            },
+            Theme::Dark => Palette {
+                background: (0x1e, 0x1e, 0x24),
+                grid: (0x3a, 0x3a, 0x44),
+                text: (0xc8, 0xc8, 0xd0),
+                weight: (0x6c, 0xb4, 0xe8),
+                average: (0x5f, 0xd8, 0xd0),
+                goal: (0x5c, 0xc8, 0x78),
+                outlier: (0xff, 0x6b, 0x66),
+            },
+        }
+    }
+}
+
+/// Size and colors of the SVG and PNG charts.
+#[derive(Debug, Clone)]
+pub struct ImageOptions {
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    pub theme: Theme,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            width: 800,
+            height: 400,
+            theme: Theme::Light,
+        }
+    }
+}
+
+/// The series drawn in a chart of an interval: actual and interpolated weights, the rolling
+/// average, the optional goal weight and the outliers.
+#[derive(Debug)]
+pub struct ChartData {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub weights: Vec<(Weight, bool)>,
+    pub averages: Vec<(NaiveDate, f64)>,
+    pub average_days: u32,
+    pub goal: Option<f64>,
+    pub outliers: Vec<NaiveDate>,
+}
+
+impl ChartData {
+    /// Loads the chart data between two dates (inclusive). The goal is the
+    /// [`goal_kg`](crate::config::Config::goal_kg) of the current configuration.
+    pub fn load(
+        conn: &mut DbConnection,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        amount_of_days: u32,
+    ) -> QueryResult<Self> {
+        let weights = crate::weights_between_dates_with_interpolation(conn, start_date, end_date)?;
+        let averages =
+            crate::rolling_average_between_dates(conn, start_date, end_date, amount_of_days)?;
+        let outliers = crate::stats::outliers(&weights, &averages);
+
+        Ok(ChartData {
+            start_date,
+            end_date,
+            weights,
+            averages,
+            average_days: amount_of_days,
+            goal: crate::config::current().goal_kg,
+            outliers,
+        })
+    }
+
+    /// The lowest and highest value of all series, including the goal. Returns `None` when
+    /// there is nothing to draw.
+    pub fn value_range(&self) -> Option<(f64, f64)> {
+        let (min, max) = self
+            .weights
+            .iter()
+            .map(|(weight, _)| weight.weight_value)
+            .chain(self.averages.iter().map(|(_, average)| *average))
+            .chain(self.goal)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+        (min <= max).then_some((min, max))
+    }
+
+    pub fn is_outlier(&self, date: NaiveDate) -> bool {
+        self.outliers.contains(&date)
+    }
+
+    /// Splits the weight series into runs of actual and interpolated segments, in plot
+    /// coordinates. A segment is interpolated when either of its ends is.
+    pub(crate) fn weight_runs(&self, scale: &Scale) -> Vec<(bool, Vec<(f64, f64)>)> {
+        let mut runs: Vec<(bool, Vec<(f64, f64)>)> = Vec::new();
+        for pair in self.weights.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            let is_interpolated = from.1 || to.1;
+            let from_point = (
+                scale.x(from.0.measurement_date),
+                scale.y(from.0.weight_value),
+            );
+            let to_point = (scale.x(to.0.measurement_date), scale.y(to.0.weight_value));
+            match runs.last_mut() {
+                Some((run_interpolated, points)) if *run_interpolated == is_interpolated => {
+                    points.push(to_point)
+                }
+                _ => runs.push((is_interpolated, vec![from_point, to_point])),
+            }
+        }
+        runs
+    }
+}
+
+/// Maps dates and weights to coordinates inside a plotting area, with the y axis pointing down.
+#[derive(Debug, Clone)]
+pub(crate) struct Scale {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+    pub first_date: NaiveDate,
+    pub span_days: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Scale {
+    pub fn x(&self, date: NaiveDate) -> f64 {
+        self.left + (date - self.first_date).num_days() as f64 / self.span_days * self.width
+    }
+
+    pub fn y(&self, value: f64) -> f64 {
+        self.top + (self.max - value) / (self.max - self.min) * self.height
+    }
+}
+
+/// Returns round values between `min` and `max` to label the weight axis with, at most
+/// `max_count` of them, and the range widened to the surrounding round values.
+pub(crate) fn value_ticks(min: f64, max: f64, max_count: usize) -> (Vec<f64>, f64, f64) {
+    let (min, max) = if max - min < 1.0 {
+        (min - 0.5, max + 0.5)
+    } else {
+        (min, max)
+    };
+
+    let step = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0]
+        .into_iter()
+        .find(|step| (max - min) / step <= max_count.max(2) as f64)
+        .unwrap_or(100.0);
+    let low = (min / step).floor() * step;
+    let high = (max / step).ceil() * step;
+
+    let count = ((high - low) / step).round() as usize;
+    let ticks = (0..=count).map(|index| low + step * index as f64).collect();
+    (ticks, low, high)
+}
+
+/// Returns up to `max_count` evenly spaced dates between `first` and `last` (inclusive).
+pub(crate) fn date_ticks(first: NaiveDate, last: NaiveDate, max_count: usize) -> Vec<NaiveDate> {
+    let span = (last - first).num_days();
+    if span <= 0 {
+        return vec![first];
+    }
+    let count = (max_count.max(2) as i64 - 1).min(span);
+    (0..=count)
+        .map(|index| first + chrono::Duration::days(span * index / count))
+        .collect()
+}
@@ -0,0 +1,224 @@
+use tiny_skia::{Color, Paint, PathBuilder, Pixmap, Rect, Stroke, StrokeDash, Transform};
+
+use super::font::{self, ADVANCE, GLYPH_HEIGHT};
+use super::{date_ticks, value_ticks, ChartData, ImageOptions, Rgb, Scale};
+
+/// Images wider than this get their text drawn at twice the size.
+const LARGE_TEXT_WIDTH: u32 = 700;
+
+fn paint(color: Rgb) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(Color::from_rgba8(color.0, color.1, color.2, 255));
+    paint.anti_alias = true;
+    paint
+}
+
+struct Canvas {
+    pixmap: Pixmap,
+    text_scale: f32,
+}
+
+impl Canvas {
+    fn text_width(&self, text: &str) -> f32 {
+        text.chars().count() as f32 * ADVANCE as f32 * self.text_scale
+    }
+
+    /// Draws text with its top left corner at the given position.
+    fn text(&mut self, text: &str, x: f32, y: f32, color: Rgb) {
+        let mut paint = paint(color);
+        paint.anti_alias = false;
+        let pixel = self.text_scale;
+        for (index, c) in text.chars().enumerate() {
+            let left = x + index as f32 * ADVANCE as f32 * pixel;
+            for (row, bits) in font::glyph(c).iter().enumerate() {
+                for column in 0..font::GLYPH_WIDTH {
+                    if bits & (0b10000 >> column) == 0 {
+                        continue;
+                    }
+                    if let Some(rect) = Rect::from_xywh(
+                        left + column as f32 * pixel,
+                        y + row as f32 * pixel,
+                        pixel,
+                        pixel,
+                    ) {
+                        self.pixmap
+                            .fill_rect(rect, &paint, Transform::identity(), None);
+                    }
+                }
+            }
+        }
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)], color: Rgb, width: f32, dashed: bool) {
+        let mut builder = PathBuilder::new();
+        for (index, (x, y)) in points.iter().enumerate() {
+            if index == 0 {
+                builder.move_to(*x as f32, *y as f32);
+            } else {
+                builder.line_to(*x as f32, *y as f32);
+            }
+        }
+        let Some(path) = builder.finish() else {
+            return;
+        };
+        let stroke = Stroke {
+            width,
+            dash: if dashed {
+                StrokeDash::new(vec![4.0 * width, 3.0 * width], 0.0)
+            } else {
+                None
+            },
+            ..Stroke::default()
+        };
+        self.pixmap
+            .stroke_path(&path, &paint(color), &stroke, Transform::identity(), None);
+    }
+
+    fn circle(&mut self, x: f64, y: f64, radius: f32, color: Rgb, filled: bool) {
+        let Some(path) = PathBuilder::from_circle(x as f32, y as f32, radius) else {
+            return;
+        };
+        if filled {
+            self.pixmap.fill_path(
+                &path,
+                &paint(color),
+                tiny_skia::FillRule::Winding,
+                Transform::identity(),
+                None,
+            );
+        } else {
+            let stroke = Stroke {
+                width: 1.5,
+                ..Stroke::default()
+            };
+            self.pixmap
+                .stroke_path(&path, &paint(color), &stroke, Transform::identity(), None);
+        }
+    }
+}
+
+/// Renders the chart of an interval as a PNG image, with the same content as `render_svg`.
+///
+/// The image is rasterised in pure Rust and the text uses a built-in bitmap font, so no system
+/// fonts or graphics libraries are needed.
+pub fn render_png(data: &ChartData, options: &ImageOptions) -> Result<Vec<u8>, String> {
+    let width = options.width.clamp(200, 8000);
+    let height = options.height.clamp(120, 8000);
+    let palette = options.theme.palette();
+
+    let mut canvas = Canvas {
+        pixmap: Pixmap::new(width, height).ok_or("Invalid image size")?,
+        text_scale: if width > LARGE_TEXT_WIDTH { 2.0 } else { 1.0 },
+    };
+    let (red, green, blue) = palette.background;
+    canvas.pixmap.fill(Color::from_rgba8(red, green, blue, 255));
+
+    let text_height = GLYPH_HEIGHT as f32 * canvas.text_scale;
+    let line_scale = canvas.text_scale;
+
+    let Some((min, max)) = data.value_range() else {
+        let message = "no weights to display";
+        let x = (width as f32 - canvas.text_width(message)) / 2.0;
+        canvas.text(message, x, height as f32 / 2.0, palette.text);
+        return canvas.pixmap.encode_png().map_err(|err| err.to_string());
+    };
+
+    let margin_left = canvas.text_width("000.0") + 12.0;
+    let margin_right = 10.0 * line_scale;
+    let margin_top = text_height * 2.0 + 16.0;
+    let margin_bottom = text_height * 1.5 + 16.0;
+
+    let plot_height = height as f32 - margin_top - margin_bottom;
+    let (ticks, min, max) = value_ticks(min, max, (plot_height / (40.0 * line_scale)) as usize);
+    let scale = Scale {
+        left: margin_left as f64,
+        top: margin_top as f64,
+        width: (width as f32 - margin_left - margin_right) as f64,
+        height: plot_height as f64,
+        first_date: data.start_date,
+        span_days: (data.end_date - data.start_date).num_days().max(1) as f64,
+        min,
+        max,
+    };
+
+    for tick in ticks {
+        let y = scale.y(tick);
+        canvas.polyline(
+            &[(scale.left, y), (scale.left + scale.width, y)],
+            palette.grid,
+            1.0,
+            false,
+        );
+        let label = format!("{:.1}", tick);
+        let x = margin_left - 6.0 - canvas.text_width(&label);
+        canvas.text(&label, x, y as f32 - text_height / 2.0, palette.text);
+    }
+
+    let label_width = canvas.text_width("0000-00-00") + 20.0;
+    let date_count = (scale.width as f32 / label_width) as usize;
+    for date in date_ticks(data.start_date, data.end_date, date_count) {
+        let label = date.format("%Y-%m-%d").to_string();
+        let x = (scale.x(date) as f32 - canvas.text_width(&label) / 2.0)
+            .clamp(0.0, width as f32 - canvas.text_width(&label));
+        let y = (scale.top + scale.height) as f32 + text_height / 2.0 + 8.0;
+        canvas.text(&label, x, y, palette.text);
+    }
+
+    if let Some(goal) = data.goal {
+        let y = scale.y(goal);
+        canvas.polyline(
+            &[(scale.left, y), (scale.left + scale.width, y)],
+            palette.goal,
+            1.5 * line_scale,
+            true,
+        );
+        let label = format!("goal {:.1} kg", goal);
+        let x = (scale.left + scale.width) as f32 - canvas.text_width(&label);
+        canvas.text(&label, x, y as f32 - text_height - 4.0, palette.goal);
+    }
+
+    for (is_interpolated, points) in data.weight_runs(&scale) {
+        canvas.polyline(&points, palette.weight, line_scale, is_interpolated);
+    }
+
+    let average_points: Vec<(f64, f64)> = data
+        .averages
+        .iter()
+        .map(|(date, average)| (scale.x(*date), scale.y(*average)))
+        .collect();
+    canvas.polyline(&average_points, palette.average, 2.5 * line_scale, false);
+
+    for (weight, is_interpolated) in &data.weights {
+        if *is_interpolated {
+            continue;
+        }
+        let x = scale.x(weight.measurement_date);
+        let y = scale.y(weight.weight_value);
+        canvas.circle(x, y, 2.5 * line_scale, palette.weight, true);
+        if data.is_outlier(weight.measurement_date) {
+            canvas.circle(x, y, 6.0 * line_scale, palette.outlier, false);
+        }
+    }
+
+    let legend = [
+        (palette.weight, String::from("weight")),
+        (
+            palette.average,
+            format!("{}-day rolling average", data.average_days),
+        ),
+        (palette.goal, String::from("goal")),
+        (palette.outlier, String::from("outlier")),
+    ];
+    let mut x = margin_left;
+    for (color, label) in legend {
+        if let Some(rect) = Rect::from_xywh(x, 8.0, text_height, text_height) {
+            canvas
+                .pixmap
+                .fill_rect(rect, &paint(color), Transform::identity(), None);
+        }
+        canvas.text(&label, x + text_height + 6.0, 8.0, palette.text);
+        x += text_height + canvas.text_width(&label) + 20.0;
+    }
+
+    canvas.pixmap.encode_png().map_err(|err| err.to_string())
+}
@@ -0,0 +1,215 @@
+use std::fmt::Write;
+
+use super::{date_ticks, value_ticks, ChartData, ImageOptions, Rgb, Scale};
+
+const MARGIN_LEFT: f64 = 50.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 36.0;
+const MARGIN_BOTTOM: f64 = 36.0;
+
+fn hex((red, green, blue): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn polyline(points: &[(f64, f64)], color: &str, width: f64, dashed: bool) -> String {
+    let points: Vec<String> = points
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+        .collect();
+    format!(
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{}/>\n",
+        points.join(" "),
+        color,
+        width,
+        if dashed {
+            " stroke-dasharray=\"4 3\""
+        } else {
+            ""
+        }
+    )
+}
+
+/// Renders the chart of an interval as a standalone SVG document.
+///
+/// Actual weights are drawn as dots joined by a solid line, the gaps filled by interpolated
+/// weights as a dashed line, the rolling average as a thicker line, the goal as a horizontal
+/// dashed line and the outliers circled in red.
+pub fn render_svg(data: &ChartData, options: &ImageOptions) -> String {
+    let width = options.width.max(200) as f64;
+    let height = options.height.max(120) as f64;
+
+    let palette = options.theme.palette();
+    let background = hex(palette.background);
+    let grid_color = hex(palette.grid);
+    let text_color = hex(palette.text);
+    let weight_color = hex(palette.weight);
+    let average_color = hex(palette.average);
+    let goal_color = hex(palette.goal);
+    let outlier_color = hex(palette.outlier);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"11\">",
+        w = width,
+        h = height
+    );
+    let _ = writeln!(
+        svg,
+        "<title>Weights from {} to {}</title>",
+        data.start_date.format("%Y-%m-%d"),
+        data.end_date.format("%Y-%m-%d")
+    );
+    let _ = writeln!(
+        svg,
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        background
+    );
+
+    let Some((min, max)) = data.value_range() else {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"{}\">No weights to display</text>\n</svg>",
+            width / 2.0,
+            height / 2.0,
+            text_color
+        );
+        return svg;
+    };
+
+    let plot_height = height - MARGIN_TOP - MARGIN_BOTTOM;
+    let (ticks, min, max) = value_ticks(min, max, (plot_height / 40.0) as usize);
+    let scale = Scale {
+        left: MARGIN_LEFT,
+        top: MARGIN_TOP,
+        width: width - MARGIN_LEFT - MARGIN_RIGHT,
+        height: plot_height,
+        first_date: data.start_date,
+        span_days: (data.end_date - data.start_date).num_days().max(1) as f64,
+        min,
+        max,
+    };
+
+    for tick in ticks {
+        let y = scale.y(tick);
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{:.1}\" y1=\"{y:.1}\" x2=\"{:.1}\" y2=\"{y:.1}\" stroke=\"{}\"/>",
+            scale.left,
+            scale.left + scale.width,
+            grid_color,
+            y = y
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" fill=\"{}\">{:.1}</text>",
+            scale.left - 6.0,
+            y + 4.0,
+            text_color,
+            tick
+        );
+    }
+
+    for date in date_ticks(
+        data.start_date,
+        data.end_date,
+        (scale.width / 90.0) as usize,
+    ) {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"{}\">{}</text>",
+            scale.x(date),
+            scale.top + scale.height + 18.0,
+            text_color,
+            date.format("%Y-%m-%d")
+        );
+    }
+
+    if let Some(goal) = data.goal {
+        let y = scale.y(goal);
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{:.1}\" y1=\"{y:.1}\" x2=\"{:.1}\" y2=\"{y:.1}\" stroke=\"{}\" stroke-width=\"1.5\" stroke-dasharray=\"8 4\"/>",
+            scale.left,
+            scale.left + scale.width,
+            goal_color,
+            y = y
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" fill=\"{}\">goal {:.1} kg</text>",
+            scale.left + scale.width,
+            y - 4.0,
+            goal_color,
+            goal
+        );
+    }
+
+    for (is_interpolated, points) in data.weight_runs(&scale) {
+        svg.push_str(&polyline(&points, &weight_color, 1.0, is_interpolated));
+    }
+
+    let average_points: Vec<(f64, f64)> = data
+        .averages
+        .iter()
+        .map(|(date, average)| (scale.x(*date), scale.y(*average)))
+        .collect();
+    svg.push_str(&polyline(&average_points, &average_color, 2.5, false));
+
+    for (weight, is_interpolated) in &data.weights {
+        if *is_interpolated {
+            continue;
+        }
+        let x = scale.x(weight.measurement_date);
+        let y = scale.y(weight.weight_value);
+        let _ = writeln!(
+            svg,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"2.5\" fill=\"{}\"><title>{}: {:.1} kg</title></circle>",
+            x,
+            y,
+            weight_color,
+            weight.measurement_date.format("%Y-%m-%d"),
+            weight.weight_value
+        );
+        if data.is_outlier(weight.measurement_date) {
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"6\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/>",
+                x, y, outlier_color
+            );
+        }
+    }
+
+    let legend = [
+        (&weight_color, String::from("weight")),
+        (
+            &average_color,
+            format!("{}-day rolling average", data.average_days),
+        ),
+        (&goal_color, String::from("goal")),
+        (&outlier_color, String::from("outlier")),
+    ];
+    let mut x = MARGIN_LEFT;
+    for (color, label) in legend {
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"12\" width=\"12\" height=\"12\" fill=\"{}\"/><text x=\"{:.1}\" y=\"22\" fill=\"{}\">{}</text>",
+            x,
+            color,
+            x + 16.0,
+            text_color,
+            escape(&label)
+        );
+        x += 28.0 + label.len() as f64 * 6.0;
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
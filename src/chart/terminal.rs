@@ -0,0 +1,316 @@
+use chrono::NaiveDate;
+use diesel::QueryResult;
+
+use crate::db::DbConnection;
+use crate::Weight;
+
+const DEFAULT_WIDTH: usize = 80;
+const DEFAULT_HEIGHT: usize = 16;
+const MIN_PLOT_WIDTH: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartStyle {
+    /// Unicode braille characters, giving 2x4 dots per terminal cell.
+    Braille,
+    /// Plain ASCII characters, one dot per terminal cell.
+    Ascii,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    /// Total width of the chart in columns, including the axis labels.
+    pub width: usize,
+    /// Height of the plotting area in rows.
+    pub height: usize,
+    pub style: ChartStyle,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        ChartOptions {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            style: ChartStyle::Braille,
+        }
+    }
+}
+
+impl ChartOptions {
+    /// Options using the full width of the current terminal, falling back to 80 columns when
+    /// the output is not a terminal.
+    pub fn for_terminal() -> Self {
+        let width = terminal_size::terminal_size()
+            .map(|(terminal_size::Width(w), _)| w as usize)
+            .unwrap_or(DEFAULT_WIDTH);
+        ChartOptions {
+            width,
+            ..ChartOptions::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Marker {
+    Average,
+    Interpolated,
+    Actual,
+}
+
+impl Marker {
+    fn symbol(self, style: ChartStyle) -> char {
+        match (self, style) {
+            (Marker::Actual, ChartStyle::Braille) => '●',
+            (Marker::Interpolated, ChartStyle::Braille) => '∙',
+            (Marker::Average, ChartStyle::Braille) => '⣀',
+            (Marker::Actual, ChartStyle::Ascii) => '*',
+            (Marker::Interpolated, ChartStyle::Ascii) => '.',
+            (Marker::Average, ChartStyle::Ascii) => '-',
+        }
+    }
+}
+
+/// A grid of terminal cells. The rolling average is drawn as a line of dots (braille dots or
+/// ASCII dashes), while actual and interpolated weights are drawn as one marker per cell.
+struct Canvas {
+    cols: usize,
+    rows: usize,
+    style: ChartStyle,
+    dots: Vec<u8>,
+    markers: Vec<Option<Marker>>,
+}
+
+impl Canvas {
+    fn new(cols: usize, rows: usize, style: ChartStyle) -> Self {
+        Canvas {
+            cols,
+            rows,
+            style,
+            dots: vec![0; cols * rows],
+            markers: vec![None; cols * rows],
+        }
+    }
+
+    /// Resolution of the canvas in dots, horizontally and vertically.
+    fn resolution(&self) -> (usize, usize) {
+        match self.style {
+            ChartStyle::Braille => (self.cols * 2, self.rows * 4),
+            ChartStyle::Ascii => (self.cols, self.rows),
+        }
+    }
+
+    fn set_dot(&mut self, x: usize, y: usize) {
+        let (dots_x, dots_y) = self.resolution();
+        if x >= dots_x || y >= dots_y {
+            return;
+        }
+        match self.style {
+            ChartStyle::Braille => {
+                const BITS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+                self.dots[(y / 4) * self.cols + x / 2] |= BITS[x % 2][y % 4];
+            }
+            ChartStyle::Ascii => self.dots[y * self.cols + x] = 1,
+        }
+    }
+
+    fn line(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let (mut x0, mut y0) = (from.0 as i64, from.1 as i64);
+        let (x1, y1) = (to.0 as i64, to.1 as i64);
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            self.set_dot(x0 as usize, y0 as usize);
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x0 += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y0 += step_y;
+            }
+        }
+    }
+
+    /// Places a marker on the cell containing the given dot. Actual weights take precedence
+    /// over interpolated ones.
+    fn mark(&mut self, x: usize, y: usize, marker: Marker) {
+        let (col, row) = match self.style {
+            ChartStyle::Braille => (x / 2, y / 4),
+            ChartStyle::Ascii => (x, y),
+        };
+        if col >= self.cols || row >= self.rows {
+            return;
+        }
+        let cell = &mut self.markers[row * self.cols + col];
+        if cell.is_none_or(|existing| existing < marker) {
+            *cell = Some(marker);
+        }
+    }
+
+    fn row_to_string(&self, row: usize) -> String {
+        (0..self.cols)
+            .map(|col| {
+                let index = row * self.cols + col;
+                if let Some(marker) = self.markers[index] {
+                    return marker.symbol(self.style);
+                }
+                match (self.style, self.dots[index]) {
+                    (_, 0) => ' ',
+                    (ChartStyle::Braille, bits) => {
+                        char::from_u32(0x2800 + bits as u32).unwrap_or(' ')
+                    }
+                    (ChartStyle::Ascii, _) => Marker::Average.symbol(ChartStyle::Ascii),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Renders the actual weights, the interpolated weights and the rolling average as a text chart.
+///
+/// The horizontal axis covers the dates of all given values, and the vertical axis is scaled to
+/// the lowest and highest value. Each line of the result ends with a newline.
+///
+/// # Arguments
+///
+/// * `weights` - Weights as returned by `weights_between_dates_with_interpolation`
+/// * `averages` - Rolling averages as returned by `rolling_average_between_dates`
+/// * `options` - The size and style of the chart
+pub fn render_chart(
+    weights: &[(Weight, bool)],
+    averages: &[(NaiveDate, f64)],
+    options: &ChartOptions,
+) -> String {
+    let points: Vec<(NaiveDate, f64)> = weights
+        .iter()
+        .map(|(weight, _)| (weight.measurement_date, weight.weight_value))
+        .chain(averages.iter().copied())
+        .collect();
+
+    let (Some(first_date), Some(last_date)) = (
+        points.iter().map(|(date, _)| *date).min(),
+        points.iter().map(|(date, _)| *date).max(),
+    ) else {
+        return String::from("No weights to display\n");
+    };
+
+    let mut min_value = points.iter().map(|(_, v)| *v).fold(f64::INFINITY, f64::min);
+    let mut max_value = points
+        .iter()
+        .map(|(_, v)| *v)
+        .fold(f64::NEG_INFINITY, f64::max);
+    if max_value - min_value < 1.0 {
+        min_value -= 0.5;
+        max_value += 0.5;
+    }
+
+    let label_width = format!("{:.1}", max_value)
+        .len()
+        .max(format!("{:.1}", min_value).len());
+    let cols = options
+        .width
+        .saturating_sub(label_width + 2)
+        .max(MIN_PLOT_WIDTH);
+    let rows = options.height.max(2);
+
+    let mut canvas = Canvas::new(cols, rows, options.style);
+    let (dots_x, dots_y) = canvas.resolution();
+    let span_days = (last_date - first_date).num_days().max(1) as f64;
+    let to_dot = |date: NaiveDate, value: f64| {
+        let x = (date - first_date).num_days() as f64 / span_days * (dots_x - 1) as f64;
+        let y = (max_value - value) / (max_value - min_value) * (dots_y - 1) as f64;
+        (x.round() as usize, y.round() as usize)
+    };
+
+    let mut previous = None;
+    for (date, average) in averages {
+        let dot = to_dot(*date, *average);
+        canvas.line(previous.unwrap_or(dot), dot);
+        previous = Some(dot);
+    }
+
+    for (weight, is_interpolated) in weights {
+        let (x, y) = to_dot(weight.measurement_date, weight.weight_value);
+        let marker = if *is_interpolated {
+            Marker::Interpolated
+        } else {
+            Marker::Actual
+        };
+        canvas.mark(x, y, marker);
+    }
+
+    let (axis, corner, ruler) = match options.style {
+        ChartStyle::Braille => ('┤', '└', '─'),
+        ChartStyle::Ascii => ('|', '+', '-'),
+    };
+
+    let mut output = String::new();
+    for row in 0..rows {
+        let label = if row == 0 || row == rows - 1 || row == rows / 2 {
+            let value = max_value - (max_value - min_value) * row as f64 / (rows - 1) as f64;
+            format!("{:.1}", value)
+        } else {
+            String::new()
+        };
+        output.push_str(&format!(
+            "{:>width$} {}{}\n",
+            label,
+            axis,
+            canvas.row_to_string(row),
+            width = label_width
+        ));
+    }
+
+    output.push_str(&format!(
+        "{:>width$} {}{}\n",
+        "",
+        corner,
+        ruler.to_string().repeat(cols),
+        width = label_width
+    ));
+
+    let first_label = first_date.format("%Y-%m-%d").to_string();
+    let last_label = last_date.format("%Y-%m-%d").to_string();
+    output.push_str(&format!(
+        "{:>width$}  {}{:>gap$}\n",
+        "",
+        first_label,
+        last_label,
+        width = label_width,
+        gap = cols
+            .saturating_sub(first_label.len())
+            .max(last_label.len() + 1)
+    ));
+
+    output.push_str(&format!(
+        "{:>width$}  {} actual  {} interpolated  {} rolling average (kg)\n",
+        "",
+        Marker::Actual.symbol(options.style),
+        Marker::Interpolated.symbol(options.style),
+        Marker::Average.symbol(options.style),
+        width = label_width
+    ));
+
+    output
+}
+
+/// Loads the weights and rolling averages between two dates and renders them as a text chart.
+pub fn chart_between_dates(
+    conn: &mut DbConnection,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    amount_of_days: u32,
+    options: &ChartOptions,
+) -> QueryResult<String> {
+    let weights = crate::weights_between_dates_with_interpolation(conn, start_date, end_date)?;
+    let averages =
+        crate::rolling_average_between_dates(conn, start_date, end_date, amount_of_days)?;
+
+    Ok(render_chart(&weights, &averages, options))
+}
@@ -0,0 +1,718 @@
+//! The configuration of the server and of the command line tools.
+//!
+//! Every setting has a default, and can be set in a TOML file, by an environment variable and by a
+//! command line flag, each one overriding the previous. The environment variables can also be set
+//! in a `local.env` file or, when there is none, in a `.env` file. The file is the one named by
+//! `--config`, or else by `WEIGHT_TRACKER_CONFIG`, or else `weight-tracker.toml` when it exists.
+//! See `weight-tracker.example.toml` for every setting.
+
+use axum::http::HeaderValue;
+use chrono::Duration;
+use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::{env, fmt, fs};
+
+use crate::validation::ValidationRules;
+
+/// The file read when neither `--config` nor `WEIGHT_TRACKER_CONFIG` name one, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "weight-tracker.toml";
+
+/// The environment variable naming the configuration file.
+pub const CONFIG_FILE_VAR: &str = "WEIGHT_TRACKER_CONFIG";
+
+/// The database used when `DATABASE_URL` is not set.
+pub const DEFAULT_DATABASE_URL: &str = "weight.sqlite";
+
+/// The port of the API when `SERVER_BACKEND_PORT_NUM` is not set.
+pub const DEFAULT_BACKEND_PORT: u16 = 15280;
+
+/// The port of the frontend when `SERVER_FRONTEND_PORT_NUM` is not set.
+pub const DEFAULT_FRONTEND_PORT: u16 = 14280;
+
+/// How long the requests in progress are given to complete by default when the server stops.
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECONDS: u64 = 30;
+
+/// The settings of the server and of the command line tools.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The database, `DATABASE_URL`: the path of an SQLite file, or a `postgres://` URL when
+    /// built with the `postgres` feature.
+    pub database_url: String,
+    /// The goal weight shown on the charts and in `wt tui`, `WEIGHT_GOAL_KG`.
+    pub goal_kg: Option<f64>,
+    /// How many days deleted weights are kept in the trash, `TRASH_RETENTION_DAYS`.
+    pub trash_retention_days: i64,
+    pub server: ServerConfig,
+    /// The plausibility rules of the weights to store.
+    pub validation: ValidationRules,
+    /// The files the settings were read from, for the logs.
+    #[serde(skip)]
+    pub loaded_from: Vec<PathBuf>,
+}
+
+/// The settings of the server only.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// The address both servers listen on, IPv4 or IPv6 (`::` for every interface),
+    /// `SERVER_BIND_ADDRESS`.
+    pub bind_address: IpAddr,
+    /// `SERVER_BACKEND_PORT_NUM`.
+    pub backend_port: u16,
+    /// `SERVER_FRONTEND_PORT_NUM`.
+    pub frontend_port: u16,
+    /// Serve the frontend and the API from the backend port only, `SERVER_SINGLE_PORT`.
+    pub single_port: bool,
+    /// The path under which the app is served, such as `/weight-tracker` behind a reverse proxy,
+    /// `SERVER_BASE_PATH`. Empty to serve it at the root.
+    pub base_path: String,
+    /// Serve the frontend from this directory instead of the files embedded in the binary, to see
+    /// the changes of the files without rebuilding, `SERVER_STATIC_DIR`.
+    pub static_dir: Option<PathBuf>,
+    /// The PEM file of the TLS certificate chain, `SERVER_TLS_CERT_PATH`. With
+    /// [`tls_key_path`](Self::tls_key_path), both servers use HTTPS instead of HTTP, and reload the
+    /// certificate when the files change.
+    pub tls_cert_path: Option<PathBuf>,
+    /// The PEM file of the private key of the certificate, `SERVER_TLS_KEY_PATH`.
+    pub tls_key_path: Option<PathBuf>,
+    /// A port on which plain HTTP requests are redirected to HTTPS, `SERVER_HTTPS_REDIRECT_PORT`.
+    pub https_redirect_port: Option<u16>,
+    /// The origins allowed to call the API from a browser, `CORS_ALLOWED_ORIGINS` (separated by
+    /// commas).
+    pub cors_allowed_origins: Vec<String>,
+    /// How many hours the responses to an `Idempotency-Key` are kept,
+    /// `IDEMPOTENCY_KEY_RETENTION_HOURS`.
+    pub idempotency_key_retention_hours: i64,
+    /// How many seconds an `Idempotency-Key` stays reserved by a request that never completed,
+    /// e.g. because the server handling it was stopped, `IDEMPOTENCY_PENDING_TIMEOUT_SECONDS`.
+    /// The keys of the requests still being handled by the server are never taken over.
+    pub idempotency_pending_timeout_seconds: i64,
+    /// Do not apply the pending migrations of the database when the server starts,
+    /// `SERVER_SKIP_MIGRATIONS`. The server then is not ready until they are applied with
+    /// `wt db migrate`.
+    pub skip_migrations: bool,
+    /// How many seconds the requests in progress are given to complete when the server is asked
+    /// to stop, `SERVER_SHUTDOWN_TIMEOUT_SECONDS`.
+    pub shutdown_timeout_seconds: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            database_url: String::from(DEFAULT_DATABASE_URL),
+            goal_kg: None,
+            trash_retention_days: crate::trash::DEFAULT_RETENTION_DAYS,
+            server: ServerConfig::default(),
+            validation: ValidationRules::default(),
+            loaded_from: Vec::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The base path without its trailing slash: empty, or starting with a slash.
+    pub fn base_path(&self) -> &str {
+        self.base_path.trim_end_matches('/')
+    }
+
+    /// The certificate and key files, when the servers use HTTPS.
+    pub fn tls(&self) -> Option<(&Path, &Path)> {
+        match (&self.tls_cert_path, &self.tls_key_path) {
+            (Some(cert), Some(key)) => Some((cert, key)),
+            _ => None,
+        }
+    }
+
+    /// The port of the pages: the backend one in single-port mode.
+    pub fn pages_port(&self) -> u16 {
+        match self.single_port {
+            true => self.backend_port,
+            false => self.frontend_port,
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            backend_port: DEFAULT_BACKEND_PORT,
+            frontend_port: DEFAULT_FRONTEND_PORT,
+            single_port: false,
+            base_path: String::new(),
+            static_dir: None,
+            tls_cert_path: None,
+            tls_key_path: None,
+            https_redirect_port: None,
+            cors_allowed_origins: vec![format!("http://localhost:{}", DEFAULT_FRONTEND_PORT)],
+            idempotency_key_retention_hours: crate::idempotency::DEFAULT_RETENTION_HOURS,
+            idempotency_pending_timeout_seconds:
+                crate::idempotency::DEFAULT_PENDING_TIMEOUT_SECONDS,
+            skip_migrations: false,
+            shutdown_timeout_seconds: DEFAULT_SHUTDOWN_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+/// Why the configuration could not be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The configuration file could not be read.
+    Read { path: PathBuf, message: String },
+    /// The configuration file is not valid TOML, or has unknown settings or values of the wrong
+    /// type.
+    Parse { path: PathBuf, message: String },
+    /// A setting has an invalid value. `source` is where it was set: the key in the file, the
+    /// environment variable or the flag.
+    Invalid { source: String, message: String },
+}
+
+impl ConfigError {
+    fn invalid(source: &str, message: impl Into<String>) -> Self {
+        ConfigError::Invalid {
+            source: source.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, message } => {
+                write!(
+                    f,
+                    "Cannot read the configuration file {}: {}",
+                    path.display(),
+                    message
+                )
+            }
+            ConfigError::Parse { path, message } => write!(
+                f,
+                "Invalid configuration file {}: {}",
+                path.display(),
+                message.trim_end()
+            ),
+            ConfigError::Invalid { source, message } => {
+                write!(f, "Invalid {}: {}", source, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A setting that can be overridden by an environment variable and a flag.
+struct Setting {
+    /// The key in the file, to tell the user which setting the variable or the flag is about.
+    key: &'static str,
+    var: &'static str,
+    flag: &'static str,
+    /// Whether the flag is given without a value to turn the setting on.
+    switch: bool,
+    set: fn(&mut Config, &str) -> Result<(), String>,
+}
+
+fn parse<T: std::str::FromStr>(value: &str, expected: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|_| format!("{:?} is not {}", value, expected))
+}
+
+fn parse_switch(value: &str) -> Result<bool, String> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("{:?} is not true or false", value)),
+    }
+}
+
+/// A path, or none when the value is empty, so that a variable or a flag can unset a file setting.
+fn optional_path(value: &str) -> Option<PathBuf> {
+    match value.trim() {
+        "" => None,
+        path => Some(PathBuf::from(path)),
+    }
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "database_url",
+        var: "DATABASE_URL",
+        flag: "--database-url",
+        switch: false,
+        set: |config, value| {
+            config.database_url = value.trim().to_owned();
+            Ok(())
+        },
+    },
+    Setting {
+        key: "goal_kg",
+        var: "WEIGHT_GOAL_KG",
+        flag: "--goal-kg",
+        switch: false,
+        set: |config, value| {
+            config.goal_kg = match value.trim() {
+                "" => None,
+                value => Some(parse(value, "a number")?),
+            };
+            Ok(())
+        },
+    },
+    Setting {
+        key: "trash_retention_days",
+        var: "TRASH_RETENTION_DAYS",
+        flag: "--trash-retention-days",
+        switch: false,
+        set: |config, value| {
+            config.trash_retention_days = parse(value, "a number of days")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.backend_port",
+        var: "SERVER_BACKEND_PORT_NUM",
+        flag: "--backend-port",
+        switch: false,
+        set: |config, value| {
+            config.server.backend_port = parse(value, "a port number")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.frontend_port",
+        var: "SERVER_FRONTEND_PORT_NUM",
+        flag: "--frontend-port",
+        switch: false,
+        set: |config, value| {
+            config.server.frontend_port = parse(value, "a port number")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.bind_address",
+        var: "SERVER_BIND_ADDRESS",
+        flag: "--bind-address",
+        switch: false,
+        set: |config, value| {
+            config.server.bind_address = parse(value, "an IPv4 or IPv6 address")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.single_port",
+        var: "SERVER_SINGLE_PORT",
+        flag: "--single-port",
+        switch: true,
+        set: |config, value| {
+            config.server.single_port = parse_switch(value)?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.base_path",
+        var: "SERVER_BASE_PATH",
+        flag: "--base-path",
+        switch: false,
+        set: |config, value| {
+            config.server.base_path = value.trim().to_owned();
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.static_dir",
+        var: "SERVER_STATIC_DIR",
+        flag: "--static-dir",
+        switch: false,
+        set: |config, value| {
+            config.server.static_dir = optional_path(value);
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.tls_cert_path",
+        var: "SERVER_TLS_CERT_PATH",
+        flag: "--tls-cert",
+        switch: false,
+        set: |config, value| {
+            config.server.tls_cert_path = optional_path(value);
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.tls_key_path",
+        var: "SERVER_TLS_KEY_PATH",
+        flag: "--tls-key",
+        switch: false,
+        set: |config, value| {
+            config.server.tls_key_path = optional_path(value);
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.https_redirect_port",
+        var: "SERVER_HTTPS_REDIRECT_PORT",
+        flag: "--https-redirect-port",
+        switch: false,
+        set: |config, value| {
+            config.server.https_redirect_port = match value.trim() {
+                "" => None,
+                port => Some(parse(port, "a port number")?),
+            };
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.cors_allowed_origins",
+        var: "CORS_ALLOWED_ORIGINS",
+        flag: "--cors-allowed-origins",
+        switch: false,
+        set: |config, value| {
+            config.server.cors_allowed_origins = value
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(String::from)
+                .collect();
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.idempotency_key_retention_hours",
+        var: "IDEMPOTENCY_KEY_RETENTION_HOURS",
+        flag: "--idempotency-key-retention-hours",
+        switch: false,
+        set: |config, value| {
+            config.server.idempotency_key_retention_hours = parse(value, "a number of hours")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.idempotency_pending_timeout_seconds",
+        var: "IDEMPOTENCY_PENDING_TIMEOUT_SECONDS",
+        flag: "--idempotency-pending-timeout-seconds",
+        switch: false,
+        set: |config, value| {
+            config.server.idempotency_pending_timeout_seconds =
+                parse(value, "a number of seconds")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.skip_migrations",
+        var: "SERVER_SKIP_MIGRATIONS",
+        flag: "--no-migrate",
+        switch: true,
+        set: |config, value| {
+            config.server.skip_migrations = parse_switch(value)?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "server.shutdown_timeout_seconds",
+        var: "SERVER_SHUTDOWN_TIMEOUT_SECONDS",
+        flag: "--shutdown-timeout-seconds",
+        switch: false,
+        set: |config, value| {
+            config.server.shutdown_timeout_seconds = parse(value, "a number of seconds")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "validation.min_kg",
+        var: "WEIGHT_MIN_KG",
+        flag: "--min-kg",
+        switch: false,
+        set: |config, value| {
+            config.validation.min_kg = parse(value, "a number")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "validation.max_kg",
+        var: "WEIGHT_MAX_KG",
+        flag: "--max-kg",
+        switch: false,
+        set: |config, value| {
+            config.validation.max_kg = parse(value, "a number")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "validation.max_daily_change_kg",
+        var: "WEIGHT_MAX_DAILY_CHANGE_KG",
+        flag: "--max-daily-change-kg",
+        switch: false,
+        set: |config, value| {
+            config.validation.max_daily_change_kg = parse(value, "a number")?;
+            Ok(())
+        },
+    },
+    Setting {
+        key: "validation.future_tolerance_days",
+        var: "WEIGHT_FUTURE_TOLERANCE_DAYS",
+        flag: "--future-tolerance-days",
+        switch: false,
+        set: |config, value| {
+            config.validation.future_tolerance_days = parse(value, "a number of days")?;
+            Ok(())
+        },
+    },
+];
+
+/// The flag naming the configuration file.
+pub const CONFIG_FLAG: &str = "--config";
+
+/// Takes a flag and its value out of the arguments, as `--flag VALUE` or `--flag=VALUE`, or just
+/// `--flag` for a switch. The last occurrence wins.
+fn take_flag(
+    args: &mut Vec<String>,
+    flag: &str,
+    switch: bool,
+) -> Result<Option<String>, ConfigError> {
+    let mut value = None;
+    while let Some(position) = args
+        .iter()
+        .position(|arg| arg == flag || arg.starts_with(&format!("{}=", flag)))
+    {
+        let arg = args.remove(position);
+        value = Some(match arg.split_once('=') {
+            Some((_, value)) => value.to_owned(),
+            None if switch => String::from("true"),
+            None if position < args.len() => args.remove(position),
+            None => return Err(ConfigError::invalid(flag, "missing value")),
+        });
+    }
+    Ok(value)
+}
+
+impl Config {
+    /// Reads a configuration file over the defaults.
+    pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|err| ConfigError::Read {
+            path: path.to_owned(),
+            message: err.to_string(),
+        })?;
+        let mut config = Config::from_toml(&text).map_err(|message| ConfigError::Parse {
+            path: path.to_owned(),
+            message,
+        })?;
+        config.loaded_from.push(path.to_owned());
+        Ok(config)
+    }
+
+    /// Parses the content of a configuration file over the defaults.
+    pub fn from_toml(text: &str) -> Result<Config, String> {
+        toml::from_str(text).map_err(|err| err.to_string())
+    }
+
+    /// Overrides the settings with the environment variables returned by `lookup`.
+    pub fn apply_env(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), ConfigError> {
+        for setting in SETTINGS {
+            if let Some(value) = lookup(setting.var) {
+                (setting.set)(self, &value).map_err(|message| {
+                    ConfigError::invalid(&format!("{} ({})", setting.var, setting.key), message)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Overrides the settings with the flags found in the arguments, and removes them from the
+    /// arguments.
+    pub fn apply_flags(&mut self, args: &mut Vec<String>) -> Result<(), ConfigError> {
+        for setting in SETTINGS {
+            if let Some(value) = take_flag(args, setting.flag, setting.switch)? {
+                (setting.set)(self, &value).map_err(|message| {
+                    ConfigError::invalid(&format!("{} ({})", setting.flag, setting.key), message)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the configuration of a tool from the file, the environment and its arguments, and
+    /// checks it. The `--config` flag and the setting flags are removed from the arguments, which
+    /// are left to the tool.
+    pub fn load(args: &mut Vec<String>) -> Result<Config, ConfigError> {
+        let env_file = dotenvy::from_filename("local.env").or_else(|_| dotenvy::dotenv());
+
+        let path = match take_flag(args, CONFIG_FLAG, false)? {
+            Some(path) => Some(PathBuf::from(path)),
+            None => env::var_os(CONFIG_FILE_VAR).map(PathBuf::from),
+        };
+        let mut config = match path {
+            Some(path) => Config::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Config::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Config::default(),
+        };
+        if let Ok(env_file) = env_file {
+            config.loaded_from.push(env_file);
+        }
+
+        config.apply_env(|name| env::var(name).ok())?;
+        config.apply_flags(args)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the settings against each other and their bounds.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.database_url.trim().is_empty() {
+            return Err(ConfigError::invalid("database_url", "must not be empty"));
+        }
+        if self
+            .goal_kg
+            .is_some_and(|goal| !goal.is_finite() || goal <= 0.0)
+        {
+            return Err(ConfigError::invalid("goal_kg", "must be a positive number"));
+        }
+        if !(0..=crate::trash::MAX_RETENTION_DAYS).contains(&self.trash_retention_days) {
+            return Err(ConfigError::invalid(
+                "trash_retention_days",
+                format!(
+                    "must be between 0 and {} days",
+                    crate::trash::MAX_RETENTION_DAYS
+                ),
+            ));
+        }
+
+        let server = &self.server;
+        if server.backend_port == 0 || server.frontend_port == 0 {
+            return Err(ConfigError::invalid(
+                "server.backend_port",
+                "the ports must not be 0",
+            ));
+        }
+        if server.backend_port == server.frontend_port && !server.single_port {
+            return Err(ConfigError::invalid(
+                "server.backend_port",
+                format!(
+                    "the backend and the frontend cannot both use port {}",
+                    server.backend_port
+                ),
+            ));
+        }
+        if server.tls_cert_path.is_some() != server.tls_key_path.is_some() {
+            return Err(ConfigError::invalid(
+                "server.tls_cert_path",
+                "the certificate and the key must be set together",
+            ));
+        }
+        if let Some(port) = server.https_redirect_port {
+            if server.tls().is_none() {
+                return Err(ConfigError::invalid(
+                    "server.https_redirect_port",
+                    "redirecting to HTTPS requires a TLS certificate and key",
+                ));
+            }
+            if port == 0 || port == server.backend_port || port == server.pages_port() {
+                return Err(ConfigError::invalid(
+                    "server.https_redirect_port",
+                    format!("port {} is 0 or already used by the servers", port),
+                ));
+            }
+        }
+        let base_path_valid = server.base_path.is_empty()
+            || (server.base_path.starts_with('/')
+                && !server.base_path.contains("//")
+                && server
+                    .base_path
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "/-._~".contains(c)));
+        if !base_path_valid {
+            return Err(ConfigError::invalid(
+                "server.base_path",
+                format!(
+                    "{:?} is not a path such as /weight-tracker",
+                    server.base_path
+                ),
+            ));
+        }
+        for origin in &server.cors_allowed_origins {
+            let valid = (origin.starts_with("http://") || origin.starts_with("https://"))
+                && HeaderValue::from_str(origin).is_ok();
+            if !valid {
+                return Err(ConfigError::invalid(
+                    "server.cors_allowed_origins",
+                    format!(
+                        "{:?} is not an origin such as http://localhost:14280",
+                        origin
+                    ),
+                ));
+            }
+        }
+        if server.idempotency_key_retention_hours <= 0 {
+            return Err(ConfigError::invalid(
+                "server.idempotency_key_retention_hours",
+                "must be positive",
+            ));
+        }
+        if server.idempotency_pending_timeout_seconds <= 0 {
+            return Err(ConfigError::invalid(
+                "server.idempotency_pending_timeout_seconds",
+                "must be positive",
+            ));
+        }
+
+        self.validation
+            .validate()
+            .map_err(|message| ConfigError::invalid("validation", message))
+    }
+
+    /// How long deleted weights are kept in the trash.
+    pub fn trash_retention(&self) -> Duration {
+        Duration::days(self.trash_retention_days)
+    }
+
+    /// How long the responses to an `Idempotency-Key` are kept.
+    pub fn idempotency_key_retention(&self) -> Duration {
+        Duration::hours(self.server.idempotency_key_retention_hours)
+    }
+
+    /// How long an `Idempotency-Key` stays reserved by a request that never completed.
+    pub fn idempotency_pending_timeout(&self) -> Duration {
+        Duration::seconds(self.server.idempotency_pending_timeout_seconds)
+    }
+
+    /// How long the requests in progress are given to complete when the server stops.
+    pub fn shutdown_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.server.shutdown_timeout_seconds)
+    }
+}
+
+static CURRENT: OnceLock<Config> = OnceLock::new();
+
+/// Loads the configuration of a tool (see [`Config::load`]) and makes it the [`current`] one.
+pub fn init(args: &mut Vec<String>) -> Result<&'static Config, ConfigError> {
+    let config = Config::load(args)?;
+    Ok(CURRENT.get_or_init(|| config))
+}
+
+/// Like [`init`], printing the error and exiting when the configuration is invalid, for the
+/// `main` of the tools.
+pub fn init_or_exit(args: &mut Vec<String>) -> &'static Config {
+    init(args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+/// The configuration loaded by [`init`]. A program that did not call it gets the configuration
+/// from the file and the environment, without flags.
+///
+/// # Panics
+///
+/// When that configuration is invalid.
+pub fn current() -> &'static Config {
+    CURRENT.get_or_init(|| Config::load(&mut Vec::new()).unwrap_or_else(|err| panic!("{}", err)))
+}
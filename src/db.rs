@@ -0,0 +1,152 @@
+//! The database backends: SQLite, always available, and PostgreSQL with the `postgres` feature.
+//! The backend is chosen by the scheme of the database URL: `postgres://` and `postgresql://` URLs
+//! are PostgreSQL databases, anything else is the path of an SQLite database.
+//!
+//! The queries are written once for [`DbConnection`], which runs them on either backend. The few
+//! statements without a common syntax, such as `INSERT ... ON CONFLICT`, are run on the connection
+//! of each backend with `on_backend!`.
+
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+
+#[cfg(feature = "postgres")]
+use diesel::pg::PgConnection;
+
+/// A connection to the database, of either backend.
+#[derive(diesel::MultiConnection)]
+pub enum DbConnection {
+    Sqlite(SqliteConnection),
+    #[cfg(feature = "postgres")]
+    Pg(PgConnection),
+}
+
+/// The backend of the queries run on a [`DbConnection`].
+pub type Backend = <DbConnection as Connection>::Backend;
+
+/// Runs a statement on the connection of the backend, for the statements that [`DbConnection`]
+/// cannot run itself: the body is compiled once for each backend, with `$inner` bound to the
+/// connection of the backend.
+macro_rules! on_backend {
+    ($conn:expr, |$inner:ident| $body:expr) => {
+        match $conn {
+            $crate::db::DbConnection::Sqlite($inner) => $body,
+            #[cfg(feature = "postgres")]
+            $crate::db::DbConnection::Pg($inner) => $body,
+        }
+    };
+}
+pub(crate) use on_backend;
+
+/// How long a connection waits for another connection to release its lock on an SQLite database
+/// before failing with "database is locked".
+const BUSY_TIMEOUT_MS: u32 = 5000;
+
+/// Whether a database URL names a PostgreSQL database.
+pub fn is_postgres_url(database_url: &str) -> bool {
+    database_url.starts_with("postgres://") || database_url.starts_with("postgresql://")
+}
+
+/// Opens a connection to a database, on the backend given by the scheme of its URL. Concurrent
+/// writers wait for each other instead of failing right away, and SQLite enforces the foreign keys,
+/// which it does not by default, so that removing a weight removes its tags like on PostgreSQL.
+pub fn connect(database_url: &str) -> ConnectionResult<DbConnection> {
+    if is_postgres_url(database_url) {
+        return connect_postgres(database_url);
+    }
+    let path = database_url
+        .strip_prefix("sqlite://")
+        .unwrap_or(database_url);
+    let mut conn = SqliteConnection::establish(path)?;
+    diesel::sql_query(format!("PRAGMA busy_timeout = {}", BUSY_TIMEOUT_MS))
+        .execute(&mut conn)
+        .map_err(ConnectionError::CouldntSetupConfiguration)?;
+    diesel::sql_query("PRAGMA foreign_keys = ON")
+        .execute(&mut conn)
+        .map_err(ConnectionError::CouldntSetupConfiguration)?;
+    Ok(DbConnection::Sqlite(conn))
+}
+
+#[cfg(feature = "postgres")]
+fn connect_postgres(database_url: &str) -> ConnectionResult<DbConnection> {
+    PgConnection::establish(database_url).map(DbConnection::Pg)
+}
+
+#[cfg(not(feature = "postgres"))]
+fn connect_postgres(_database_url: &str) -> ConnectionResult<DbConnection> {
+    Err(ConnectionError::BadConnection(String::from(
+        "PostgreSQL support is not compiled in, build with --features postgres",
+    )))
+}
+
+/// The key of the PostgreSQL advisory lock taken by the transactions meant to write.
+#[cfg(feature = "postgres")]
+const WRITE_LOCK_KEY: i64 = 0x7765_6967_6874;
+
+/// Runs a function in a transaction meant to write, committed when it returns `Ok` and rolled back
+/// otherwise. The transaction takes the write lock of the database right away, so that two writers
+/// wait for each other instead of failing, or overwriting each other, when the second one writes
+/// after reading: `BEGIN IMMEDIATE` on SQLite, an advisory lock held until the end of the
+/// transaction on PostgreSQL.
+pub fn write_transaction<T, E, F>(conn: &mut DbConnection, f: F) -> Result<T, E>
+where
+    F: FnOnce(&mut DbConnection) -> Result<T, E>,
+    E: From<diesel::result::Error>,
+{
+    match conn {
+        DbConnection::Sqlite(inner) => {
+            AnsiTransactionManager::begin_transaction_sql(inner, "BEGIN IMMEDIATE")?
+        }
+        #[cfg(feature = "postgres")]
+        DbConnection::Pg(inner) => {
+            AnsiTransactionManager::begin_transaction(inner)?;
+            let locked =
+                diesel::sql_query(format!("SELECT pg_advisory_xact_lock({})", WRITE_LOCK_KEY))
+                    .execute(inner);
+            if let Err(err) = locked {
+                AnsiTransactionManager::rollback_transaction(inner)?;
+                return Err(err.into());
+            }
+        }
+    }
+    finish_transaction(conn, f)
+}
+
+/// Runs a function that only reads in a transaction, so that all its queries see the database as
+/// it was when the first one ran, whatever is written in the meantime: a deferred transaction on
+/// SQLite, a `REPEATABLE READ` one on PostgreSQL.
+pub fn read_transaction<T, E, F>(conn: &mut DbConnection, f: F) -> Result<T, E>
+where
+    F: FnOnce(&mut DbConnection) -> Result<T, E>,
+    E: From<diesel::result::Error>,
+{
+    match conn {
+        DbConnection::Sqlite(inner) => {
+            AnsiTransactionManager::begin_transaction_sql(inner, "BEGIN")?
+        }
+        #[cfg(feature = "postgres")]
+        DbConnection::Pg(inner) => AnsiTransactionManager::begin_transaction_sql(
+            inner,
+            "BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY",
+        )?,
+    }
+    finish_transaction(conn, f)
+}
+
+/// Runs a function in the transaction just begun, then commits or rolls it back.
+fn finish_transaction<T, E, F>(conn: &mut DbConnection, f: F) -> Result<T, E>
+where
+    F: FnOnce(&mut DbConnection) -> Result<T, E>,
+    E: From<diesel::result::Error>,
+{
+    match f(conn) {
+        Ok(value) => {
+            DbConnection::commit_transaction(conn)?;
+            Ok(value)
+        }
+        Err(err) => {
+            DbConnection::rollback_transaction(conn)?;
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Events of the timeline, separate from the notes of single weights: periods such as diet
+//! phases, bulk and cut cycles or injuries, and one-day markers such as a medication change.
+//!
+//! The weights measured during a period are summarized by [`stats::phase_summary`].
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use std::{fmt, str::FromStr};
+
+use crate::db::{on_backend, DbConnection};
+use crate::models::{Event, NewEvent};
+use crate::stats::{self, PhaseSummary};
+
+/// The longest title accepted, in characters.
+pub const MAX_TITLE_LENGTH: usize = 100;
+
+/// What an event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Diet,
+    Bulk,
+    Cut,
+    Injury,
+    Medication,
+    Other,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 6] = [
+        EventKind::Diet,
+        EventKind::Bulk,
+        EventKind::Cut,
+        EventKind::Injury,
+        EventKind::Medication,
+        EventKind::Other,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Diet => "diet",
+            EventKind::Bulk => "bulk",
+            EventKind::Cut => "cut",
+            EventKind::Injury => "injury",
+            EventKind::Medication => "medication",
+            EventKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        EventKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+            .ok_or_else(|| {
+                let kinds: Vec<&str> = EventKind::ALL.iter().map(|kind| kind.as_str()).collect();
+                format!("kind must be one of {}", kinds.join(", "))
+            })
+    }
+}
+
+impl NewEvent {
+    /// An event of the given kind, from `start_date` to `end_date` (inclusive). Without an end
+    /// date the event is still going on; a one-day marker ends on the day it starts.
+    pub fn new(
+        kind: EventKind,
+        title: &str,
+        start_date: NaiveDate,
+        end_date: Option<NaiveDate>,
+    ) -> Self {
+        NewEvent {
+            kind: kind.as_str().to_owned(),
+            title: title.trim().to_owned(),
+            start_date,
+            end_date,
+            note: None,
+        }
+    }
+
+    /// Checks the title and the dates, returning a message for the user when they are invalid.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.title.trim().is_empty() {
+            return Err(String::from("title must not be empty"));
+        }
+        if self.title.chars().count() > MAX_TITLE_LENGTH {
+            return Err(format!(
+                "title must not be longer than {} characters",
+                MAX_TITLE_LENGTH
+            ));
+        }
+        if self
+            .end_date
+            .is_some_and(|end_date| end_date < self.start_date)
+        {
+            return Err(String::from("end_date must not be before start_date"));
+        }
+        Ok(())
+    }
+}
+
+impl Event {
+    /// The last day of the event, or `today` while it is still going on.
+    pub fn end_or(&self, today: NaiveDate) -> NaiveDate {
+        self.end_date.unwrap_or(today).max(self.start_date)
+    }
+}
+
+/// Stores a new event and returns it with its id.
+pub fn add_event(conn: &mut DbConnection, event: &NewEvent) -> QueryResult<Event> {
+    use crate::schema::events::dsl::*;
+
+    // The selection depends on the backend, so the statement is built for each of them.
+    on_backend!(conn, |conn| diesel::insert_into(events)
+        .values(event)
+        .returning(Event::as_returning())
+        .get_result(conn))
+}
+
+/// Every event, by start date.
+pub fn all_events(conn: &mut DbConnection) -> QueryResult<Vec<Event>> {
+    use crate::schema::events::dsl::*;
+
+    events
+        .select(Event::as_select())
+        .order((start_date.asc(), id.asc()))
+        .load(conn)
+}
+
+/// The events overlapping the interval between two dates (inclusive), by start date. Events
+/// that are still going on overlap every interval after their start.
+pub fn events_between_dates(
+    conn: &mut DbConnection,
+    interval_start: NaiveDate,
+    interval_end: NaiveDate,
+) -> QueryResult<Vec<Event>> {
+    use crate::schema::events::dsl::*;
+
+    events
+        .select(Event::as_select())
+        .filter(start_date.le(interval_end))
+        .filter(end_date.is_null().or(end_date.ge(interval_start)))
+        .order((start_date.asc(), id.asc()))
+        .load(conn)
+}
+
+/// Deletes an event. Returns the number of events deleted (0 when the id is unknown).
+pub fn delete_event(conn: &mut DbConnection, event_id: i32) -> QueryResult<usize> {
+    use crate::schema::events::dsl::*;
+
+    diesel::delete(events.find(event_id)).execute(conn)
+}
+
+/// Summarizes the weights measured during each of the events, in the same order. Events that are
+/// still going on are summarized up to `today`.
+pub fn phase_summaries(
+    conn: &mut DbConnection,
+    events: &[Event],
+    today: NaiveDate,
+) -> QueryResult<Vec<Option<PhaseSummary>>> {
+    let (Some(first), Some(last)) = (
+        events.iter().map(|event| event.start_date).min(),
+        events.iter().map(|event| event.end_or(today)).max(),
+    ) else {
+        return Ok(Vec::new());
+    };
+
+    let weights = crate::weights_between_dates(conn, first, last)?;
+    Ok(events
+        .iter()
+        .map(|event| stats::phase_summary(&weights, event.start_date, event.end_or(today)))
+        .collect())
+}
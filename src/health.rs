@@ -0,0 +1,71 @@
+//! Probes for the orchestration of the server, such as the liveness and readiness probes of
+//! Kubernetes: `/healthz` answers as long as the process serves requests, `/readyz` only when it
+//! can also handle them, that is when the database is reachable and its schema is up to date.
+
+use axum::http::StatusCode;
+use axum::Json;
+use diesel::connection::SimpleConnection;
+use serde::Serialize;
+
+/// The body of the probe responses.
+#[derive(Debug, Serialize)]
+pub struct ProbeStatus {
+    /// `ok`, `ready` or `not_ready`.
+    pub status: &'static str,
+    /// Why the server is not ready.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Whether the database of the [current configuration](crate::config::current) can be used: it
+/// accepts connections and queries, every migration is applied and none is unknown to this binary.
+/// The check only reads the database, which a probe must not change: a database whose migrations
+/// were never applied is not ready. It blocks while it connects and queries.
+pub fn check_database() -> Result<(), String> {
+    let database_url = &crate::config::current().database_url;
+    let mut conn = crate::connect(database_url)
+        .map_err(|err| format!("Cannot connect to the database: {}", err))?;
+    conn.batch_execute("SELECT 1")
+        .map_err(|err| format!("Cannot query the database: {}", err))?;
+    crate::migrations::check_not_newer(&mut conn).map_err(|err| err.to_string())?;
+    let pending = crate::migrations::pending(&mut conn)
+        .map_err(|err| format!("Cannot read the migrations of the database: {}", err))?;
+    match pending.is_empty() {
+        true => Ok(()),
+        false => Err(format!("Pending migrations: {}", pending.join(", "))),
+    }
+}
+
+/// Liveness: the process is up and serving requests.
+pub async fn healthz() -> Json<ProbeStatus> {
+    Json(ProbeStatus {
+        status: "ok",
+        reason: None,
+    })
+}
+
+/// Readiness: `503 Service Unavailable` while the database cannot be used.
+pub async fn readyz() -> (StatusCode, Json<ProbeStatus>) {
+    let checked = tokio::task::spawn_blocking(check_database)
+        .await
+        .unwrap_or_else(|err| Err(format!("The database check failed: {}", err)));
+    match checked {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ProbeStatus {
+                status: "ready",
+                reason: None,
+            }),
+        ),
+        Err(reason) => {
+            tracing::warn!("Not ready: {}", reason);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ProbeStatus {
+                    status: "not_ready",
+                    reason: Some(reason),
+                }),
+            )
+        }
+    }
+}
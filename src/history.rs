@@ -0,0 +1,142 @@
+use chrono::{NaiveDate, Utc};
+use diesel::prelude::*;
+use std::env;
+
+use crate::db::{write_transaction, DbConnection};
+use crate::models::{NewWeightChange, WeightChange};
+
+/// Who made a change, and through which tool. Every write records its origin in the history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    /// The tool that made the change: `cli:<command>`, `api` or `import:<file name>`.
+    pub source: String,
+    /// The user, or the API client, that made the change.
+    pub actor: String,
+}
+
+impl Origin {
+    pub fn new(source: impl Into<String>, actor: impl Into<String>) -> Self {
+        Origin {
+            source: source.into(),
+            actor: actor.into(),
+        }
+    }
+
+    /// A change made by the local user with a command line tool.
+    pub fn cli(command: &str) -> Self {
+        Origin::new(format!("cli:{}", command), local_user())
+    }
+
+    /// A change made by the local user by importing a file.
+    pub fn import(file_name: &str) -> Self {
+        Origin::new(format!("import:{}", file_name), local_user())
+    }
+
+    /// A change made through the HTTP API.
+    pub fn api(actor: impl Into<String>) -> Self {
+        Origin::new("api", actor)
+    }
+}
+
+fn local_user() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| String::from("unknown"))
+}
+
+/// The kind of a change in the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeAction {
+    Insert,
+    Update,
+    /// The weight was moved to the trash.
+    Delete,
+    /// The weight was taken out of the trash.
+    Restore,
+    /// The weight was removed from the trash for good.
+    Purge,
+}
+
+impl ChangeAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeAction::Insert => "insert",
+            ChangeAction::Update => "update",
+            ChangeAction::Delete => "delete",
+            ChangeAction::Restore => "restore",
+            ChangeAction::Purge => "purge",
+        }
+    }
+}
+
+/// Adds a change to the history. Called by every write, in the transaction of the write.
+pub(crate) fn record(
+    conn: &mut DbConnection,
+    measurement_date: NaiveDate,
+    action: ChangeAction,
+    old_value: Option<f64>,
+    new_value: Option<f64>,
+    origin: &Origin,
+) -> QueryResult<usize> {
+    use crate::schema::weight_history;
+
+    diesel::insert_into(weight_history::table)
+        .values(NewWeightChange {
+            measurement_date,
+            action: action.as_str(),
+            old_value,
+            new_value,
+            changed_at: Utc::now().naive_utc(),
+            source: &origin.source,
+            actor: &origin.actor,
+        })
+        .execute(conn)
+}
+
+/// The changes of the weight of a day, oldest first.
+pub fn history_for_date(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+) -> QueryResult<Vec<WeightChange>> {
+    use crate::schema::weight_history::dsl::*;
+    weight_history
+        .filter(measurement_date.eq(in_measurement_date))
+        .order(id.asc())
+        .load::<WeightChange>(conn)
+}
+
+/// Restores the weight of a day to what it was right after the given change of its history.
+/// Reverting to a delete deletes the weight again. The revert is recorded as a new change, unless
+/// the weight already is in that state: reverting to the current version changes and records
+/// nothing, like storing a weight that is already stored.
+///
+/// Returns `Ok(None)` when the change does not exist or belongs to another day.
+pub fn revert_to_change(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+    change_id: i32,
+    origin: &Origin,
+) -> QueryResult<Option<WeightChange>> {
+    use crate::schema::weight_history::dsl::*;
+
+    write_transaction(conn, |conn| {
+        let change = weight_history
+            .find(change_id)
+            .filter(measurement_date.eq(in_measurement_date))
+            .first::<WeightChange>(conn)
+            .optional()?;
+        let Some(change) = change else {
+            return Ok(None);
+        };
+
+        match change.new_value {
+            Some(value) => {
+                crate::upsert_in_transaction(conn, value, in_measurement_date, origin)?;
+            }
+            None => {
+                crate::delete_in_transaction(conn, in_measurement_date, origin)?;
+            }
+        }
+        Ok(Some(change))
+    })
+}
@@ -0,0 +1,187 @@
+//! Idempotency keys of write requests: a client that retries a request with the same key gets
+//! the response of the first attempt instead of applying the request twice.
+//!
+//! A key is reserved when its first request starts and holds the response once it completes.
+//! Keys are kept for a retention period, after which they can be used again. A reservation whose
+//! request never completed, e.g. because the server was stopped while handling it, is given up
+//! after a pending timeout.
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::db::{write_transaction, DbConnection};
+
+/// How long keys are kept by default, see
+/// [`Config::idempotency_key_retention`](crate::config::Config::idempotency_key_retention).
+pub const DEFAULT_RETENTION_HOURS: i64 = 24;
+
+/// The longest key accepted, in characters.
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// How long a key stays reserved by default by a request that never completed, see
+/// [`Config::idempotency_pending_timeout`](crate::config::Config::idempotency_pending_timeout).
+pub const DEFAULT_PENDING_TIMEOUT_SECONDS: i64 = 60;
+
+/// Keys are chosen by the clients, usually a UUID: any printable ASCII text up to
+/// [`MAX_KEY_LENGTH`] characters is accepted.
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= MAX_KEY_LENGTH
+        && key
+            .bytes()
+            .all(|byte| byte.is_ascii_graphic() || byte == b' ')
+}
+
+/// A fingerprint of a request, to tell a retry from another request reusing the same key.
+pub fn request_hash(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The response stored for a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status_code: u16,
+    pub body: String,
+    /// The headers to send again with the body, e.g. its `Content-Type` and `ETag`, as names and
+    /// values.
+    pub headers: Vec<(String, String)>,
+}
+
+/// Headers are stored one per line, as `name: value`: header values cannot contain line breaks.
+fn encode_headers(headers: &[(String, String)]) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_headers(headers: &str) -> Vec<(String, String)> {
+    headers
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        .collect()
+}
+
+/// What to do with a request carrying an idempotency key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reservation {
+    /// The key is new: handle the request, then [`complete`] or [`release`] the key.
+    Reserved,
+    /// The request was already handled: send its response again.
+    Completed(StoredResponse),
+    /// The first request with this key is still being handled.
+    InProgress,
+    /// The key was used for a different request.
+    Mismatch,
+}
+
+/// Reserves a key for a request, unless it is known already. A reservation older than
+/// `pending_timeout` that still has no response is taken over by the new request.
+pub fn reserve(
+    conn: &mut DbConnection,
+    key: &str,
+    hash: &str,
+    retention: Duration,
+    pending_timeout: Duration,
+) -> QueryResult<Reservation> {
+    use crate::schema::idempotency_keys::dsl::*;
+
+    write_transaction(conn, |conn| {
+        let now = Utc::now().naive_utc();
+        let existing = idempotency_keys
+            .find(key)
+            .select((
+                request_hash,
+                status_code,
+                response_body,
+                response_headers,
+                created_at,
+            ))
+            .first::<(
+                String,
+                Option<i32>,
+                Option<String>,
+                Option<String>,
+                NaiveDateTime,
+            )>(conn)
+            .optional()?;
+
+        if let Some((stored_hash, stored_status, stored_body, stored_headers, reserved_at)) =
+            existing
+        {
+            let expired = reserved_at < now - retention;
+            let abandoned = stored_status.is_none() && reserved_at < now - pending_timeout;
+            if !expired && !abandoned {
+                return Ok(match (stored_status, stored_body) {
+                    _ if stored_hash != hash => Reservation::Mismatch,
+                    (Some(stored_status), Some(stored_body)) => {
+                        Reservation::Completed(StoredResponse {
+                            status_code: stored_status as u16,
+                            body: stored_body,
+                            headers: stored_headers
+                                .as_deref()
+                                .map(decode_headers)
+                                .unwrap_or_default(),
+                        })
+                    }
+                    _ => Reservation::InProgress,
+                });
+            }
+            diesel::delete(idempotency_keys.find(key)).execute(conn)?;
+        }
+
+        diesel::insert_into(idempotency_keys)
+            .values((
+                idempotency_key.eq(key),
+                request_hash.eq(hash),
+                created_at.eq(now),
+            ))
+            .execute(conn)?;
+        Ok(Reservation::Reserved)
+    })
+}
+
+/// Stores the response of the request that reserved a key.
+pub fn complete(
+    conn: &mut DbConnection,
+    key: &str,
+    response: &StoredResponse,
+) -> QueryResult<usize> {
+    use crate::schema::idempotency_keys::dsl::*;
+
+    diesel::update(idempotency_keys.find(key))
+        .set((
+            status_code.eq(response.status_code as i32),
+            response_body.eq(&response.body),
+            response_headers.eq(encode_headers(&response.headers)),
+        ))
+        .execute(conn)
+}
+
+/// Frees a key whose request failed, so that a retry is handled as a new request.
+pub fn release(conn: &mut DbConnection, key: &str) -> QueryResult<usize> {
+    use crate::schema::idempotency_keys::dsl::*;
+
+    diesel::delete(idempotency_keys.find(key)).execute(conn)
+}
+
+/// Removes the keys older than the retention period. Returns the number of keys removed.
+pub fn purge_expired(conn: &mut DbConnection, retention: Duration) -> QueryResult<usize> {
+    use crate::schema::idempotency_keys::dsl::*;
+
+    let cutoff = Utc::now().naive_utc() - retention;
+    diesel::delete(idempotency_keys.filter(created_at.lt(cutoff))).execute(conn)
+}
@@ -1,34 +1,73 @@
+pub mod annotations;
+pub mod api;
+pub mod assets;
+pub mod chart;
+pub mod config;
+pub mod db;
+pub mod events;
+pub mod health;
+pub mod history;
+pub mod idempotency;
+pub mod migrations;
 pub mod models;
+pub mod output;
+pub mod report;
 pub mod schema;
+pub mod server;
+pub mod stats;
+pub mod store;
+pub mod trash;
+pub mod validation;
+pub mod versions;
 
 use chrono::NaiveDate;
-pub use models::{NewWeight, Weight};
+use history::ChangeAction;
+pub use history::Origin;
+pub use models::{Event, NewEvent, NewWeight, Weight, WeightChange};
 
-use diesel::{prelude::*, SqliteConnection};
-use dotenvy::dotenv;
-use std::{env, fmt};
+use diesel::prelude::*;
+use std::fmt;
 
-pub fn establish_connection() -> SqliteConnection {
-    dotenv().ok();
+use crate::annotations::AnnotationError;
+use crate::db::{on_backend, write_transaction, DbConnection};
+use crate::store::WeightStore;
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    SqliteConnection::establish(&database_url)
-        .expect(&format!("Error connecting to {}", database_url))
+/// Opens a connection to the database of the [current configuration](config::current).
+pub fn establish_connection() -> DbConnection {
+    let database_url = &config::current().database_url;
+    connect(database_url).unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
+}
+
+/// Opens a connection to the given database, SQLite or PostgreSQL, see [`db::connect`].
+pub fn connect(database_url: &str) -> ConnectionResult<DbConnection> {
+    db::connect(database_url)
 }
 
 pub fn add_weight(
-    conn: &mut SqliteConnection,
+    conn: &mut DbConnection,
     weight_value: f64,
     measurement_date: NaiveDate,
+    origin: &Origin,
 ) -> Result<usize, diesel::result::Error> {
     let new_weight = NewWeight {
         weight_value,
         measurement_date,
     };
     use schema::weights;
-    diesel::insert_into(weights::table)
-        .values(&new_weight)
-        .execute(conn)
+    write_transaction(conn, |conn| {
+        let count = diesel::insert_into(weights::table)
+            .values(&new_weight)
+            .execute(conn)?;
+        history::record(
+            conn,
+            measurement_date,
+            ChangeAction::Insert,
+            None,
+            Some(weight_value),
+            origin,
+        )?;
+        Ok(count)
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -54,12 +93,12 @@ impl From<std::num::ParseIntError> for LocalParseError {
 impl From<diesel::result::Error> for LocalParseError {
     fn from(err: diesel::result::Error) -> LocalParseError {
         LocalParseError {
-            message: format!("Some db error {}", err.to_string()),
+            message: format!("Some db error {}", err),
         }
     }
 }
 
-fn parse_date(date_string: &str) -> Result<NaiveDate, LocalParseError> {
+pub fn parse_date(date_string: &str) -> Result<NaiveDate, LocalParseError> {
     let mut parts = date_string.split('-');
     let year_str = parts.next().ok_or(LocalParseError {
         message: String::from("year problem"),
@@ -83,60 +122,296 @@ fn parse_date(date_string: &str) -> Result<NaiveDate, LocalParseError> {
     })
 }
 
+/// What storing the weight of a day did to the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    /// There was no weight for the day.
+    Inserted,
+    /// The day had a different weight, which was replaced.
+    Updated,
+    /// The day already had the same weight.
+    Unchanged,
+}
+
+impl UpsertOutcome {
+    /// The number of rows written: 0 when the weight was unchanged, 1 otherwise.
+    pub fn rows(self) -> usize {
+        match self {
+            UpsertOutcome::Unchanged => 0,
+            UpsertOutcome::Inserted | UpsertOutcome::Updated => 1,
+        }
+    }
+}
+
+/// Stores the weight of a day, replacing any other weight of that day.
+///
+/// The write is a single `INSERT ... ON CONFLICT(measurement_date) DO UPDATE`, so concurrent
+/// writers of the same day cannot run into the `UNIQUE` constraint. The previous weight is read
+/// in the same [write transaction](db::write_transaction), which holds the write lock from the
+/// start, to tell the outcomes apart.
+pub fn upsert_weight_with_outcome(
+    conn: &mut DbConnection,
+    in_weight_value: f64,
+    in_measurement_date: NaiveDate,
+    origin: &Origin,
+) -> QueryResult<UpsertOutcome> {
+    write_transaction(conn, |conn| {
+        upsert_in_transaction(conn, in_weight_value, in_measurement_date, origin)
+    })
+}
+
+/// Like [`upsert_weight_with_outcome`], returning the number of rows written.
 pub fn upsert_weight(
-    conn: &mut SqliteConnection,
+    conn: &mut DbConnection,
     in_weight_value: f64,
     in_measurement_date: NaiveDate,
+    origin: &Origin,
 ) -> QueryResult<usize> {
+    upsert_weight_with_outcome(conn, in_weight_value, in_measurement_date, origin)
+        .map(UpsertOutcome::rows)
+}
+
+/// The weight of a day to store, with an optional note and tags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewMeasurement {
+    pub measurement_date: NaiveDate,
+    pub weight_value: f64,
+    /// Replaces the note of the day when set; an empty note removes it.
+    pub note: Option<String>,
+    /// Replaces the tags of the day when set.
+    pub tags: Option<Vec<String>>,
+}
+
+impl NewMeasurement {
+    pub fn new(weight_value: f64, measurement_date: NaiveDate) -> Self {
+        NewMeasurement {
+            measurement_date,
+            weight_value,
+            note: None,
+            tags: None,
+        }
+    }
+}
+
+/// Stores a weight with its note and tags in one transaction. The outcome only describes the
+/// weight: changing just the note or the tags of a day is `Unchanged`. Nothing is stored when a
+/// tag is invalid.
+pub fn upsert_measurement(
+    conn: &mut DbConnection,
+    measurement: &NewMeasurement,
+    origin: &Origin,
+) -> Result<UpsertOutcome, AnnotationError> {
+    write_transaction(conn, |conn| store_in_transaction(conn, measurement, origin))
+}
+
+/// Stores the weights of several days in a single transaction: either all of them are written, or
+/// none when an error occurs, e.g. an invalid tag. Returns the outcome for each weight, in order.
+pub fn upsert_weights(
+    conn: &mut DbConnection,
+    measurements: &[NewMeasurement],
+    origin: &Origin,
+) -> Result<Vec<UpsertOutcome>, AnnotationError> {
+    write_transaction(conn, |conn| {
+        measurements
+            .iter()
+            .map(|measurement| store_in_transaction(conn, measurement, origin))
+            .collect()
+    })
+}
+
+pub(crate) fn store_in_transaction(
+    conn: &mut DbConnection,
+    measurement: &NewMeasurement,
+    origin: &Origin,
+) -> Result<UpsertOutcome, AnnotationError> {
     use crate::schema::weights::dsl::*;
-    use diesel::{prelude::*, update};
 
-    let existing_weight = weights
+    let outcome = upsert_in_transaction(
+        conn,
+        measurement.weight_value,
+        measurement.measurement_date,
+        origin,
+    )?;
+    if measurement.note.is_none() && measurement.tags.is_none() {
+        return Ok(outcome);
+    }
+
+    let weight_id = weights
+        .filter(measurement_date.eq(measurement.measurement_date))
+        .select(id)
+        .first::<i32>(conn)?;
+    let mut annotated = false;
+    if let Some(new_note) = &measurement.note {
+        annotated |= annotations::set_note(conn, weight_id, Some(new_note))?;
+    }
+    if let Some(new_tags) = &measurement.tags {
+        annotated |= annotations::set_tags(conn, weight_id, new_tags)?;
+    }
+    // A new weight already has a new version, and storing the note and tags the day already has
+    // changes nothing.
+    if outcome == UpsertOutcome::Unchanged && annotated {
+        diesel::update(weights.find(weight_id))
+            .set(version.eq(version + 1))
+            .execute(conn)?;
+    }
+    Ok(outcome)
+}
+
+pub(crate) fn upsert_in_transaction(
+    conn: &mut DbConnection,
+    in_weight_value: f64,
+    in_measurement_date: NaiveDate,
+    origin: &Origin,
+) -> QueryResult<UpsertOutcome> {
+    use crate::schema::weights::dsl::*;
+    use diesel::upsert::excluded;
+
+    // A weight in the trash still holds its date; storing a new weight for that day takes the
+    // row out of the trash without its old note and tags, which counts as an insert.
+    let previous = weights
         .filter(measurement_date.eq(in_measurement_date))
-        .first::<Weight>(conn)
+        .select((id, weight_value, deleted_at.is_not_null()))
+        .first::<(i32, f64, bool)>(conn)
         .optional()?;
-
-    if let Some(existing_weight) = existing_weight {
-        update(weights)
-            .filter(id.eq(existing_weight.id))
-            .set(weight_value.eq(in_weight_value))
-            .execute(conn)
-    } else {
-        add_weight(conn, in_weight_value, in_measurement_date)
+    let previous_value = match previous {
+        Some((_, value, false)) => Some(value),
+        _ => None,
+    };
+    if previous_value == Some(in_weight_value) {
+        return Ok(UpsertOutcome::Unchanged);
+    }
+    if let Some((trashed_id, _, true)) = previous {
+        annotations::clear(conn, trashed_id)?;
     }
+
+    let upsert = diesel::insert_into(weights)
+        .values(NewWeight::new(in_weight_value, in_measurement_date))
+        .on_conflict(measurement_date)
+        .do_update()
+        .set((
+            weight_value.eq(excluded(weight_value)),
+            deleted_at.eq(None::<chrono::NaiveDateTime>),
+            version.eq(version + 1),
+        ));
+    on_backend!(conn, |conn| upsert.execute(conn))?;
+
+    let (outcome, action) = match previous_value {
+        Some(_) => (UpsertOutcome::Updated, ChangeAction::Update),
+        None => (UpsertOutcome::Inserted, ChangeAction::Insert),
+    };
+    history::record(
+        conn,
+        in_measurement_date,
+        action,
+        previous_value,
+        Some(in_weight_value),
+        origin,
+    )?;
+    Ok(outcome)
 }
 
 pub fn upsert_weight_for_date(
-    conn: &mut SqliteConnection,
+    conn: &mut DbConnection,
     in_weight_value: f64,
     in_measurement_date: String,
+    origin: &Origin,
 ) -> Result<usize, LocalParseError> {
     let measurement_date = parse_date(&in_measurement_date)?;
 
-    let result = upsert_weight(conn, in_weight_value, measurement_date)?;
+    let result = upsert_weight(conn, in_weight_value, measurement_date, origin)?;
     Ok(result)
 }
 
+/// Moves the weight of a day to the trash. It stays there until it is restored (see
+/// [`trash::restore_weight`]) or purged after the retention period.
+pub fn delete_weight(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+    origin: &Origin,
+) -> QueryResult<usize> {
+    write_transaction(conn, |conn| {
+        delete_in_transaction(conn, in_measurement_date, origin)
+    })
+}
+
+pub(crate) fn delete_in_transaction(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+    origin: &Origin,
+) -> QueryResult<usize> {
+    use crate::schema::weights::dsl::*;
+
+    let live_weight = weights
+        .filter(measurement_date.eq(in_measurement_date))
+        .filter(deleted_at.is_null());
+    let previous_value = live_weight
+        .select(weight_value)
+        .first::<f64>(conn)
+        .optional()?;
+    let Some(previous_value) = previous_value else {
+        return Ok(0);
+    };
+
+    let count = diesel::update(live_weight)
+        .set((
+            deleted_at.eq(chrono::Utc::now().naive_utc()),
+            version.eq(version + 1),
+        ))
+        .execute(conn)?;
+    history::record(
+        conn,
+        in_measurement_date,
+        ChangeAction::Delete,
+        Some(previous_value),
+        None,
+        origin,
+    )?;
+    Ok(count)
+}
+
 pub fn weights_between_dates(
-    conn: &mut SqliteConnection,
+    conn: &mut DbConnection,
     start_date: NaiveDate,
     end_date: NaiveDate,
 ) -> QueryResult<Vec<Weight>> {
     use crate::schema::weights::dsl::*;
     weights
+        .select(Weight::as_select())
+        .filter(deleted_at.is_null())
         .filter(measurement_date.ge(start_date))
         .filter(measurement_date.le(end_date))
         .order(measurement_date.asc())
         .load::<Weight>(conn)
 }
 
+/// Returns the weights between two dates (inclusive) that carry the given tag, oldest first.
+pub fn weights_between_dates_with_tag(
+    conn: &mut DbConnection,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    tag: &str,
+) -> QueryResult<Vec<Weight>> {
+    use crate::schema::{tags, weight_tags, weights};
+
+    let tag = tag.trim().to_lowercase();
+    weights::table
+        .inner_join(weight_tags::table.inner_join(tags::table))
+        .filter(tags::name.eq(tag))
+        .filter(weights::deleted_at.is_null())
+        .filter(weights::measurement_date.ge(start_date))
+        .filter(weights::measurement_date.le(end_date))
+        .order(weights::measurement_date.asc())
+        .select(Weight::as_select())
+        .load::<Weight>(conn)
+}
+
 /// Returns a vector of weights between two given dates, with additional interpolated weights added
 /// to fill gaps between the actual weights. The interpolated weights are calculated by linearly
-/// interpolating between adjacent actual weights.
+/// interpolating between adjacent actual weights, see [`stats::interpolate`].
 ///
 /// # Arguments
 ///
-/// * `conn` - The connection to the SQLite database
+/// * `store` - The store of the weights, such as the connection to the database
 /// * `start_date` - The starting date for the weight range (inclusive)
 /// * `end_date` - The ending date for the weight range (inclusive)
 ///
@@ -149,71 +424,43 @@ pub fn weights_between_dates(
 /// # Examples
 ///
 /// ```rust
-/// use rs_weight_tracker::Weight;
-/// use chrono::{NaiveDate};
+/// use chrono::NaiveDate;
+/// use rs_weight_tracker::store::InMemoryStore;
+/// use rs_weight_tracker::NewWeight;
 ///
-/// let mut conn = rs_weight_tracker::establish_connection();
-/// let start_date = NaiveDate::from_ymd(2021, 1, 1);
-/// let end_date = NaiveDate::from_ymd(2021, 1, 31);
+/// let start_date = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+/// let end_date = NaiveDate::from_ymd_opt(2021, 1, 31).unwrap();
+/// let mut store: InMemoryStore = [
+///     NewWeight::new(80.0, start_date),
+///     NewWeight::new(79.0, NaiveDate::from_ymd_opt(2021, 1, 11).unwrap()),
+/// ]
+/// .into_iter()
+/// .collect();
 ///
-/// let weights = rs_weight_tracker::weights_between_dates_with_interpolation(&mut conn, start_date, end_date);
+/// let weights =
+///     rs_weight_tracker::weights_between_dates_with_interpolation(&mut store, start_date, end_date)
+///         .unwrap();
+/// assert_eq!(weights.len(), 31);
+/// let (sixth_day, is_interpolated) = &weights[5];
+/// assert!(is_interpolated);
+/// assert_eq!(sixth_day.weight_value, 79.5);
 /// ```
 ///
 /// This will return a vector of weights between the 1st and 31st of January 2021, with additional
 /// interpolated weights added to fill gaps between the actual weights.
-pub fn weights_between_dates_with_interpolation(
-    conn: &mut SqliteConnection,
+pub fn weights_between_dates_with_interpolation<S: WeightStore + ?Sized>(
+    store: &mut S,
     start_date: NaiveDate,
     end_date: NaiveDate,
 ) -> QueryResult<Vec<(Weight, bool)>> {
-    let weights = weights_between_dates(conn, start_date, end_date)?;
-
-    let mut interpolated_weights = Vec::new();
-    let mut current_weight = None;
-    let mut current_date = start_date;
-    for weight in weights {
-        while current_date < weight.measurement_date {
-            let interpolated_weight = current_weight.map(|weight_value| {
-                let weight_ratio = (current_date - start_date).num_days() as f64
-                    / (weight.measurement_date - start_date).num_days() as f64;
-                let interpolated_weight_value =
-                    weight_value + weight_ratio * (weight.weight_value - weight_value);
-                let interpolated_weight = Weight {
-                    weight_value: interpolated_weight_value,
-                    measurement_date: current_date,
-                    id: 0,
-                };
-                (interpolated_weight, true)
-            });
-            if let Some((interpolated_weight, is_interpolated)) = interpolated_weight {
-                interpolated_weights.push((interpolated_weight, is_interpolated));
-            }
-            current_date = current_date.succ_opt().unwrap_or(current_date);
-        }
-        current_weight = Some(weight.weight_value);
-        current_date = weight.measurement_date.succ_opt().unwrap_or(current_date);
-        interpolated_weights.push((weight, false));
-    }
-    while current_date <= end_date {
-        let interpolated_weight = current_weight.map(|weight_value| {
-            let interpolated_weight = Weight {
-                weight_value,
-                measurement_date: current_date,
-                id: 0,
-            };
-            (interpolated_weight, true)
-        });
-        if let Some((interpolated_weight, is_interpolated)) = interpolated_weight {
-            interpolated_weights.push((interpolated_weight, is_interpolated));
-        }
-        current_date = current_date.succ_opt().unwrap_or(current_date);
-    }
-
-    Ok(interpolated_weights)
+    let weights = store.weights_between(start_date, end_date)?;
+    Ok(stats::interpolate(&weights, start_date, end_date))
 }
 
-pub fn rolling_average_between_dates(
-    conn: &mut SqliteConnection,
+/// The rolling averages of the days between two dates (inclusive) over `amount_of_days` days,
+/// computed on the interpolated weights, see [`stats::rolling_averages`].
+pub fn rolling_average_between_dates<S: WeightStore + ?Sized>(
+    store: &mut S,
     start_date: NaiveDate,
     end_date: NaiveDate,
     amount_of_days: u32,
@@ -221,31 +468,10 @@ pub fn rolling_average_between_dates(
     assert!(amount_of_days > 0 && amount_of_days <= 7);
 
     let start_date_in_advance = start_date - chrono::Duration::days(amount_of_days as i64 - 1);
-
-    let weights = weights_between_dates_with_interpolation(conn, start_date_in_advance, end_date)?;
-
-    let mut rolling_window: Vec<f64> = vec![0.0; amount_of_days as usize];
-    let mut rolling_sum = 0.0;
-    let mut results = Vec::new();
-
-    for (weight, _) in &weights {
-        let value = weight.weight_value;
-        rolling_sum += value;
-
-        if rolling_window.len() == amount_of_days as usize {
-            rolling_sum -= rolling_window[0];
-            rolling_window.remove(0);
-        }
-
-        rolling_window.push(value);
-
-        let days = (weight.measurement_date - start_date_in_advance).num_days() as u32;
-
-        if days >= amount_of_days - 1 {
-            let average = rolling_sum / rolling_window.len() as f64;
-            results.push((weight.measurement_date, average));
-        }
-    }
-
-    Ok(results)
+    let weights = weights_between_dates_with_interpolation(store, start_date_in_advance, end_date)?;
+    Ok(stats::rolling_averages(
+        &weights,
+        start_date,
+        amount_of_days,
+    ))
 }
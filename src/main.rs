@@ -1,153 +1,695 @@
 use std::net::SocketAddr;
-use std::path::PathBuf;
 
-use axum::extract::Query;
+use axum::extract::{Path, Query};
 use axum::http::{self, HeaderValue, Method, StatusCode};
-use axum::response::IntoResponse;
+use axum::middleware;
+use axum::response::{Html, IntoResponse, Redirect, Response};
 use axum::routing::{get, post};
-use axum::Json;
 use axum::Router;
+use axum::{Extension, Json};
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
 use chrono::NaiveDate;
-use dotenvy::dotenv;
-use serde::Deserialize;
-use serde_json::json;
-use tower_http::trace::TraceLayer;
+use rs_weight_tracker::annotations::AnnotationError;
+use rs_weight_tracker::api::conditional::conditional_get;
+use rs_weight_tracker::api::idempotency::{
+    idempotent, KeyRetention, PendingTimeout, IDEMPOTENCY_KEY_HEADER, REPLAYED_HEADER,
+};
+use rs_weight_tracker::api::{self, v1};
+use rs_weight_tracker::assets;
+use rs_weight_tracker::chart::{render_png, render_svg, ChartData, ImageOptions, Theme};
+use rs_weight_tracker::health;
+use rs_weight_tracker::migrations::{self, MigrationResult};
+use rs_weight_tracker::report::{render_html, render_pdf, Report};
+use rs_weight_tracker::server::{https_redirect, reload_certificate_on_change, with_base_path};
+use rs_weight_tracker::validation::ValidationRules;
+use rs_weight_tracker::NewMeasurement;
+use serde::{Deserialize, Serialize};
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::trace::TraceLayer;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
-use tower_http::{cors::CorsLayer, services::ServeDir};
+use tower_http::cors::CorsLayer;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct AddWeightPayload {
+    /// The weight in kilograms.
+    #[schema(example = 81.4)]
     pub weight_value: f64,
+    /// The day of the measurement, as YYYY-MM-DD.
+    #[schema(format = Date, example = "2023-01-20")]
     pub measurement_date: String,
+    /// Replaces the note of the day when present; an empty note removes it.
+    #[schema(example = "after holiday")]
+    pub note: Option<String>,
+    /// Replaces the tags of the day when present.
+    #[schema(example = json!(["holiday"]))]
+    pub tags: Option<Vec<String>>,
+    /// Store the weight even if it breaks the plausibility rules (default false).
+    pub force: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AddWeightResponse {
+    #[schema(example = "ok")]
+    status: String,
+    /// The number of rows inserted or updated.
+    #[schema(example = 1)]
+    rows: usize,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct Interval {
+    /// The first day of the interval, as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-01-01")]
     start_date: String,
+    /// The last day of the interval (inclusive), as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-02-28")]
     end_date: String,
+    /// The size of the rolling window, in days.
+    #[param(minimum = 1, maximum = 7, example = 7)]
     days: u32,
 }
 
-async fn rolling_average(params: Query<Interval>) -> impl IntoResponse {
+/// One day of the rolling average.
+#[derive(Debug, Serialize, ToSchema)]
+struct RollingAverage {
+    #[schema(format = Date, example = "2023-01-20")]
+    date: String,
+    /// The average weight in kilograms over the window ending on `date`.
+    #[schema(example = 81.25)]
+    average: f64,
+}
+
+#[utoipa::path(
+    get,
+    tag = "weights",
+    path = "/api/rolling_average",
+    params(Interval),
+    responses(
+        (status = 200, description = "The rolling average for every day of the interval, with the missing days interpolated", body = [RollingAverage]),
+        (status = 400, description = "Invalid dates or days", body = String)
+    )
+)]
+async fn rolling_average(params: Query<Interval>) -> Response {
     let interval: Interval = params.0;
 
-    let start_date = NaiveDate::parse_from_str(&interval.start_date, "%Y-%m-%d").unwrap();
-    let end_date = NaiveDate::parse_from_str(&interval.end_date, "%Y-%m-%d").unwrap();
+    let (Ok(start_date), Ok(end_date)) = (
+        rs_weight_tracker::parse_date(&interval.start_date),
+        rs_weight_tracker::parse_date(&interval.end_date),
+    ) else {
+        return (StatusCode::BAD_REQUEST, "Invalid start_date or end_date").into_response();
+    };
+    if !(1..=7).contains(&interval.days) {
+        return (StatusCode::BAD_REQUEST, "days must be between 1 and 7").into_response();
+    }
 
-    let mut conn = rs_weight_tracker::establish_connection();
-    let averages = rs_weight_tracker::rolling_average_between_dates(
-        &mut conn,
-        start_date,
-        end_date,
-        interval.days,
-    )
-    .unwrap();
+    let days = interval.days;
+    let averages = api::with_connection(move |conn| {
+        Ok(rs_weight_tracker::rolling_average_between_dates(
+            conn, start_date, end_date, days,
+        ))
+    })
+    .await;
+    let averages = match averages {
+        Ok(Ok(averages)) => averages,
+        Ok(Err(err)) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        }
+        Err(err) => return err.into_response(),
+    };
 
     let result = averages
         .into_iter()
-        .map(|(date, avg)| {
-            let mut map = serde_json::Map::new();
-            map.insert(
-                "date".to_string(),
-                serde_json::Value::String(date.format("%Y-%m-%d").to_string()),
-            );
-            map.insert(
-                "average".to_string(),
-                serde_json::Value::Number(serde_json::Number::from_f64(avg).unwrap()),
-            );
-            serde_json::Value::Object(map)
+        .map(|(date, average)| RollingAverage {
+            date: date.format("%Y-%m-%d").to_string(),
+            average,
         })
-        .collect::<Vec<serde_json::Value>>();
+        .collect::<Vec<RollingAverage>>();
 
-    (StatusCode::OK, Json(result))
+    (StatusCode::OK, Json(result)).into_response()
 }
 
-async fn add_weight(payload: axum::extract::Json<AddWeightPayload>) -> impl IntoResponse {
-    let mut conn = rs_weight_tracker::establish_connection();
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ChartParams {
+    /// The first day of the chart, as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-01-01")]
+    start_date: String,
+    /// The last day of the chart (inclusive), as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-02-28")]
+    end_date: String,
+    /// The size of the rolling average window, in days.
+    #[param(minimum = 1, maximum = 7, example = 7)]
+    days: u32,
+    /// The image width in pixels (default 800).
+    width: Option<u32>,
+    /// The image height in pixels (default 400).
+    height: Option<u32>,
+    /// `light` (default) or `dark`.
+    #[param(example = "light")]
+    theme: Option<String>,
+}
 
-    if let Ok(changed_entries_count) = rs_weight_tracker::upsert_weight_for_date(
-        &mut conn,
-        payload.weight_value,
-        payload.measurement_date.clone(),
-    ) {
-        (
-            StatusCode::CREATED,
-            Json(json!({ "status": "ok", "rows": changed_entries_count })),
+/// Loads the chart data and image options requested by the query parameters, or returns the
+/// error response to send.
+async fn load_chart(params: ChartParams) -> Result<(ChartData, ImageOptions), Response> {
+    let (Ok(start_date), Ok(end_date)) = (
+        NaiveDate::parse_from_str(&params.start_date, "%Y-%m-%d"),
+        NaiveDate::parse_from_str(&params.end_date, "%Y-%m-%d"),
+    ) else {
+        return Err((StatusCode::BAD_REQUEST, "Invalid start_date or end_date").into_response());
+    };
+    if !(1..=7).contains(&params.days) {
+        return Err((StatusCode::BAD_REQUEST, "days must be between 1 and 7").into_response());
+    }
+    let theme = match params.theme.as_deref().map(str::parse::<Theme>) {
+        None => Theme::default(),
+        Some(Ok(theme)) => theme,
+        Some(Err(message)) => return Err((StatusCode::BAD_REQUEST, message).into_response()),
+    };
+
+    let days = params.days;
+    let data =
+        api::with_connection(move |conn| Ok(ChartData::load(conn, start_date, end_date, days)))
+            .await
+            .map_err(IntoResponse::into_response)?
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response())?;
+
+    let defaults = ImageOptions::default();
+    let options = ImageOptions {
+        width: params.width.unwrap_or(defaults.width),
+        height: params.height.unwrap_or(defaults.height),
+        theme,
+    };
+    Ok((data, options))
+}
+
+#[utoipa::path(
+    get,
+    tag = "weights",
+    path = "/api/chart.svg",
+    params(ChartParams),
+    responses(
+        (status = 200, description = "The chart of the weights, rolling average, goal and outliers", content_type = "image/svg+xml", body = String),
+        (status = 400, description = "Invalid dates, days or theme", body = String)
+    )
+)]
+async fn chart_svg(params: Query<ChartParams>) -> Response {
+    let (data, options) = match load_chart(params.0).await {
+        Ok(chart) => chart,
+        Err(response) => return response,
+    };
+
+    (
+        [(http::header::CONTENT_TYPE, "image/svg+xml")],
+        render_svg(&data, &options),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    tag = "weights",
+    path = "/api/chart.png",
+    params(ChartParams),
+    responses(
+        (status = 200, description = "The same chart as /api/chart.svg, as a PNG image", content_type = "image/png", body = Vec<u8>),
+        (status = 400, description = "Invalid dates, days or theme", body = String)
+    )
+)]
+async fn chart_png(params: Query<ChartParams>) -> Response {
+    let (data, options) = match load_chart(params.0).await {
+        Ok(chart) => chart,
+        Err(response) => return response,
+    };
+
+    match render_png(&data, &options) {
+        Ok(png) => ([(http::header::CONTENT_TYPE, "image/png")], png).into_response(),
+        Err(message) => (StatusCode::INTERNAL_SERVER_ERROR, message).into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ReportParams {
+    /// The first day of the report, as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-01-01")]
+    start_date: String,
+    /// The last day of the report (inclusive), as YYYY-MM-DD.
+    #[param(format = Date, example = "2023-02-28")]
+    end_date: String,
+    /// The size of the rolling average window in the chart, in days (default 7).
+    #[param(minimum = 1, maximum = 7)]
+    days: Option<u32>,
+    /// `html` (default) or `pdf`.
+    #[param(example = "html")]
+    format: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    tag = "weights",
+    path = "/api/report",
+    params(ReportParams),
+    responses(
+        (status = 200, description = "The progress report, as a self-contained HTML page or as a PDF document", content(
+            ("text/html" = String),
+            ("application/pdf" = Vec<u8>)
+        )),
+        (status = 400, description = "Invalid dates, days or format", body = String)
+    )
+)]
+async fn report(params: Query<ReportParams>) -> Response {
+    let params: ReportParams = params.0;
+
+    let (Ok(start_date), Ok(end_date)) = (
+        NaiveDate::parse_from_str(&params.start_date, "%Y-%m-%d"),
+        NaiveDate::parse_from_str(&params.end_date, "%Y-%m-%d"),
+    ) else {
+        return (StatusCode::BAD_REQUEST, "Invalid start_date or end_date").into_response();
+    };
+    let days = params.days.unwrap_or(7);
+    if !(1..=7).contains(&days) {
+        return (StatusCode::BAD_REQUEST, "days must be between 1 and 7").into_response();
+    }
+
+    let report =
+        api::with_connection(move |conn| Ok(Report::load(conn, start_date, end_date, days)));
+    let report = match report.await {
+        Ok(Ok(report)) => report,
+        Ok(Err(err)) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        }
+        Err(err) => return err.into_response(),
+    };
+
+    match params.format.as_deref() {
+        None | Some("html") => Html(render_html(&report)).into_response(),
+        Some("pdf") => (
+            [
+                (http::header::CONTENT_TYPE, String::from("application/pdf")),
+                (
+                    http::header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"weight-report-{}-{}.pdf\"",
+                        start_date.format("%Y-%m-%d"),
+                        end_date.format("%Y-%m-%d")
+                    ),
+                ),
+            ],
+            render_pdf(&report),
         )
-    } else {
-        (
+            .into_response(),
+        Some(_) => (StatusCode::BAD_REQUEST, "format must be html or pdf").into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    tag = "weights",
+    path = "/api/add_weight",
+    request_body = AddWeightPayload,
+    params(("Idempotency-Key" = Option<String>, Header, description = "A key chosen by the client, e.g. a UUID, to retry the request safely: a retry with the same key gets the response of the first request")),
+    responses(
+        (status = 201, description = "The weight was stored, replacing any weight already recorded for the date", body = AddWeightResponse),
+        (status = 400, description = "An invalid tag, or an invalid Idempotency-Key", body = ErrorBody),
+        (status = 409, description = "A request with the same Idempotency-Key is still being handled", body = ErrorBody),
+        (status = 422, description = "The weight breaks plausibility rules, listed in the details (set force to store it anyway), or the Idempotency-Key was used for a different request", body = ErrorBody)
+    )
+)]
+async fn add_weight(
+    api::ApiOrigin(origin): api::ApiOrigin,
+    Extension(rules): Extension<ValidationRules>,
+    payload: axum::extract::Json<AddWeightPayload>,
+) -> Response {
+    let payload = payload.0;
+
+    // This endpoint predates the API errors and answers every other failure with 0 rows, but a
+    // weight breaking the plausibility rules is answered with the rules, and an invalid tag with
+    // the tag, so that the user can correct it or force it. A database that cannot be reached is
+    // answered with 503, so that the client retries.
+    let stored = api::with_connection(move |conn| {
+        let date = rs_weight_tracker::parse_date(&payload.measurement_date);
+        if let (Ok(date), false) = (date, payload.force.unwrap_or(false)) {
+            let errors = rules.check(conn, payload.weight_value, date)?;
+            if !errors.is_empty() {
+                return Err(api::ApiError::validation_failed(&errors));
+            }
+        }
+
+        Ok(rs_weight_tracker::parse_date(&payload.measurement_date)
+            .map_err(|_| None)
+            .and_then(|measurement_date| {
+                let measurement = NewMeasurement {
+                    measurement_date,
+                    weight_value: payload.weight_value,
+                    note: payload.note,
+                    tags: payload.tags,
+                };
+                rs_weight_tracker::upsert_measurement(conn, &measurement, &origin).map_err(|err| {
+                    match err {
+                        AnnotationError::InvalidTag { .. } => Some(err),
+                        AnnotationError::Database(_) => None,
+                    }
+                })
+            }))
+    })
+    .await;
+    let stored = match stored {
+        Ok(stored) => stored,
+        Err(err) => return err.into_response(),
+    };
+    match stored {
+        Ok(outcome) => {
+            let changed_entries_count = outcome.rows();
+            (
+                StatusCode::CREATED,
+                Json(AddWeightResponse {
+                    status: String::from("ok"),
+                    rows: changed_entries_count,
+                }),
+            )
+                .into_response()
+        }
+        Err(Some(err)) => api::ApiError::from(err).into_response(),
+        Err(None) => (
             StatusCode::CREATED,
-            Json(json!({ "status": "ok", "rows": 0 })),
+            Json(AddWeightResponse {
+                status: String::from("ok"),
+                rows: 0,
+            }),
         )
+            .into_response(),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "rs-weight-tracker",
+        description = "Record daily weights and query rolling averages, charts and reports.",
+        license(name = "MIT")
+    ),
+    paths(
+        v1::list_weights,
+        v1::store_weight,
+        v1::store_weights_batch,
+        v1::weight_history,
+        v1::get_weight,
+        v1::delete_weight,
+        v1::restore_weight,
+        v1::list_trash,
+        v1::undo_delete,
+        v1::list_annotations,
+        v1::list_events,
+        v1::create_event,
+        v1::delete_event,
+        v1::rolling_average,
+        rolling_average,
+        add_weight,
+        chart_svg,
+        chart_png,
+        report
+    ),
+    components(schemas(
+        api::Meta,
+        api::ErrorBody,
+        api::ErrorDetail,
+        api::ValidationIssue,
+        api::RollingAverageResponse,
+        api::WeightsResponse,
+        api::StoredWeightResponse,
+        api::BatchResponse,
+        api::HistoryResponse,
+        api::WeightResponse,
+        api::TrashResponse,
+        api::AnnotationsResponse,
+        api::EventsResponse,
+        api::EventResponse,
+        v1::WeightEntry,
+        v1::NewWeightPayload,
+        v1::StoredWeight,
+        v1::RollingAveragePoint,
+        v1::BatchItemStatus,
+        v1::BatchItemResult,
+        v1::BatchResult,
+        v1::HistoryEntry,
+        v1::TrashedWeight,
+        v1::AnnotationEntry,
+        v1::EventEntry,
+        v1::PhaseSummaryEntry,
+        v1::NewEventPayload,
+        AddWeightPayload,
+        AddWeightResponse,
+        RollingAverage
+    )),
+    tags(
+        (name = "v1", description = "The versioned API; see the versioning policy in the README"),
+        (name = "weights", description = "The original, unversioned API used by the bundled frontend")
+    )
+)]
+struct ApiDoc;
+
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    let mut doc = ApiDoc::openapi();
+    // The paths of the document are relative to the base path of the server.
+    let base_path = rs_weight_tracker::config::current().server.base_path();
+    if !base_path.is_empty() {
+        doc.servers = Some(vec![utoipa::openapi::Server::new(base_path)]);
     }
+    Json(doc)
 }
 
-/// Load the local.env file if it exists, and then load the .env file.
-pub fn load_dotenv() -> Option<PathBuf> {
-    let local_file_result = dotenvy::from_filename("local.env");
+/// The OpenAPI document, as seen from the Swagger UI pages under `/api/docs/`.
+const API_DOCS_URL: &str = "../openapi.json";
 
-    match &local_file_result {
-        Ok(path) => {
-            println!("Loaded local.env file from {:?}", path);
-            return local_file_result.ok();
+/// Swagger UI for the OpenAPI document. Its files are embedded in the binary, like the frontend,
+/// so the page works offline and without loading anything from another origin.
+async fn api_docs(file: Option<Path<String>>) -> Response {
+    let file = file.map_or_else(String::new, |Path(file)| file);
+    let config = Arc::new(utoipa_swagger_ui::Config::from(API_DOCS_URL));
+    match utoipa_swagger_ui::serve(&file, config) {
+        Ok(Some(file)) => (
+            [(http::header::CONTENT_TYPE, file.content_type)],
+            file.bytes.into_owned(),
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+/// How often the server purges the weights that have been in the trash, and the idempotency
+/// keys that have been kept, for longer than their retention period.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Purges the expired weights from the trash and the expired idempotency keys now and then
+/// every `PURGE_INTERVAL`.
+async fn purge_periodically(trash_retention: chrono::Duration, key_retention: chrono::Duration) {
+    let origin = rs_weight_tracker::Origin::new("purge", "server");
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let origin = origin.clone();
+        let purged = tokio::task::spawn_blocking(move || {
+            let mut conn = rs_weight_tracker::establish_connection();
+            (
+                rs_weight_tracker::trash::purge_deleted(&mut conn, trash_retention, &origin),
+                rs_weight_tracker::idempotency::purge_expired(&mut conn, key_retention),
+            )
+        })
+        .await;
+        let (weights, keys) = match purged {
+            Ok(purged) => purged,
+            Err(err) => {
+                tracing::error!("Failed to purge the trash: {}", err);
+                continue;
+            }
+        };
+        match weights {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Purged {} weight(s) from the trash", count),
+            Err(err) => tracing::error!("Failed to purge the trash: {}", err),
         }
-        Err(e) => {
-            println!(
-                "No local.env file found, or error reading it. Skipping: {:?}",
-                e
-            );
+        match keys {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Purged {} expired idempotency key(s)", count),
+            Err(err) => tracing::error!("Failed to purge the idempotency keys: {}", err),
+        }
+    }
+}
+
+/// Checks that the schema of the database is not newer than the server, and applies the pending
+/// migrations unless told not to.
+fn prepare_database(migrate: bool) -> MigrationResult<()> {
+    let database_url = &rs_weight_tracker::config::current().database_url;
+    let mut conn = rs_weight_tracker::connect(database_url)
+        .map_err(|err| format!("Cannot connect to {}: {}", database_url, err))?;
+    if migrate {
+        for name in migrations::migrate(&mut conn)? {
+            tracing::info!("Applied the migration {}", name);
         }
+        return Ok(());
+    }
+    migrations::check_not_newer(&mut conn)?;
+    let pending = migrations::pending(&mut conn)?;
+    if !pending.is_empty() {
+        tracing::warn!(
+            "{} pending migration(s), not ready until `wt db migrate` applies them",
+            pending.len()
+        );
     }
+    Ok(())
+}
 
-    dotenv().ok()
+/// Waits for Ctrl+C or, on Unix, for SIGTERM, as sent by container orchestrators.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!("Cannot listen for Ctrl+C: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                tracing::error!("Cannot listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Serves an app on a port of the configured bind address, over HTTPS when a TLS configuration
+/// is given, until the server fails or the handle shuts it down.
+async fn serve(app: Router, port: u16, base_path: &str, tls: Option<RustlsConfig>, handle: Handle) {
+    let addr = SocketAddr::new(
+        rs_weight_tracker::config::current().server.bind_address,
+        port,
+    );
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    tracing::info!("Listening on {}://{}{}/", scheme, addr, base_path);
+    let make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let served = match tls {
+        Some(tls) => {
+            axum_server::bind_rustls(addr, tls)
+                .handle(handle)
+                .serve(make_service)
+                .await
+        }
+        None => {
+            axum_server::bind(addr)
+                .handle(handle)
+                .serve(make_service)
+                .await
+        }
+    };
+    if let Err(err) = served {
+        eprintln!("Cannot listen on {}: {}", addr, err);
+        std::process::exit(1);
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    load_dotenv();
     tracing_subscriber::fmt::init();
 
-    let port_num_backend: String =
-        env::var("SERVER_BACKEND_PORT_NUM").expect("SERVER_BACKEND_PORT_NUM must be set");
-    let port_num_backend: u16 = port_num_backend
-        .parse::<u16>()
-        .expect("Invalid SERVER_BACKEND_PORT_NUM in environment");
+    let mut args: Vec<String> = env::args().collect();
+    let config = rs_weight_tracker::config::init_or_exit(&mut args);
+    if args.len() > 1 {
+        eprintln!("Unexpected argument {}", args[1]);
+        std::process::exit(1);
+    }
+    for path in &config.loaded_from {
+        tracing::info!("Loaded the configuration from {}", path.display());
+    }
+
+    let server = &config.server;
+    let base_path = server.base_path();
+    let trash_retention = config.trash_retention();
+    let validation_rules = config.validation.clone();
+    let key_retention = config.idempotency_key_retention();
+    let pending_timeout = config.idempotency_pending_timeout();
+
+    if let Err(err) = prepare_database(!server.skip_migrations) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 
-    let port_num_frontend: String =
-        env::var("SERVER_FRONTEND_PORT_NUM").expect("SERVER_FRONTEND_PORT_NUM must be set");
-    let port_num_frontend: u16 = port_num_frontend
-        .parse::<u16>()
-        .expect("Invalid SERVER_FRONTEND_PORT_NUM in environment");
+    // Shared by the servers, to stop them all at once.
+    let handle = Handle::new();
 
-    let serve_dir_from_static = ServeDir::new("static");
+    let tls = match server.tls() {
+        Some((cert, key)) => match RustlsConfig::from_pem_file(cert, key).await {
+            Ok(tls) => Some(tls),
+            Err(err) => {
+                eprintln!(
+                    "Cannot load the TLS certificate {} and key {}: {}",
+                    cert.display(),
+                    key.display(),
+                    err
+                );
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let frontend_app = assets::router(server.static_dir.as_deref());
+    if let Some(dir) = &server.static_dir {
+        tracing::info!("Serving the frontend from {}", dir.display());
+    }
 
     let frontend = async {
-        let app = Router::new()
-            .nest_service("/", serve_dir_from_static)
-            .layer(TraceLayer::new_for_http());
+        if server.single_port {
+            return;
+        }
+        let app = frontend_app.clone().layer(TraceLayer::new_for_http());
+        serve(
+            with_base_path(app, base_path),
+            server.frontend_port,
+            base_path,
+            tls.clone(),
+            handle.clone(),
+        )
+        .await;
+    };
 
+    let https_redirect = async {
+        if let Some(port) = server.https_redirect_port {
+            let app = https_redirect(server.pages_port()).layer(TraceLayer::new_for_http());
+            serve(app, port, "", None, handle.clone()).await;
+        }
+    };
 
-        let addr = SocketAddr::from(([127, 0, 0, 1], port_num_frontend));
-        axum::Server::bind(&addr)
-            .serve(app.into_make_service())
-            .await
-            .unwrap();
+    let certificate_reload = async {
+        if let (Some(tls), Some((cert, key))) = (&tls, server.tls()) {
+            reload_certificate_on_change(tls.clone(), cert.to_owned(), key.to_owned()).await;
+        }
     };
 
     let backend = async {
-        let local_address_for_cors: String =
-            env::var("CORS_ALLOWED_ORIGINS").expect("CORS_ALLOWED_ORIGINS must be set");
-        let split_cors_addresses: Vec<String> = local_address_for_cors
-            .split(',')
-            .map(String::from)
-            .collect();
-
-        for address in &split_cors_addresses {
-            println!("Will add address {} in CORS allowed_origin list", address);
+        for address in &server.cors_allowed_origins {
+            tracing::info!("Will add address {} in CORS allowed_origin list", address);
         }
 
-        let allowed_origins: Vec<HeaderValue> = split_cors_addresses
+        let allowed_origins: Vec<HeaderValue> = server
+            .cors_allowed_origins
             .iter()
             .map(|address| {
                 address
@@ -156,9 +698,37 @@ async fn main() {
             })
             .collect();
 
-        let app = Router::new()
+        // Only the JSON of the API is worth tagging: the charts, the report and the documentation
+        // would be buffered and hashed whole for nothing, and the probes must always be answered.
+        let json_api = Router::new()
+            .nest("/api/v1", v1::router())
             .route("/api/rolling_average", get(rolling_average))
-            .route("/api/add_weight", post(add_weight))
+            .route(
+                "/api/add_weight",
+                post(add_weight).layer(middleware::from_fn(idempotent)),
+            )
+            .route(
+                "/api/weights/batch",
+                post(v1::store_weights_batch).layer(middleware::from_fn(idempotent)),
+            )
+            .route("/api/weights/:date/history", get(v1::weight_history))
+            .route("/api/events", get(v1::list_events).post(v1::create_event))
+            .layer(middleware::from_fn(conditional_get));
+        let mut app = Router::new()
+            .merge(json_api)
+            .route("/healthz", get(health::healthz))
+            .route("/readyz", get(health::readyz))
+            .route("/api/chart.svg", get(chart_svg))
+            .route("/api/chart.png", get(chart_png))
+            .route("/api/report", get(report))
+            .route("/api/openapi.json", get(openapi_json))
+            // Relative to /api/docs/, so that the links of Swagger UI work under any base path.
+            .route("/api/docs", get(|| async { Redirect::permanent("docs/") }))
+            .route("/api/docs/", get(api_docs))
+            .route("/api/docs/*file", get(api_docs))
+            .layer(Extension(validation_rules))
+            .layer(Extension(KeyRetention(key_retention)))
+            .layer(Extension(PendingTimeout(pending_timeout)))
             .layer(
                 // see https://docs.rs/tower-http/latest/tower_http/cors/index.html
                 // for more details
@@ -168,16 +738,50 @@ async fn main() {
                 // or see this issue https://github.com/tokio-rs/axum/issues/849
                 CorsLayer::new()
                     .allow_origin(allowed_origins)
-                    .allow_methods([Method::GET, Method::POST])
-                    .allow_headers([http::header::CONTENT_TYPE, http::header::AUTHORIZATION]),
-            )
-            .layer(TraceLayer::new_for_http());
-        let addr = SocketAddr::from(([127, 0, 0, 1], port_num_backend));
-        axum::Server::bind(&addr)
-            .serve(app.into_make_service())
-            .await
-            .unwrap();
+                    .allow_methods([Method::GET, Method::POST, Method::DELETE])
+                    .allow_headers([
+                        http::header::CONTENT_TYPE,
+                        http::header::AUTHORIZATION,
+                        http::HeaderName::from_static(api::ACTOR_HEADER),
+                        http::HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+                        http::header::IF_MATCH,
+                        http::header::IF_NONE_MATCH,
+                    ])
+                    .expose_headers([
+                        http::HeaderName::from_static(REPLAYED_HEADER),
+                        http::header::ETAG,
+                    ]),
+            );
+        if server.single_port {
+            app = app.fallback_service(frontend_app.clone());
+        }
+        let app = app.layer(TraceLayer::new_for_http());
+        serve(
+            with_base_path(app, base_path),
+            server.backend_port,
+            base_path,
+            tls.clone(),
+            handle.clone(),
+        )
+        .await;
     };
 
-    tokio::join!(frontend, backend);
+    let shutdown = async {
+        shutdown_signal().await;
+        tracing::info!(
+            "Shutting down, waiting up to {} s for the requests in progress",
+            server.shutdown_timeout_seconds
+        );
+        handle.graceful_shutdown(Some(config.shutdown_timeout()));
+    };
+
+    // The background tasks run forever: they are dropped once the servers have stopped.
+    tokio::select! {
+        _ = async { tokio::join!(frontend, backend, https_redirect, shutdown) } => {
+            tracing::info!("Stopped");
+        }
+        _ = async {
+            tokio::join!(certificate_reload, purge_periodically(trash_retention, key_retention))
+        } => {}
+    }
 }
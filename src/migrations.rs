@@ -0,0 +1,150 @@
+//! The migrations of the database schema, embedded in the binaries at build time, so that a
+//! database can be created and upgraded without the Diesel CLI: the server applies the pending
+//! migrations when it starts, and `wt db` applies, lists and reverts them.
+
+use diesel::dsl::sql;
+use diesel::migration::MigrationSource;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::error::Error;
+
+use crate::db::{Backend, DbConnection};
+
+/// Every migration of the `migrations` directory, for SQLite databases.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Every migration of the `migrations_postgres` directory, for PostgreSQL databases. They mirror
+/// the SQLite ones, with the same versions and names.
+#[cfg(feature = "postgres")]
+pub const POSTGRES_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_postgres");
+
+/// The result of the operations on the migrations.
+pub type MigrationResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// A migration known to this binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// The version, e.g. `20230221164500`.
+    pub version: String,
+    /// The name of the directory of the migration, e.g. `2023-02-21-164500_create_weights`.
+    pub name: String,
+    pub applied: bool,
+}
+
+/// The migrations for the backend of a connection.
+pub fn migrations_for(conn: &DbConnection) -> EmbeddedMigrations {
+    match conn {
+        DbConnection::Sqlite(_) => MIGRATIONS,
+        #[cfg(feature = "postgres")]
+        DbConnection::Pg(_) => POSTGRES_MIGRATIONS,
+    }
+}
+
+/// Whether the table in which Diesel records the applied migrations exists.
+fn has_migrations_table(conn: &mut DbConnection) -> QueryResult<bool> {
+    let exists = match conn {
+        DbConnection::Sqlite(_) => {
+            "EXISTS (SELECT 1 FROM sqlite_master \
+             WHERE type = 'table' AND name = '__diesel_schema_migrations')"
+        }
+        #[cfg(feature = "postgres")]
+        DbConnection::Pg(_) => "to_regclass('__diesel_schema_migrations') IS NOT NULL",
+    };
+    diesel::select(sql::<Bool>(exists)).get_result(conn)
+}
+
+/// The versions of the migrations applied to a database. Diesel creates its table of the applied
+/// migrations when it reads it, so a database without the table has none applied and is left as
+/// it is: reading the status of a database never writes to it.
+fn applied(conn: &mut DbConnection) -> MigrationResult<Vec<String>> {
+    if !has_migrations_table(conn)? {
+        return Ok(Vec::new());
+    }
+    Ok(conn
+        .applied_migrations()?
+        .iter()
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// The migrations known to this binary, oldest first, and whether they are applied.
+pub fn status(conn: &mut DbConnection) -> MigrationResult<Vec<MigrationStatus>> {
+    let applied = applied(conn)?;
+    let mut migrations = MigrationSource::<Backend>::migrations(&migrations_for(conn))?;
+    migrations.sort_by(|a, b| a.name().version().cmp(&b.name().version()));
+    Ok(migrations
+        .iter()
+        .map(|migration| {
+            let version = migration.name().version().to_string();
+            MigrationStatus {
+                applied: applied.contains(&version),
+                name: migration.name().to_string(),
+                version,
+            }
+        })
+        .collect())
+}
+
+/// The versions of the migrations not applied to a database yet, oldest first.
+pub fn pending(conn: &mut DbConnection) -> MigrationResult<Vec<String>> {
+    Ok(status(conn)?
+        .into_iter()
+        .filter(|migration| !migration.applied)
+        .map(|migration| migration.version)
+        .collect())
+}
+
+/// The versions of the migrations applied to a database but unknown to this binary, when the
+/// database was upgraded by a newer version of the application.
+pub fn unknown(conn: &mut DbConnection) -> MigrationResult<Vec<String>> {
+    let known: Vec<String> = status(conn)?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    Ok(applied(conn)?
+        .into_iter()
+        .filter(|version| !known.contains(version))
+        .collect())
+}
+
+/// Fails when the schema of a database is newer than this binary, which could then corrupt the
+/// data it does not know about.
+pub fn check_not_newer(conn: &mut DbConnection) -> MigrationResult<()> {
+    let unknown = unknown(conn)?;
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "The database schema is newer than this version of the application (unknown migrations: \
+         {}); upgrade the application",
+        unknown.join(", ")
+    )
+    .into())
+}
+
+/// Applies the pending migrations, unless the schema is newer than this binary. Returns the names
+/// of the migrations applied, oldest first.
+pub fn migrate(conn: &mut DbConnection) -> MigrationResult<Vec<String>> {
+    check_not_newer(conn)?;
+    let pending: Vec<String> = status(conn)?
+        .into_iter()
+        .filter(|migration| !migration.applied)
+        .map(|migration| migration.name)
+        .collect();
+    conn.run_pending_migrations(migrations_for(conn))?;
+    Ok(pending)
+}
+
+/// Reverts the last migration applied. Returns its name.
+pub fn rollback(conn: &mut DbConnection) -> MigrationResult<String> {
+    check_not_newer(conn)?;
+    let version = conn
+        .revert_last_migration(migrations_for(conn))?
+        .to_string();
+    let name = status(conn)?
+        .into_iter()
+        .find(|migration| migration.version == version)
+        .map_or(version, |migration| migration.name);
+    Ok(name)
+}
@@ -1,8 +1,12 @@
-use super::schema::weights;
-use chrono::NaiveDate;
+use super::schema::{events, weight_history, weights};
+use crate::db::{Backend, DbConnection};
+use crate::history::Origin;
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
 
-#[derive(Identifiable, Insertable, Queryable, Debug)]
+/// A weight of one day. Loading it selects its columns explicitly (see [`Weight::all`]), as the
+/// table also holds the `deleted_at` time of the weights in the trash.
+#[derive(Identifiable, Insertable, Queryable, Selectable, Debug, Clone)]
 pub struct Weight {
     pub id: i32,
     pub weight_value: f64,
@@ -16,10 +20,22 @@ pub struct NewWeight {
     pub measurement_date: NaiveDate,
 }
 
+/// The query type of [`Weight::all`].
+pub type AllWeights = weights::BoxedQuery<
+    'static,
+    Backend,
+    diesel::dsl::SqlTypeOf<diesel::dsl::AsSelect<Weight, Backend>>,
+>;
+
 impl Weight {
-    pub fn all() -> weights::BoxedQuery<'static, diesel::sqlite::Sqlite> {
+    /// The weights that are not in the trash, newest first.
+    pub fn all() -> AllWeights {
         use crate::schema::weights::dsl::*;
-        weights.order(measurement_date.desc()).into_boxed()
+        weights
+            .select(Weight::as_select())
+            .filter(deleted_at.is_null())
+            .order(measurement_date.desc())
+            .into_boxed()
     }
 }
 
@@ -33,21 +49,64 @@ impl NewWeight {
 }
 
 impl Weight {
-    pub fn upsert(&self, conn: &mut SqliteConnection) -> QueryResult<usize> {
-        use crate::schema::weights::dsl::*;
-        use diesel::{insert_into, prelude::*, update};
-
-        let existing_weight = weights
-            .filter(measurement_date.eq(self.measurement_date))
-            .first::<Weight>(conn)
-            .optional()?;
-
-        if let Some(_) = existing_weight {
-            update(weights)
-                .set(weight_value.eq(self.weight_value))
-                .execute(conn)
-        } else {
-            insert_into(weights).values(self).execute(conn)
-        }
+    /// Stores the weight for its date, replacing any other weight of that day. The id is not
+    /// written: a new row gets the next free id.
+    pub fn upsert(&self, conn: &mut DbConnection, origin: &Origin) -> QueryResult<usize> {
+        crate::upsert_weight(conn, self.weight_value, self.measurement_date, origin)
     }
 }
+
+/// A change of the weight of a day, as recorded in the history.
+#[derive(Identifiable, Queryable, Debug, Clone)]
+#[diesel(table_name = weight_history)]
+pub struct WeightChange {
+    pub id: i32,
+    pub measurement_date: NaiveDate,
+    /// `insert`, `update`, `delete`, `restore` or `purge`.
+    pub action: String,
+    /// The weight before the change; `None` for an insert or a restore.
+    pub old_value: Option<f64>,
+    /// The weight after the change; `None` for a delete or a purge.
+    pub new_value: Option<f64>,
+    /// When the change was made, in UTC.
+    pub changed_at: NaiveDateTime,
+    pub source: String,
+    pub actor: String,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = weight_history)]
+pub struct NewWeightChange<'a> {
+    pub measurement_date: NaiveDate,
+    pub action: &'a str,
+    pub old_value: Option<f64>,
+    pub new_value: Option<f64>,
+    pub changed_at: NaiveDateTime,
+    pub source: &'a str,
+    pub actor: &'a str,
+}
+
+/// An event of the timeline: a single day (a medication change) or a period (a diet phase, a
+/// bulk or a cut, an injury). See [`crate::events`].
+#[derive(Identifiable, Queryable, Selectable, Debug, Clone, PartialEq)]
+#[diesel(table_name = events)]
+pub struct Event {
+    pub id: i32,
+    /// `diet`, `bulk`, `cut`, `injury`, `medication` or `other`.
+    pub kind: String,
+    pub title: String,
+    pub start_date: NaiveDate,
+    /// The last day of the event (inclusive); `None` while it is still going on.
+    pub end_date: Option<NaiveDate>,
+    pub note: Option<String>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = events)]
+pub struct NewEvent {
+    pub kind: String,
+    pub title: String,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub note: Option<String>,
+}
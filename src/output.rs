@@ -0,0 +1,392 @@
+use chrono::NaiveDate;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::stats::PhaseSummary;
+use crate::{Event, Weight, WeightChange};
+
+/// The formats in which the command line tools can print their listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// An array of objects, one per row, keyed by column name.
+    Json,
+    /// Comma separated values with a header line (RFC 4180 quoting).
+    Csv,
+    /// Tab separated values with a header line.
+    Tsv,
+    /// Aligned columns for reading in a terminal.
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "table" => Ok(OutputFormat::Table),
+            _ => Err(format!(
+                "Unknown format '{}', expected one of: json, csv, tsv, table",
+                value
+            )),
+        }
+    }
+}
+
+/// Removes a `--format FORMAT` (or `--format=FORMAT`) option from the given arguments and
+/// returns the parsed format, if present.
+pub fn take_format_arg(args: &mut Vec<String>) -> Result<Option<OutputFormat>, String> {
+    let Some(position) = args
+        .iter()
+        .position(|arg| arg == "--format" || arg.starts_with("--format="))
+    else {
+        return Ok(None);
+    };
+
+    let arg = args.remove(position);
+    let value = match arg.strip_prefix("--format=") {
+        Some(value) => value.to_string(),
+        None if position < args.len() => args.remove(position),
+        None => return Err(String::from("Missing value for --format")),
+    };
+    value.parse::<OutputFormat>().map(Some)
+}
+
+/// A single value in a listing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    Text(String),
+    Integer(i64),
+    Decimal(f64),
+    Bool(bool),
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Empty => Ok(()),
+            Cell::Text(text) => write!(f, "{}", text),
+            Cell::Integer(value) => write!(f, "{}", value),
+            Cell::Decimal(value) => write!(f, "{}", value),
+            Cell::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Cell::Empty => serializer.serialize_none(),
+            Cell::Text(text) => serializer.serialize_str(text),
+            Cell::Integer(value) => serializer.serialize_i64(*value),
+            Cell::Decimal(value) if value.is_finite() => serializer.serialize_f64(*value),
+            Cell::Decimal(_) => serializer.serialize_none(),
+            Cell::Bool(value) => serializer.serialize_bool(*value),
+        }
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(value: f64) -> Self {
+        Cell::Decimal(value)
+    }
+}
+
+impl From<i32> for Cell {
+    fn from(value: i32) -> Self {
+        Cell::Integer(value as i64)
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(value: i64) -> Self {
+        Cell::Integer(value)
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(value: bool) -> Self {
+        Cell::Bool(value)
+    }
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<NaiveDate> for Cell {
+    fn from(value: NaiveDate) -> Self {
+        Cell::Text(value.format("%Y-%m-%d").to_string())
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Cell::Empty, Into::into)
+    }
+}
+
+/// Rows of values sharing a fixed list of columns. The column names form the stable schema of
+/// the machine readable formats: JSON keys and CSV/TSV headers.
+#[derive(Debug, Clone)]
+pub struct Listing {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<Cell>>,
+}
+
+struct JsonRow<'a> {
+    columns: &'a [&'static str],
+    cells: &'a [Cell],
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, cell) in self.columns.iter().zip(self.cells) {
+            map.serialize_entry(column, cell)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Listing {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.rows.len()))?;
+        for cells in &self.rows {
+            seq.serialize_element(&JsonRow {
+                columns: &self.columns,
+                cells,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+fn csv_field(cell: &Cell) -> String {
+    let text = cell.to_string();
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+fn tsv_field(cell: &Cell) -> String {
+    cell.to_string()
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn table_field(cell: &Cell) -> String {
+    match cell {
+        Cell::Decimal(value) => format!("{:.1}", value),
+        Cell::Bool(true) => String::from("yes"),
+        Cell::Bool(false) => String::from("no"),
+        _ => cell.to_string(),
+    }
+}
+
+impl Listing {
+    pub fn new(columns: &[&'static str]) -> Self {
+        Listing {
+            columns: columns.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Appends a row. Missing trailing cells are left empty and extra cells are ignored.
+    pub fn push_row(&mut self, mut cells: Vec<Cell>) {
+        cells.resize(self.columns.len(), Cell::Empty);
+        self.rows.push(cells);
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn write<W: Write>(&self, format: OutputFormat, out: &mut W) -> io::Result<()> {
+        match format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut *out, self)?;
+                writeln!(out)
+            }
+            OutputFormat::Csv => self.write_separated(out, ",", csv_field),
+            OutputFormat::Tsv => self.write_separated(out, "\t", tsv_field),
+            OutputFormat::Table => self.write_table(out),
+        }
+    }
+
+    fn write_separated<W: Write>(
+        &self,
+        out: &mut W,
+        separator: &str,
+        field: fn(&Cell) -> String,
+    ) -> io::Result<()> {
+        writeln!(out, "{}", self.columns.join(separator))?;
+        for cells in &self.rows {
+            let fields: Vec<String> = cells.iter().map(field).collect();
+            writeln!(out, "{}", fields.join(separator))?;
+        }
+        Ok(())
+    }
+
+    fn write_table<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|cells| cells.iter().map(table_field).collect())
+            .collect();
+
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                rows.iter()
+                    .map(|row| row[index].chars().count())
+                    .fold(column.len(), usize::max)
+            })
+            .collect();
+
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, width)| format!("{:<width$}", column, width = width))
+            .collect();
+        writeln!(out, "{}", header.join("  ").trim_end())?;
+
+        let ruler: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        writeln!(out, "{}", ruler.join("  "))?;
+
+        for (row, cells) in rows.iter().zip(&self.rows) {
+            let fields: Vec<String> = row
+                .iter()
+                .zip(cells)
+                .zip(&widths)
+                .map(|((text, cell), width)| match cell {
+                    Cell::Integer(_) | Cell::Decimal(_) => {
+                        format!("{:>width$}", text, width = width)
+                    }
+                    _ => format!("{:<width$}", text, width = width),
+                })
+                .collect();
+            writeln!(out, "{}", fields.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists stored weights with the columns `id`, `date` and `weight_kg`.
+pub fn weights_listing(weights: &[Weight]) -> Listing {
+    let mut listing = Listing::new(&["id", "date", "weight_kg"]);
+    for weight in weights {
+        listing.push_row(vec![
+            weight.id.into(),
+            weight.measurement_date.into(),
+            weight.weight_value.into(),
+        ]);
+    }
+    listing
+}
+
+/// Lists the weights of an interval with the columns `date`, `weight_kg`, `interpolated` and
+/// `average_kg`. The average is empty for the dates without a rolling average.
+pub fn interval_listing(weights: &[(Weight, bool)], averages: &[(NaiveDate, f64)]) -> Listing {
+    let averages: HashMap<NaiveDate, f64> = averages.iter().copied().collect();
+
+    let mut listing = Listing::new(&["date", "weight_kg", "interpolated", "average_kg"]);
+    for (weight, is_interpolated) in weights {
+        listing.push_row(vec![
+            weight.measurement_date.into(),
+            weight.weight_value.into(),
+            (*is_interpolated).into(),
+            averages.get(&weight.measurement_date).copied().into(),
+        ]);
+    }
+    listing
+}
+
+/// Lists the changes of a weight with the columns `id`, `date`, `action`, `old_kg`, `new_kg`,
+/// `changed_at` (UTC), `source` and `actor`.
+pub fn history_listing(changes: &[WeightChange]) -> Listing {
+    let mut listing = Listing::new(&[
+        "id",
+        "date",
+        "action",
+        "old_kg",
+        "new_kg",
+        "changed_at",
+        "source",
+        "actor",
+    ]);
+    for change in changes {
+        listing.push_row(vec![
+            change.id.into(),
+            change.measurement_date.into(),
+            change.action.clone().into(),
+            change.old_value.into(),
+            change.new_value.into(),
+            change
+                .changed_at
+                .format("%Y-%m-%dT%H:%M:%SZ")
+                .to_string()
+                .into(),
+            change.source.clone().into(),
+            change.actor.clone().into(),
+        ]);
+    }
+    listing
+}
+
+/// The events with the summary of the weights measured during each of them. The summary columns
+/// are empty for an event without measurements.
+pub fn events_listing(events: &[(Event, Option<PhaseSummary>)]) -> Listing {
+    let mut listing = Listing::new(&[
+        "id",
+        "kind",
+        "title",
+        "start_date",
+        "end_date",
+        "days",
+        "measurements",
+        "start_kg",
+        "end_kg",
+        "change_kg",
+        "kg_per_week",
+    ]);
+    for (event, summary) in events {
+        listing.push_row(vec![
+            event.id.into(),
+            event.kind.clone().into(),
+            event.title.clone().into(),
+            event.start_date.into(),
+            event.end_date.into(),
+            summary.as_ref().map(|summary| summary.duration_days).into(),
+            summary.as_ref().map(|summary| summary.count as i64).into(),
+            summary.as_ref().map(|summary| summary.start_weight).into(),
+            summary.as_ref().map(|summary| summary.end_weight).into(),
+            summary.as_ref().map(PhaseSummary::change).into(),
+            summary
+                .as_ref()
+                .and_then(|summary| summary.rate_per_week)
+                .into(),
+        ]);
+    }
+    listing
+}
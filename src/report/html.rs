@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use super::{Report, WEEKLY_COLUMNS};
+use crate::chart::{render_svg, ImageOptions};
+
+const STYLE: &str = "body { font-family: sans-serif; color: #333; max-width: 860px; margin: 2em auto; padding: 0 1em; }
+h1 { font-size: 1.5em; }
+h2 { font-size: 1.15em; margin-top: 1.5em; border-bottom: 1px solid #ddd; }
+table { border-collapse: collapse; }
+th, td { padding: 4px 12px; text-align: right; border-bottom: 1px solid #eee; }
+th:first-child, td:first-child { text-align: left; }
+.figures th { font-weight: normal; color: #666; }
+.chart svg { max-width: 100%; height: auto; }
+footer { margin-top: 2em; font-size: 0.8em; color: #888; }
+@media print { body { margin: 0; } h2 { page-break-after: avoid; } table { page-break-inside: avoid; } }";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the report as a self-contained HTML page: the styles and the SVG chart are inline,
+/// so the file can be mailed or printed without any other resources.
+pub fn render_html(report: &Report) -> String {
+    let title = escape(&report.title());
+    let mut html = String::new();
+
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>",
+        title, STYLE, title
+    );
+
+    let figures = report.key_figures();
+    if figures.is_empty() {
+        html.push_str("<p>No weights were recorded in this interval.</p>\n");
+    } else {
+        html.push_str("<h2>Summary</h2>\n<table class=\"figures\">\n");
+        for (label, value) in figures {
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(label),
+                escape(&value)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    let _ = writeln!(
+        html,
+        "<h2>Chart</h2>\n<div class=\"chart\">\n{}</div>",
+        render_svg(&report.chart, &ImageOptions::default())
+    );
+
+    let rows = report.weekly_rows();
+    if !rows.is_empty() {
+        html.push_str("<h2>Weekly averages (kg)</h2>\n<table>\n<tr>");
+        for column in WEEKLY_COLUMNS {
+            let _ = write!(html, "<th>{}</th>", column);
+        }
+        html.push_str("</tr>\n");
+        for row in rows {
+            html.push_str("<tr>");
+            for cell in row {
+                let _ = write!(html, "<td>{}</td>", escape(&cell));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+    }
+
+    let _ = writeln!(
+        html,
+        "<footer>Generated by rs-weight-tracker on {}</footer>\n</body>\n</html>",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+    html
+}
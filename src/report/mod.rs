@@ -0,0 +1,154 @@
+mod html;
+mod pdf;
+
+use chrono::NaiveDate;
+use diesel::QueryResult;
+
+use crate::chart::ChartData;
+use crate::db::DbConnection;
+use crate::stats::{self, GoalProgress, Summary, WeeklyAggregate};
+
+pub use html::render_html;
+pub use pdf::render_pdf;
+
+/// A progress summary of an interval, for sharing with a coach.
+#[derive(Debug)]
+pub struct Report {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub summary: Option<Summary>,
+    pub weekly: Vec<WeeklyAggregate>,
+    /// Trend of the weights over the interval, in kg per week.
+    pub rate_per_week: Option<f64>,
+    /// Progress from the first weight of the interval towards the goal, if one is set.
+    pub goal: Option<GoalProgress>,
+    /// When the goal will be reached at the current rate.
+    pub goal_projection: Option<NaiveDate>,
+    pub chart: ChartData,
+}
+
+impl Report {
+    /// Computes the report between two dates (inclusive), with a rolling average over
+    /// `amount_of_days` days in the chart.
+    pub fn load(
+        conn: &mut DbConnection,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        amount_of_days: u32,
+    ) -> QueryResult<Self> {
+        let weights = crate::weights_between_dates(conn, start_date, end_date)?;
+        let chart = ChartData::load(conn, start_date, end_date, amount_of_days)?;
+
+        let summary = Summary::from_weights(&weights);
+        let rate_per_week = stats::weekly_rate_of_change(&weights);
+        let goal = chart.goal.zip(summary.as_ref()).map(|(goal, summary)| {
+            GoalProgress::new(goal, summary.first_weight, summary.last_weight)
+        });
+        let goal_projection = goal
+            .as_ref()
+            .zip(summary.as_ref())
+            .and_then(|(goal, summary)| {
+                stats::project_goal_date(summary.last_date, goal.current, goal.goal, rate_per_week?)
+            });
+
+        Ok(Report {
+            start_date,
+            end_date,
+            summary,
+            weekly: stats::weekly_aggregates(&weights),
+            rate_per_week,
+            goal,
+            goal_projection,
+            chart,
+        })
+    }
+
+    pub fn title(&self) -> String {
+        format!(
+            "Weight progress report {} to {}",
+            self.start_date.format("%Y-%m-%d"),
+            self.end_date.format("%Y-%m-%d")
+        )
+    }
+
+    /// The report as (label, value) pairs, shared by the HTML and PDF layouts.
+    fn key_figures(&self) -> Vec<(&'static str, String)> {
+        let mut figures = Vec::new();
+        let Some(summary) = &self.summary else {
+            return figures;
+        };
+
+        figures.push(("Measurements", summary.count.to_string()));
+        figures.push((
+            "First weight",
+            format!(
+                "{:.1} kg ({})",
+                summary.first_weight,
+                summary.first_date.format("%Y-%m-%d")
+            ),
+        ));
+        figures.push((
+            "Last weight",
+            format!(
+                "{:.1} kg ({})",
+                summary.last_weight,
+                summary.last_date.format("%Y-%m-%d")
+            ),
+        ));
+        figures.push(("Change", format!("{:+.1} kg", summary.change())));
+        figures.push((
+            "Lowest / highest",
+            format!(
+                "{:.1} kg / {:.1} kg",
+                summary.min_weight, summary.max_weight
+            ),
+        ));
+        figures.push(("Mean", format!("{:.1} kg", summary.mean_weight)));
+        if let Some(rate) = self.rate_per_week {
+            figures.push(("Rate of change", format!("{:+.2} kg/week", rate)));
+        }
+        if let Some(goal) = &self.goal {
+            figures.push((
+                "Goal",
+                format!(
+                    "{:.1} kg ({:.0}% done, {:.1} kg to go)",
+                    goal.goal,
+                    goal.fraction() * 100.0,
+                    goal.remaining().abs()
+                ),
+            ));
+            figures.push((
+                "Projected goal date",
+                self.goal_projection
+                    .map_or(String::from("not reached at the current rate"), |date| {
+                        date.format("%Y-%m-%d").to_string()
+                    }),
+            ));
+        }
+        figures
+    }
+
+    /// The weekly aggregates as table rows, with the change of the mean from the previous week.
+    fn weekly_rows(&self) -> Vec<[String; 6]> {
+        let mut previous_mean = None;
+        self.weekly
+            .iter()
+            .map(|week| {
+                let change = previous_mean.map_or(String::new(), |previous| {
+                    format!("{:+.1}", week.mean_weight - previous)
+                });
+                previous_mean = Some(week.mean_weight);
+                [
+                    week.week_start.format("%Y-%m-%d").to_string(),
+                    week.count.to_string(),
+                    format!("{:.1}", week.mean_weight),
+                    format!("{:.1}", week.min_weight),
+                    format!("{:.1}", week.max_weight),
+                    change,
+                ]
+            })
+            .collect()
+    }
+}
+
+const WEEKLY_COLUMNS: [&str; 6] = ["Week of", "Entries", "Mean", "Min", "Max", "Change"];
@@ -0,0 +1,412 @@
+//! A minimal PDF writer for the report: A4 pages using the standard Helvetica fonts, text and
+//! vector graphics only, so no fonts need to be embedded.
+
+use std::fmt::Write;
+
+use super::{Report, WEEKLY_COLUMNS};
+use crate::chart::{date_ticks, value_ticks, Rgb, Scale, Theme};
+
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 50.0;
+const CHART_HEIGHT: f64 = 260.0;
+
+/// Approximate width of a Helvetica character, relative to the font size. Digits are exactly
+/// this wide, which is what the right-aligned columns mostly contain.
+const CHAR_WIDTH: f64 = 0.556;
+
+fn text_width(text: &str, size: f64) -> f64 {
+    text.chars()
+        .map(|c| match c {
+            ' ' | '.' | ',' | ':' | 'i' | 'l' | 'j' | 'f' | 't' => 0.278,
+            '-' | 'r' | '(' | ')' => 0.333,
+            '+' => 0.584,
+            'm' | 'w' => 0.833,
+            'a'..='z' => 0.52,
+            'A'..='Z' => 0.667,
+            _ => CHAR_WIDTH,
+        })
+        .sum::<f64>()
+        * size
+}
+
+/// Keeps only the characters which are encoded the same in ASCII and in WinAnsiEncoding, and
+/// escapes the characters which are special in PDF strings.
+fn pdf_string(text: &str) -> String {
+    text.chars()
+        .map(|c| if (' '..='~').contains(&c) { c } else { '?' })
+        .collect::<String>()
+        .replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
+
+fn color((red, green, blue): Rgb) -> String {
+    format!(
+        "{:.3} {:.3} {:.3}",
+        red as f64 / 255.0,
+        green as f64 / 255.0,
+        blue as f64 / 255.0
+    )
+}
+
+/// Lays out content from the top of the page down, starting new pages as needed.
+struct Layout {
+    pages: Vec<String>,
+    content: String,
+    /// Distance of the cursor from the top of the page.
+    cursor: f64,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Layout {
+            pages: Vec::new(),
+            content: String::new(),
+            cursor: MARGIN,
+        }
+    }
+
+    /// Makes sure that `height` points fit on the current page, starting a new one otherwise.
+    fn reserve(&mut self, height: f64) {
+        if self.cursor + height > PAGE_HEIGHT - MARGIN && !self.content.is_empty() {
+            self.pages.push(std::mem::take(&mut self.content));
+            self.cursor = MARGIN;
+        }
+    }
+
+    /// Converts a distance from the top of the page to a PDF y coordinate.
+    fn y(top: f64) -> f64 {
+        PAGE_HEIGHT - top
+    }
+
+    /// Writes text with its baseline at `top` points from the top of the page.
+    fn text(&mut self, x: f64, top: f64, size: f64, bold: bool, text: &str, rgb: Rgb) {
+        let _ = writeln!(
+            self.content,
+            "BT {} rg /{} {} Tf {:.2} {:.2} Td ({}) Tj ET",
+            color(rgb),
+            if bold { "F2" } else { "F1" },
+            size,
+            x,
+            Self::y(top),
+            pdf_string(text)
+        );
+    }
+
+    fn text_right(&mut self, right: f64, top: f64, size: f64, text: &str, rgb: Rgb) {
+        let x = right - text_width(text, size);
+        self.text(x, top, size, false, text, rgb);
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)], rgb: Rgb, width: f64, dashed: bool) {
+        if points.len() < 2 {
+            return;
+        }
+        let _ = write!(
+            self.content,
+            "{} RG {} w {} ",
+            color(rgb),
+            width,
+            if dashed { "[3 2] 0 d" } else { "[] 0 d" }
+        );
+        for (index, (x, top)) in points.iter().enumerate() {
+            let operator = if index == 0 { "m" } else { "l" };
+            let _ = write!(self.content, "{:.2} {:.2} {} ", x, Self::y(*top), operator);
+        }
+        self.content.push_str("S\n");
+    }
+
+    fn circle(&mut self, x: f64, top: f64, radius: f64, rgb: Rgb, filled: bool) {
+        // Four Bezier curves, with the usual 0.5523 approximation of a quarter circle.
+        let k = radius * 0.5523;
+        let y = Self::y(top);
+        let _ = writeln!(
+            self.content,
+            "{c} RG {c} rg 1 w [] 0 d {x0:.2} {y:.2} m {x0:.2} {y1:.2} {x2:.2} {y2:.2} {x:.2} {y2:.2} c \
+             {x3:.2} {y2:.2} {x4:.2} {y1:.2} {x4:.2} {y:.2} c {x4:.2} {y3:.2} {x3:.2} {y4:.2} {x:.2} {y4:.2} c \
+             {x2:.2} {y4:.2} {x0:.2} {y3:.2} {x0:.2} {y:.2} c {op}",
+            c = color(rgb),
+            x = x,
+            y = y,
+            x0 = x + radius,
+            x2 = x + k,
+            x3 = x - k,
+            x4 = x - radius,
+            y1 = y + k,
+            y2 = y + radius,
+            y3 = y - k,
+            y4 = y - radius,
+            op = if filled { "f" } else { "S" }
+        );
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if !self.content.is_empty() || self.pages.is_empty() {
+            self.pages.push(self.content);
+        }
+        self.pages
+    }
+}
+
+fn draw_chart(layout: &mut Layout, report: &Report) {
+    let data = &report.chart;
+    let palette = Theme::Light.palette();
+
+    layout.reserve(CHART_HEIGHT + 30.0);
+    let top = layout.cursor;
+    layout.cursor += CHART_HEIGHT + 50.0;
+
+    let Some((min, max)) = data.value_range() else {
+        layout.text(
+            MARGIN,
+            top + 20.0,
+            10.0,
+            false,
+            "No weights to display",
+            palette.text,
+        );
+        return;
+    };
+
+    let (ticks, min, max) = value_ticks(min, max, 8);
+    let scale = Scale {
+        left: MARGIN + 30.0,
+        top: top + 10.0,
+        width: PAGE_WIDTH - 2.0 * MARGIN - 30.0,
+        height: CHART_HEIGHT - 20.0,
+        first_date: data.start_date,
+        span_days: (data.end_date - data.start_date).num_days().max(1) as f64,
+        min,
+        max,
+    };
+
+    for tick in ticks {
+        let y = scale.y(tick);
+        layout.polyline(
+            &[(scale.left, y), (scale.left + scale.width, y)],
+            palette.grid,
+            0.5,
+            false,
+        );
+        layout.text_right(
+            scale.left - 4.0,
+            y + 3.0,
+            8.0,
+            &format!("{:.1}", tick),
+            palette.text,
+        );
+    }
+    for date in date_ticks(data.start_date, data.end_date, 5) {
+        let label = date.format("%Y-%m-%d").to_string();
+        let x = scale.x(date) - text_width(&label, 8.0) / 2.0;
+        layout.text(
+            x,
+            scale.top + scale.height + 14.0,
+            8.0,
+            false,
+            &label,
+            palette.text,
+        );
+    }
+
+    if let Some(goal) = data.goal {
+        let y = scale.y(goal);
+        layout.polyline(
+            &[(scale.left, y), (scale.left + scale.width, y)],
+            palette.goal,
+            1.0,
+            true,
+        );
+    }
+
+    for (is_interpolated, points) in data.weight_runs(&scale) {
+        layout.polyline(&points, palette.weight, 0.75, is_interpolated);
+    }
+    let averages: Vec<(f64, f64)> = data
+        .averages
+        .iter()
+        .map(|(date, average)| (scale.x(*date), scale.y(*average)))
+        .collect();
+    layout.polyline(&averages, palette.average, 2.0, false);
+
+    for (weight, is_interpolated) in &data.weights {
+        if *is_interpolated {
+            continue;
+        }
+        let x = scale.x(weight.measurement_date);
+        let y = scale.y(weight.weight_value);
+        layout.circle(x, y, 1.5, palette.weight, true);
+        if data.is_outlier(weight.measurement_date) {
+            layout.circle(x, y, 4.0, palette.outlier, false);
+        }
+    }
+
+    let legend = [
+        (palette.weight, String::from("weight")),
+        (
+            palette.average,
+            format!("{}-day rolling average", data.average_days),
+        ),
+        (palette.goal, String::from("goal")),
+        (palette.outlier, String::from("outlier")),
+    ];
+    let mut x = scale.left;
+    let legend_top = top + CHART_HEIGHT + 22.0;
+    for (rgb, label) in legend {
+        layout.polyline(
+            &[(x, legend_top - 3.0), (x + 12.0, legend_top - 3.0)],
+            rgb,
+            3.0,
+            false,
+        );
+        layout.text(x + 16.0, legend_top, 8.0, false, &label, palette.text);
+        x += 32.0 + text_width(&label, 8.0);
+    }
+}
+
+/// Renders the report as a PDF document.
+pub fn render_pdf(report: &Report) -> Vec<u8> {
+    let text_color = Theme::Light.palette().text;
+    let mut layout = Layout::new();
+
+    layout.cursor += 16.0;
+    layout.text(
+        MARGIN,
+        layout.cursor,
+        16.0,
+        true,
+        &report.title(),
+        text_color,
+    );
+    layout.cursor += 28.0;
+
+    let figures = report.key_figures();
+    if figures.is_empty() {
+        layout.text(
+            MARGIN,
+            layout.cursor,
+            10.0,
+            false,
+            "No weights were recorded in this interval.",
+            text_color,
+        );
+        layout.cursor += 20.0;
+    } else {
+        layout.text(MARGIN, layout.cursor, 12.0, true, "Summary", text_color);
+        layout.cursor += 18.0;
+        for (label, value) in figures {
+            layout.reserve(14.0);
+            layout.text(MARGIN, layout.cursor, 10.0, false, label, text_color);
+            layout.text(
+                MARGIN + 140.0,
+                layout.cursor,
+                10.0,
+                false,
+                &value,
+                text_color,
+            );
+            layout.cursor += 14.0;
+        }
+        layout.cursor += 10.0;
+    }
+
+    layout.reserve(CHART_HEIGHT + 50.0);
+    layout.text(MARGIN, layout.cursor, 12.0, true, "Chart", text_color);
+    layout.cursor += 8.0;
+    draw_chart(&mut layout, report);
+
+    let rows = report.weekly_rows();
+    if !rows.is_empty() {
+        let column_right = |index: usize| MARGIN + 90.0 + index as f64 * 70.0;
+
+        layout.reserve(50.0);
+        layout.text(
+            MARGIN,
+            layout.cursor,
+            12.0,
+            true,
+            "Weekly averages (kg)",
+            text_color,
+        );
+        layout.cursor += 18.0;
+        for (index, column) in WEEKLY_COLUMNS.iter().enumerate() {
+            if index == 0 {
+                layout.text(MARGIN, layout.cursor, 10.0, true, column, text_color);
+            } else {
+                layout.text_right(column_right(index), layout.cursor, 10.0, column, text_color);
+            }
+        }
+        layout.cursor += 14.0;
+
+        for row in rows {
+            layout.reserve(14.0);
+            for (index, cell) in row.iter().enumerate() {
+                if index == 0 {
+                    layout.text(MARGIN, layout.cursor, 10.0, false, cell, text_color);
+                } else {
+                    layout.text_right(column_right(index), layout.cursor, 10.0, cell, text_color);
+                }
+            }
+            layout.cursor += 14.0;
+        }
+    }
+
+    write_document(&layout.finish())
+}
+
+/// Assembles the pages' content streams into a PDF file, with its cross-reference table.
+fn write_document(pages: &[String]) -> Vec<u8> {
+    // Objects 1 to 4 are the catalog, the page tree and the two fonts, followed by a page
+    // object and a content stream for each page.
+    let page_ids: Vec<usize> = (0..pages.len()).map(|index| 5 + index * 2).collect();
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+
+    let mut objects = vec![
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        ),
+        String::from(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>",
+        ),
+        String::from(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>",
+        ),
+    ];
+    for (page, id) in pages.iter().zip(&page_ids) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            page.len(),
+            page
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        let _ = write!(pdf, "{} 0 obj\n{}\nendobj\n", index + 1, object);
+    }
+
+    let xref = pdf.len();
+    let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.into_bytes()
+}
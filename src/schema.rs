@@ -1,9 +1,73 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    events (id) {
+        id -> Integer,
+        kind -> Text,
+        title -> Text,
+        start_date -> Date,
+        end_date -> Nullable<Date>,
+        note -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    idempotency_keys (idempotency_key) {
+        idempotency_key -> Text,
+        request_hash -> Text,
+        status_code -> Nullable<Integer>,
+        response_body -> Nullable<Text>,
+        created_at -> Timestamp,
+        response_headers -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    weight_history (id) {
+        id -> Integer,
+        measurement_date -> Date,
+        action -> Text,
+        old_value -> Nullable<Double>,
+        new_value -> Nullable<Double>,
+        changed_at -> Timestamp,
+        source -> Text,
+        actor -> Text,
+    }
+}
+
+diesel::table! {
+    weight_tags (weight_id, tag_id) {
+        weight_id -> Integer,
+        tag_id -> Integer,
+    }
+}
+
 diesel::table! {
     weights (id) {
         id -> Integer,
         weight_value -> Double,
         measurement_date -> Date,
+        deleted_at -> Nullable<Timestamp>,
+        note -> Nullable<Text>,
+        version -> Integer,
     }
 }
+
+diesel::joinable!(weight_tags -> tags (tag_id));
+diesel::joinable!(weight_tags -> weights (weight_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    events,
+    idempotency_keys,
+    tags,
+    weight_history,
+    weight_tags,
+    weights,
+);
@@ -0,0 +1,129 @@
+//! Plumbing of the HTTP servers of the back-end and the frontend.
+
+use axum::body::Body;
+use axum::http::uri::PathAndQuery;
+use axum::http::{header, Request, StatusCode, Uri};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tower::Layer;
+
+/// How often the certificate files are checked for changes.
+const CERTIFICATE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Serves an app under a base path, such as `/weight-tracker`, so that it works behind a reverse
+/// proxy that forwards a sub-path without rewriting it. The app sees the paths without the base
+/// path; the base path itself is redirected to `<base path>/`, so that the relative links of the
+/// pages resolve, and the paths outside it are not found.
+pub fn with_base_path(app: Router, base_path: &str) -> Router {
+    if base_path.is_empty() {
+        return app;
+    }
+    let base_path: Arc<str> = Arc::from(base_path);
+    let strip = middleware::from_fn(move |request: Request<Body>, next: Next<Body>| {
+        let base_path = base_path.clone();
+        async move { strip_base_path(&base_path, request, next).await }
+    });
+    // The path must be rewritten before the routing of the app, so the app is wrapped as a whole.
+    Router::new().fallback_service(strip.layer(app))
+}
+
+async fn strip_base_path(
+    base_path: &str,
+    mut request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let uri = request.uri();
+    let Some(rest) = uri.path().strip_prefix(base_path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if rest.is_empty() {
+        let location = match uri.query() {
+            Some(query) => format!("{}/?{}", base_path, query),
+            None => format!("{}/", base_path),
+        };
+        return Redirect::permanent(&location).into_response();
+    }
+    if !rest.starts_with('/') {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", rest, query),
+        None => rest.to_owned(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = PathAndQuery::try_from(path_and_query).ok();
+    match Uri::from_parts(parts) {
+        Ok(stripped) => *request.uri_mut() = stripped,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    }
+    next.run(request).await
+}
+
+/// An app that redirects every request to the same URL over HTTPS on another port, for the
+/// clients that still use plain HTTP.
+pub fn https_redirect(https_port: u16) -> Router {
+    Router::new().fallback(move |request: Request<Body>| async move {
+        let host = request
+            .headers()
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(host_name);
+        let Some(host) = host else {
+            return (StatusCode::BAD_REQUEST, "Missing Host header").into_response();
+        };
+        let path_and_query = request
+            .uri()
+            .path_and_query()
+            .map_or("/", PathAndQuery::as_str);
+        let location = match https_port {
+            443 => format!("https://{}{}", host, path_and_query),
+            port => format!("https://{}:{}{}", host, port, path_and_query),
+        };
+        Redirect::permanent(&location).into_response()
+    })
+}
+
+/// The host of a `Host` header without its port, e.g. `[::1]` for `[::1]:8080`.
+fn host_name(host: &str) -> &str {
+    match host.find(']') {
+        Some(end) => &host[..=end],
+        None => host.split(':').next().unwrap_or(host),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Reloads the TLS certificate and key whenever one of their files changes, e.g. when a
+/// certificate is renewed, so that the servers do not need a restart. The connections already
+/// open keep the previous certificate. When the new files cannot be loaded, as while they are
+/// being written, the previous certificate is kept until the next change.
+pub async fn reload_certificate_on_change(tls: RustlsConfig, cert: PathBuf, key: PathBuf) {
+    let mut loaded = (modified(&cert), modified(&key));
+    let mut interval = tokio::time::interval(CERTIFICATE_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let current = (modified(&cert), modified(&key));
+        if current == loaded {
+            continue;
+        }
+        loaded = current;
+        match tls.reload_from_pem_file(&cert, &key).await {
+            Ok(()) => tracing::info!("Reloaded the TLS certificate {}", cert.display()),
+            Err(err) => tracing::error!(
+                "Failed to reload the TLS certificate {}: {}",
+                cert.display(),
+                err
+            ),
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::Weight;
+
+/// Summary statistics over a series of actual (non-interpolated) weights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub first_date: NaiveDate,
+    pub last_date: NaiveDate,
+    pub first_weight: f64,
+    pub last_weight: f64,
+    pub min_weight: f64,
+    pub max_weight: f64,
+    pub mean_weight: f64,
+}
+
+impl Summary {
+    /// Computes the summary of the given weights, in any order. Returns `None` for an empty slice.
+    pub fn from_weights(weights: &[Weight]) -> Option<Summary> {
+        let first = weights
+            .iter()
+            .min_by_key(|weight| weight.measurement_date)?;
+        let last = weights
+            .iter()
+            .max_by_key(|weight| weight.measurement_date)?;
+
+        let values = weights.iter().map(|weight| weight.weight_value);
+        let sum: f64 = values.clone().sum();
+
+        Some(Summary {
+            count: weights.len(),
+            first_date: first.measurement_date,
+            last_date: last.measurement_date,
+            first_weight: first.weight_value,
+            last_weight: last.weight_value,
+            min_weight: values.clone().fold(f64::INFINITY, f64::min),
+            max_weight: values.fold(f64::NEG_INFINITY, f64::max),
+            mean_weight: sum / weights.len() as f64,
+        })
+    }
+
+    /// The difference between the last and the first weight (negative when losing weight).
+    pub fn change(&self) -> f64 {
+        self.last_weight - self.first_weight
+    }
+}
+
+/// Progress towards a target weight, measured from a starting weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub goal: f64,
+    pub start: f64,
+    pub current: f64,
+}
+
+impl GoalProgress {
+    pub fn new(goal: f64, start: f64, current: f64) -> Self {
+        GoalProgress {
+            goal,
+            start,
+            current,
+        }
+    }
+
+    /// The weight still to lose (positive) or gain (negative) to reach the goal.
+    pub fn remaining(&self) -> f64 {
+        self.current - self.goal
+    }
+
+    /// The fraction of the way from the start to the goal, between 0.0 and 1.0.
+    pub fn fraction(&self) -> f64 {
+        let total = self.start - self.goal;
+        if total.abs() < f64::EPSILON {
+            return 1.0;
+        }
+        ((self.start - self.current) / total).clamp(0.0, 1.0)
+    }
+}
+
+/// Aggregated weights of one calendar week, starting on Monday.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeeklyAggregate {
+    pub week_start: NaiveDate,
+    pub count: usize,
+    pub min_weight: f64,
+    pub max_weight: f64,
+    pub mean_weight: f64,
+}
+
+/// Groups the weights by calendar week (Monday to Sunday), in chronological order.
+pub fn weekly_aggregates(weights: &[Weight]) -> Vec<WeeklyAggregate> {
+    let mut weeks: BTreeMap<NaiveDate, Vec<f64>> = BTreeMap::new();
+    for weight in weights {
+        let date = weight.measurement_date;
+        let week_start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+        weeks
+            .entry(week_start)
+            .or_default()
+            .push(weight.weight_value);
+    }
+
+    weeks
+        .into_iter()
+        .map(|(week_start, values)| WeeklyAggregate {
+            week_start,
+            count: values.len(),
+            min_weight: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max_weight: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            mean_weight: values.iter().sum::<f64>() / values.len() as f64,
+        })
+        .collect()
+}
+
+/// The trend of the weights in kg per week (negative when losing weight), as the slope of the
+/// least squares line through them. Returns `None` with fewer than two distinct dates.
+pub fn weekly_rate_of_change(weights: &[Weight]) -> Option<f64> {
+    let first_date = weights.iter().map(|weight| weight.measurement_date).min()?;
+    let points: Vec<(f64, f64)> = weights
+        .iter()
+        .map(|weight| {
+            let day = (weight.measurement_date - first_date).num_days() as f64;
+            (day, weight.weight_value)
+        })
+        .collect();
+
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+    (variance > 0.0).then(|| covariance / variance * 7.0)
+}
+
+/// Estimates when the goal will be reached if the weight keeps changing at the given rate (in
+/// kg per week). Returns `None` when the weight is not moving towards the goal.
+pub fn project_goal_date(
+    last_date: NaiveDate,
+    current: f64,
+    goal: f64,
+    rate_per_week: f64,
+) -> Option<NaiveDate> {
+    let remaining = goal - current;
+    if remaining == 0.0 {
+        return Some(last_date);
+    }
+    if rate_per_week == 0.0 || remaining.signum() != rate_per_week.signum() {
+        return None;
+    }
+
+    let days = (remaining / rate_per_week * 7.0).ceil();
+    last_date.checked_add_signed(Duration::try_days(days as i64)?)
+}
+
+/// The weights measured during a phase of the timeline, such as a diet or a bulk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseSummary {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// The length of the phase in days, counting the first and the last day.
+    pub duration_days: i64,
+    /// The number of weights measured during the phase.
+    pub count: usize,
+    /// The first weight measured during the phase.
+    pub start_weight: f64,
+    /// The last weight measured during the phase.
+    pub end_weight: f64,
+    /// The trend in kg per week, see [`weekly_rate_of_change`]; `None` with a single weight.
+    pub rate_per_week: Option<f64>,
+}
+
+impl PhaseSummary {
+    /// The difference between the last and the first weight (negative when losing weight).
+    pub fn change(&self) -> f64 {
+        self.end_weight - self.start_weight
+    }
+}
+
+/// Summarizes the weights between two dates (inclusive); the weights may cover a longer
+/// interval, in any order. Returns `None` when no weight was measured in the phase.
+pub fn phase_summary(
+    weights: &[Weight],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Option<PhaseSummary> {
+    let in_phase: Vec<Weight> = weights
+        .iter()
+        .filter(|weight| (start_date..=end_date).contains(&weight.measurement_date))
+        .cloned()
+        .collect();
+    let summary = Summary::from_weights(&in_phase)?;
+
+    Some(PhaseSummary {
+        start_date,
+        end_date,
+        duration_days: (end_date - start_date).num_days() + 1,
+        count: summary.count,
+        start_weight: summary.first_weight,
+        end_weight: summary.last_weight,
+        rate_per_week: weekly_rate_of_change(&in_phase),
+    })
+}
+
+/// Fills the days without a weight between two dates (inclusive), given the weights of that
+/// interval oldest first. A day between two weights gets the value on the line joining them; a day
+/// after the last weight gets the last weight; the days before the first weight are left out.
+/// Each weight comes with whether it is interpolated.
+pub fn interpolate(
+    weights: &[Weight],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Vec<(Weight, bool)> {
+    let interpolated = |weight_value, measurement_date| Weight {
+        id: 0,
+        weight_value,
+        measurement_date,
+    };
+
+    let mut series = Vec::new();
+    let mut previous: Option<&Weight> = None;
+    let mut current_date = start_date;
+    for weight in weights {
+        if let Some(previous) = previous {
+            let span = (weight.measurement_date - previous.measurement_date).num_days() as f64;
+            while current_date < weight.measurement_date {
+                let ratio = (current_date - previous.measurement_date).num_days() as f64 / span;
+                let value =
+                    previous.weight_value + ratio * (weight.weight_value - previous.weight_value);
+                series.push((interpolated(value, current_date), true));
+                current_date = current_date.succ_opt().unwrap_or(current_date);
+            }
+        }
+        series.push((weight.clone(), false));
+        previous = Some(weight);
+        current_date = weight.measurement_date.succ_opt().unwrap_or(current_date);
+    }
+    if let Some(last) = previous {
+        while current_date <= end_date {
+            series.push((interpolated(last.weight_value, current_date), true));
+            current_date = current_date.succ_opt().unwrap_or(current_date);
+        }
+    }
+    series
+}
+
+/// The average of each day from `start_date` on over the `amount_of_days` days ending with it,
+/// given one weight per day oldest first, such as returned by [`interpolate`] from
+/// `amount_of_days - 1` days before `start_date`.
+pub fn rolling_averages(
+    weights: &[(Weight, bool)],
+    start_date: NaiveDate,
+    amount_of_days: u32,
+) -> Vec<(NaiveDate, f64)> {
+    assert!(amount_of_days > 0);
+
+    let window_start = start_date - Duration::days(amount_of_days as i64 - 1);
+    let mut rolling_window: Vec<f64> = Vec::with_capacity(amount_of_days as usize);
+    let mut rolling_sum = 0.0;
+    let mut results = Vec::new();
+
+    for (weight, _) in weights {
+        let value = weight.weight_value;
+        rolling_sum += value;
+
+        if rolling_window.len() == amount_of_days as usize {
+            rolling_sum -= rolling_window[0];
+            rolling_window.remove(0);
+        }
+
+        rolling_window.push(value);
+
+        let days = (weight.measurement_date - window_start).num_days();
+
+        if days >= amount_of_days as i64 - 1 {
+            let average = rolling_sum / rolling_window.len() as f64;
+            results.push((weight.measurement_date, average));
+        }
+    }
+
+    results
+}
+
+/// Number of standard deviations from the rolling average beyond which a weight is an outlier.
+const OUTLIER_DEVIATIONS: f64 = 2.0;
+
+/// Returns the dates of the actual (non-interpolated) weights deviating unusually far from the
+/// rolling average of the same day: more than two standard deviations of all such deviations.
+pub fn outliers(weights: &[(Weight, bool)], averages: &[(NaiveDate, f64)]) -> Vec<NaiveDate> {
+    let averages: HashMap<NaiveDate, f64> = averages.iter().copied().collect();
+    let residuals: Vec<(NaiveDate, f64)> = weights
+        .iter()
+        .filter(|(_, is_interpolated)| !is_interpolated)
+        .filter_map(|(weight, _)| {
+            averages
+                .get(&weight.measurement_date)
+                .map(|average| (weight.measurement_date, weight.weight_value - average))
+        })
+        .collect();
+
+    if residuals.len() < 3 {
+        return Vec::new();
+    }
+
+    let count = residuals.len() as f64;
+    let mean = residuals.iter().map(|(_, residual)| residual).sum::<f64>() / count;
+    let variance = residuals
+        .iter()
+        .map(|(_, residual)| (residual - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let limit = OUTLIER_DEVIATIONS * variance.sqrt();
+
+    residuals
+        .into_iter()
+        .filter(|(_, residual)| limit > 0.0 && (residual - mean).abs() > limit)
+        .map(|(date, _)| date)
+        .collect()
+}
@@ -0,0 +1,154 @@
+//! Where the weights are kept: the [`WeightStore`] trait, implemented by the database connection
+//! and by [`InMemoryStore`], so that the analytics built on it can run without a database.
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::db::DbConnection;
+use crate::history::Origin;
+use crate::models::{NewWeight, Weight};
+use crate::UpsertOutcome;
+
+/// The weights of the days, at most one per day.
+pub trait WeightStore {
+    /// The weights between two dates (inclusive), oldest first.
+    fn weights_between(
+        &mut self,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> QueryResult<Vec<Weight>>;
+
+    /// Stores the weight of a day, replacing any other weight of that day.
+    fn upsert(
+        &mut self,
+        weight_value: f64,
+        measurement_date: NaiveDate,
+        origin: &Origin,
+    ) -> QueryResult<UpsertOutcome>;
+
+    /// Removes the weight of a day. Returns the number of weights removed, 0 or 1.
+    fn delete(&mut self, measurement_date: NaiveDate, origin: &Origin) -> QueryResult<usize>;
+
+    /// Every weight, newest first.
+    fn list(&mut self) -> QueryResult<Vec<Weight>>;
+}
+
+/// The weights of the database. Every change is recorded in the history, and a deleted weight
+/// goes to the trash.
+impl WeightStore for DbConnection {
+    fn weights_between(
+        &mut self,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> QueryResult<Vec<Weight>> {
+        crate::weights_between_dates(self, start_date, end_date)
+    }
+
+    fn upsert(
+        &mut self,
+        weight_value: f64,
+        measurement_date: NaiveDate,
+        origin: &Origin,
+    ) -> QueryResult<UpsertOutcome> {
+        crate::upsert_weight_with_outcome(self, weight_value, measurement_date, origin)
+    }
+
+    fn delete(&mut self, measurement_date: NaiveDate, origin: &Origin) -> QueryResult<usize> {
+        crate::delete_weight(self, measurement_date, origin)
+    }
+
+    fn list(&mut self) -> QueryResult<Vec<Weight>> {
+        Weight::all().load(self)
+    }
+}
+
+/// Weights kept in memory, for tests and for computations on weights that are not stored. There
+/// is no history nor trash: the origin of the changes is ignored and a deleted weight is gone.
+///
+/// ```rust
+/// use chrono::NaiveDate;
+/// use rs_weight_tracker::store::{InMemoryStore, WeightStore};
+/// use rs_weight_tracker::NewWeight;
+///
+/// let day = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+/// let mut store: InMemoryStore = [NewWeight::new(80.0, day)].into_iter().collect();
+/// assert_eq!(store.list().unwrap()[0].weight_value, 80.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStore {
+    weights: BTreeMap<NaiveDate, Weight>,
+    last_id: i32,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        InMemoryStore::default()
+    }
+
+    /// Stores a weight, replacing the one of the same day. A new weight gets the next id, like a
+    /// new row of the database.
+    fn store(&mut self, weight_value: f64, measurement_date: NaiveDate) -> UpsertOutcome {
+        match self.weights.get_mut(&measurement_date) {
+            Some(weight) if weight.weight_value == weight_value => UpsertOutcome::Unchanged,
+            Some(weight) => {
+                weight.weight_value = weight_value;
+                UpsertOutcome::Updated
+            }
+            None => {
+                self.last_id += 1;
+                let weight = Weight {
+                    id: self.last_id,
+                    weight_value,
+                    measurement_date,
+                };
+                self.weights.insert(measurement_date, weight);
+                UpsertOutcome::Inserted
+            }
+        }
+    }
+}
+
+impl FromIterator<NewWeight> for InMemoryStore {
+    fn from_iter<I: IntoIterator<Item = NewWeight>>(weights: I) -> Self {
+        let mut store = InMemoryStore::new();
+        for weight in weights {
+            store.store(weight.weight_value, weight.measurement_date);
+        }
+        store
+    }
+}
+
+impl WeightStore for InMemoryStore {
+    fn weights_between(
+        &mut self,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> QueryResult<Vec<Weight>> {
+        if start_date > end_date {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .weights
+            .range(start_date..=end_date)
+            .map(|(_, weight)| weight.clone())
+            .collect())
+    }
+
+    fn upsert(
+        &mut self,
+        weight_value: f64,
+        measurement_date: NaiveDate,
+        _origin: &Origin,
+    ) -> QueryResult<UpsertOutcome> {
+        Ok(self.store(weight_value, measurement_date))
+    }
+
+    fn delete(&mut self, measurement_date: NaiveDate, _origin: &Origin) -> QueryResult<usize> {
+        Ok(self.weights.remove(&measurement_date).map_or(0, |_| 1))
+    }
+
+    fn list(&mut self) -> QueryResult<Vec<Weight>> {
+        Ok(self.weights.values().rev().cloned().collect())
+    }
+}
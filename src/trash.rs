@@ -0,0 +1,139 @@
+//! Deleted weights are kept in the trash, where they can be restored until they are purged.
+//!
+//! A weight in the trash is a row of `weights` with a `deleted_at` time. The queries of the
+//! weights leave such rows out.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+use crate::db::{write_transaction, DbConnection};
+use crate::history::{self, ChangeAction};
+use crate::{Origin, Weight};
+
+/// How long deleted weights are kept by default, see
+/// [`Config::trash_retention`](crate::config::Config::trash_retention).
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// The longest retention accepted in the configuration, a hundred years.
+pub const MAX_RETENTION_DAYS: i64 = 36_500;
+
+/// The weights in the trash with the time (UTC) they were deleted, most recently deleted first.
+pub fn deleted_weights(conn: &mut DbConnection) -> QueryResult<Vec<(Weight, NaiveDateTime)>> {
+    use crate::schema::weights::dsl::*;
+
+    let rows = weights
+        .filter(deleted_at.is_not_null())
+        .order(deleted_at.desc())
+        .select((Weight::as_select(), deleted_at))
+        .load::<(Weight, Option<NaiveDateTime>)>(conn)?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(weight, deleted)| Some((weight, deleted?)))
+        .collect())
+}
+
+/// Takes the weight of a day out of the trash. Returns the restored weight, or `None` when the
+/// day has no weight in the trash.
+pub fn restore_weight(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+    origin: &Origin,
+) -> QueryResult<Option<Weight>> {
+    write_transaction(conn, |conn| {
+        restore_in_transaction(conn, in_measurement_date, origin)
+    })
+}
+
+/// Undoes the most recent delete: restores the weight that was moved to the trash last.
+pub fn restore_last_deleted(
+    conn: &mut DbConnection,
+    origin: &Origin,
+) -> QueryResult<Option<Weight>> {
+    use crate::schema::weights::dsl::*;
+
+    write_transaction(conn, |conn| {
+        let last_deleted = weights
+            .filter(deleted_at.is_not_null())
+            .order(deleted_at.desc())
+            .select(measurement_date)
+            .first::<NaiveDate>(conn)
+            .optional()?;
+        match last_deleted {
+            Some(date) => restore_in_transaction(conn, date, origin),
+            None => Ok(None),
+        }
+    })
+}
+
+fn restore_in_transaction(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+    origin: &Origin,
+) -> QueryResult<Option<Weight>> {
+    use crate::schema::weights::dsl::*;
+
+    let deleted_weight = weights
+        .filter(measurement_date.eq(in_measurement_date))
+        .filter(deleted_at.is_not_null());
+    let Some(weight) = deleted_weight
+        .select(Weight::as_select())
+        .first::<Weight>(conn)
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    diesel::update(deleted_weight)
+        .set((
+            deleted_at.eq(None::<NaiveDateTime>),
+            version.eq(version + 1),
+        ))
+        .execute(conn)?;
+    history::record(
+        conn,
+        in_measurement_date,
+        ChangeAction::Restore,
+        None,
+        Some(weight.weight_value),
+        origin,
+    )?;
+    weights
+        .find(weight.id)
+        .select(Weight::as_select())
+        .first::<Weight>(conn)
+        .map(Some)
+}
+
+/// Removes for good the weights that have been in the trash for longer than `retention`.
+/// Returns the number of purged weights: none when the retention reaches back before the earliest
+/// time there is.
+pub fn purge_deleted(
+    conn: &mut DbConnection,
+    retention: Duration,
+    origin: &Origin,
+) -> QueryResult<usize> {
+    use crate::schema::weights::dsl::*;
+
+    let Some(cutoff) = Utc::now().naive_utc().checked_sub_signed(retention) else {
+        return Ok(0);
+    };
+    write_transaction(conn, |conn| {
+        let expired = weights
+            .filter(deleted_at.lt(cutoff))
+            .select(Weight::as_select())
+            .load::<Weight>(conn)?;
+        for weight in &expired {
+            history::record(
+                conn,
+                weight.measurement_date,
+                ChangeAction::Purge,
+                Some(weight.weight_value),
+                None,
+                origin,
+            )?;
+        }
+        // Their tags go with them: `weight_tags` cascades the deletes.
+        let expired_ids: Vec<i32> = expired.iter().map(|weight| weight.id).collect();
+        diesel::delete(weights.filter(id.eq_any(&expired_ids))).execute(conn)
+    })
+}
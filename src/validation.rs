@@ -0,0 +1,239 @@
+//! Plausibility checks of the weights to store, so that a typo such as `19` instead of `91`, a
+//! negative value or a date far in the future does not end up in the statistics.
+//!
+//! The checks are not applied by the upsert functions themselves: the tools that store weights
+//! entered by a user run [`ValidationRules::check`] first, unless the user forces the weight.
+
+use chrono::{Local, NaiveDate};
+use diesel::prelude::*;
+use serde::Deserialize;
+use std::fmt;
+
+use crate::db::DbConnection;
+
+/// The lowest weight accepted by default.
+pub const DEFAULT_MIN_KG: f64 = 30.0;
+/// The highest weight accepted by default.
+pub const DEFAULT_MAX_KG: f64 = 300.0;
+/// The largest change per day accepted by default.
+pub const DEFAULT_MAX_DAILY_CHANGE_KG: f64 = 3.0;
+/// How many days into the future a date may be by default. One day leaves room for the time
+/// zones.
+pub const DEFAULT_FUTURE_TOLERANCE_DAYS: i64 = 1;
+
+/// The bounds a weight must respect to be stored without forcing it, from the `[validation]`
+/// section of the [configuration](crate::config).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidationRules {
+    pub min_kg: f64,
+    pub max_kg: f64,
+    /// The largest change from the closest weight before or after, per day between the two.
+    pub max_daily_change_kg: f64,
+    /// How many days after today a weight may be dated.
+    pub future_tolerance_days: i64,
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        ValidationRules {
+            min_kg: DEFAULT_MIN_KG,
+            max_kg: DEFAULT_MAX_KG,
+            max_daily_change_kg: DEFAULT_MAX_DAILY_CHANGE_KG,
+            future_tolerance_days: DEFAULT_FUTURE_TOLERANCE_DAYS,
+        }
+    }
+}
+
+impl ValidationRules {
+    /// Checks that the rules can be met, returning a message for the user when they cannot.
+    pub fn validate(&self) -> Result<(), String> {
+        let bounds_valid =
+            self.min_kg > 0.0 && self.min_kg < self.max_kg && self.max_kg.is_finite();
+        if !bounds_valid {
+            return Err(format!(
+                "min_kg ({}) must be positive and below max_kg ({})",
+                self.min_kg, self.max_kg
+            ));
+        }
+        let change_valid = self.max_daily_change_kg > 0.0 && self.max_daily_change_kg.is_finite();
+        if !change_valid {
+            return Err(String::from("max_daily_change_kg must be positive"));
+        }
+        if self.future_tolerance_days < 0 {
+            return Err(String::from("future_tolerance_days must not be negative"));
+        }
+        Ok(())
+    }
+
+    /// Checks a weight to store for a day against the rules and the closest weights stored
+    /// before and after it. Returns every rule it breaks; an empty list means it is plausible.
+    pub fn check(
+        &self,
+        conn: &mut DbConnection,
+        value: f64,
+        date: NaiveDate,
+    ) -> QueryResult<Vec<ValidationError>> {
+        self.check_on(conn, value, date, Local::now().date_naive())
+    }
+
+    /// Like [`ValidationRules::check`], for the given current day.
+    pub fn check_on(
+        &self,
+        conn: &mut DbConnection,
+        value: f64,
+        date: NaiveDate,
+        today: NaiveDate,
+    ) -> QueryResult<Vec<ValidationError>> {
+        use crate::schema::weights::dsl::*;
+
+        let mut errors = Vec::new();
+        if !value.is_finite() {
+            errors.push(ValidationError::NotFinite);
+        } else if value < self.min_kg {
+            errors.push(ValidationError::TooLow {
+                value,
+                min_kg: self.min_kg,
+            });
+        } else if value > self.max_kg {
+            errors.push(ValidationError::TooHigh {
+                value,
+                max_kg: self.max_kg,
+            });
+        }
+
+        let latest = today + chrono::Duration::days(self.future_tolerance_days);
+        if date > latest {
+            errors.push(ValidationError::InFuture { date, latest });
+        }
+
+        // The change from the other days is only worth reporting for a weight within the bounds.
+        if errors.iter().all(|error| error.field() != "weight") {
+            let previous = weights
+                .filter(deleted_at.is_null())
+                .filter(measurement_date.lt(date))
+                .order(measurement_date.desc())
+                .select((measurement_date, weight_value))
+                .first::<(NaiveDate, f64)>(conn)
+                .optional()?;
+            let next = weights
+                .filter(deleted_at.is_null())
+                .filter(measurement_date.gt(date))
+                .order(measurement_date.asc())
+                .select((measurement_date, weight_value))
+                .first::<(NaiveDate, f64)>(conn)
+                .optional()?;
+
+            for (other_date, other_value) in previous.into_iter().chain(next) {
+                let days = (other_date - date).num_days().abs() as f64;
+                let max_change_kg = self.max_daily_change_kg * days;
+                if (value - other_value).abs() > max_change_kg {
+                    errors.push(ValidationError::ChangeTooLarge {
+                        value,
+                        date,
+                        other_value,
+                        other_date,
+                        max_change_kg,
+                    });
+                }
+            }
+        }
+        Ok(errors)
+    }
+}
+
+/// A rule broken by a weight.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// The weight is NaN or infinite.
+    NotFinite,
+    TooLow {
+        value: f64,
+        min_kg: f64,
+    },
+    TooHigh {
+        value: f64,
+        max_kg: f64,
+    },
+    /// The date is after the last day accepted.
+    InFuture {
+        date: NaiveDate,
+        latest: NaiveDate,
+    },
+    /// The weight differs too much from the closest weight stored before or after it.
+    ChangeTooLarge {
+        value: f64,
+        date: NaiveDate,
+        other_value: f64,
+        other_date: NaiveDate,
+        max_change_kg: f64,
+    },
+}
+
+impl ValidationError {
+    /// A stable, machine readable code for the broken rule.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::NotFinite => "weight_not_finite",
+            ValidationError::TooLow { .. } => "weight_too_low",
+            ValidationError::TooHigh { .. } => "weight_too_high",
+            ValidationError::InFuture { .. } => "date_in_future",
+            ValidationError::ChangeTooLarge { .. } => "change_too_large",
+        }
+    }
+
+    /// The input the rule is about: `weight` or `date`.
+    pub fn field(&self) -> &'static str {
+        match self {
+            ValidationError::InFuture { .. } => "date",
+            _ => "weight",
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::NotFinite => write!(f, "the weight must be a number"),
+            ValidationError::TooLow { value, min_kg } => {
+                write!(f, "{} kg is below the minimum of {} kg", value, min_kg)
+            }
+            ValidationError::TooHigh { value, max_kg } => {
+                write!(f, "{} kg is above the maximum of {} kg", value, max_kg)
+            }
+            ValidationError::InFuture { date, latest } => write!(
+                f,
+                "{} is in the future (the latest date accepted is {})",
+                date.format("%Y-%m-%d"),
+                latest.format("%Y-%m-%d")
+            ),
+            ValidationError::ChangeTooLarge {
+                value,
+                date,
+                other_value,
+                other_date,
+                max_change_kg,
+            } => write!(
+                f,
+                "{} kg on {} differs by {:.1} kg from {} kg on {} (at most {:.1} kg)",
+                value,
+                date.format("%Y-%m-%d"),
+                (value - other_value).abs(),
+                other_value,
+                other_date.format("%Y-%m-%d"),
+                max_change_kg
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// The messages of the broken rules, separated by semicolons.
+pub fn describe(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(ValidationError::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
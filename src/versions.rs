@@ -0,0 +1,198 @@
+//! Versions of the weights, for optimistic concurrency: a client sends back the version of a
+//! weight it has seen, and its change is refused when someone else changed the weight since.
+//!
+//! The version of a day is incremented by every change of its weight, note or tags, and when it is
+//! moved to the trash or restored. It is paired with the id of the row, so that a weight purged
+//! from the trash and entered again does not reuse an old version.
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use std::fmt;
+
+use crate::annotations::AnnotationError;
+use crate::db::{write_transaction, DbConnection};
+use crate::validation::{self, ValidationError, ValidationRules};
+use crate::{NewMeasurement, Origin, UpsertOutcome};
+
+/// The version of the weight of a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeightVersion {
+    pub id: i32,
+    pub version: i32,
+}
+
+impl WeightVersion {
+    pub fn new(id: i32, version: i32) -> Self {
+        WeightVersion { id, version }
+    }
+
+    /// The version as the value of an `ETag` header, e.g. `"12-3"`.
+    pub fn etag(&self) -> String {
+        format!("\"{}-{}\"", self.id, self.version)
+    }
+
+    /// Reads a version from an entity tag, as sent in `If-Match` or `If-None-Match`. Weak tags
+    /// are accepted; tags that are not versions of a weight are not.
+    pub fn from_etag(tag: &str) -> Option<Self> {
+        let tag = tag.trim();
+        let tag = tag.strip_prefix("W/").unwrap_or(tag);
+        let (id, version) = tag.strip_prefix('"')?.strip_suffix('"')?.split_once('-')?;
+        Some(WeightVersion {
+            id: id.parse().ok()?,
+            version: version.parse().ok()?,
+        })
+    }
+}
+
+/// The version of the weight of a day, unless there is none or it is in the trash.
+pub fn current_version(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+) -> QueryResult<Option<WeightVersion>> {
+    use crate::schema::weights::dsl::*;
+
+    weights
+        .filter(measurement_date.eq(in_measurement_date))
+        .filter(deleted_at.is_null())
+        .select((id, version))
+        .first::<(i32, i32)>(conn)
+        .optional()
+        .map(|found| found.map(|(row_id, row_version)| WeightVersion::new(row_id, row_version)))
+}
+
+/// What a client expects of the weight of a day before changing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Precondition {
+    /// There is a weight, whatever its version (`If-Match: *`).
+    Exists,
+    /// There is no weight yet (`If-None-Match: *`).
+    Absent,
+    /// The weight is at one of these versions (`If-Match` with entity tags).
+    OneOf(Vec<WeightVersion>),
+    /// The weight is at none of these versions, or there is none (`If-None-Match` with entity
+    /// tags).
+    NoneOf(Vec<WeightVersion>),
+}
+
+impl Precondition {
+    /// Whether the precondition holds for the current version of a weight.
+    pub fn holds(&self, current: Option<WeightVersion>) -> bool {
+        match (self, current) {
+            (Precondition::Exists, current) => current.is_some(),
+            (Precondition::Absent, current) => current.is_none(),
+            (Precondition::OneOf(versions), Some(current)) => versions.contains(&current),
+            (Precondition::OneOf(_), None) => false,
+            (Precondition::NoneOf(versions), current) => {
+                current.is_none_or(|current| !versions.contains(&current))
+            }
+        }
+    }
+}
+
+/// Why a conditional change was not made.
+#[derive(Debug)]
+pub enum ConditionalError {
+    /// The weight is not as expected; holds its current version.
+    PreconditionFailed(Option<WeightVersion>),
+    /// The weight breaks the plausibility rules.
+    Implausible(Vec<ValidationError>),
+    /// A tag is empty or too long, see [`AnnotationError::InvalidTag`].
+    InvalidTag {
+        tag: String,
+        message: String,
+    },
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for ConditionalError {
+    fn from(error: diesel::result::Error) -> Self {
+        ConditionalError::Database(error)
+    }
+}
+
+impl From<AnnotationError> for ConditionalError {
+    fn from(error: AnnotationError) -> Self {
+        match error {
+            AnnotationError::InvalidTag { tag, message } => {
+                ConditionalError::InvalidTag { tag, message }
+            }
+            AnnotationError::Database(error) => ConditionalError::Database(error),
+        }
+    }
+}
+
+impl fmt::Display for ConditionalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConditionalError::PreconditionFailed(_) => {
+                write!(f, "the weight was changed in the meantime")
+            }
+            ConditionalError::Implausible(errors) => f.write_str(&validation::describe(errors)),
+            ConditionalError::InvalidTag { tag, message } => {
+                write!(f, "tag {:?}: {}", tag, message)
+            }
+            ConditionalError::Database(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ConditionalError {}
+
+fn check(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+    condition: Option<&Precondition>,
+) -> Result<(), ConditionalError> {
+    let Some(condition) = condition else {
+        return Ok(());
+    };
+    let current = current_version(conn, in_measurement_date)?;
+    match condition.holds(current) {
+        true => Ok(()),
+        false => Err(ConditionalError::PreconditionFailed(current)),
+    }
+}
+
+/// Like [`crate::upsert_measurement`], when the precondition holds and, given rules, the weight is
+/// plausible. Both are checked in the same transaction as the change, so that no other writer can
+/// change the weight or its neighbours in between. Returns the version of the weight once stored.
+pub fn upsert_measurement_if(
+    conn: &mut DbConnection,
+    measurement: &NewMeasurement,
+    origin: &Origin,
+    condition: Option<&Precondition>,
+    rules: Option<&ValidationRules>,
+) -> Result<(UpsertOutcome, WeightVersion), ConditionalError> {
+    write_transaction(conn, |conn| {
+        check(conn, measurement.measurement_date, condition)?;
+        if let Some(rules) = rules {
+            let errors =
+                rules.check(conn, measurement.weight_value, measurement.measurement_date)?;
+            if !errors.is_empty() {
+                return Err(ConditionalError::Implausible(errors));
+            }
+        }
+        let outcome = crate::store_in_transaction(conn, measurement, origin)?;
+        let stored = current_version(conn, measurement.measurement_date)?
+            .ok_or(diesel::result::Error::NotFound)?;
+        Ok((outcome, stored))
+    })
+}
+
+/// Like [`crate::delete_weight`], when the precondition holds. The precondition is checked in the
+/// same transaction as the change.
+pub fn delete_weight_if(
+    conn: &mut DbConnection,
+    in_measurement_date: NaiveDate,
+    origin: &Origin,
+    condition: Option<&Precondition>,
+) -> Result<usize, ConditionalError> {
+    write_transaction(conn, |conn| {
+        check(conn, in_measurement_date, condition)?;
+        Ok(crate::delete_in_transaction(
+            conn,
+            in_measurement_date,
+            origin,
+        )?)
+    })
+}
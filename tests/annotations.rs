@@ -0,0 +1,164 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::TempDatabase;
+use rs_weight_tracker::annotations::{
+    annotations_between_dates, normalize_tag, Annotation, AnnotationError,
+};
+use rs_weight_tracker::trash::purge_deleted;
+use rs_weight_tracker::{NewMeasurement, Origin, UpsertOutcome};
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn origin() -> Origin {
+    Origin::new("test", "tester")
+}
+
+fn annotated(day: u32, note: Option<&str>, tags: Option<&[&str]>) -> NewMeasurement {
+    NewMeasurement {
+        note: note.map(String::from),
+        tags: tags.map(|tags| tags.iter().map(|tag| tag.to_string()).collect()),
+        ..NewMeasurement::new(80.0, date(day))
+    }
+}
+
+#[test]
+fn stores_notes_and_tags_with_the_weight() {
+    let database = TempDatabase::new("annotations");
+    let mut conn = database.connect();
+
+    rs_weight_tracker::upsert_weights(
+        &mut conn,
+        &[
+            annotated(1, Some("after holiday"), Some(&["Holiday", "travel "])),
+            annotated(2, None, Some(&["holiday"])),
+            annotated(3, None, None),
+        ],
+        &origin(),
+    )
+    .unwrap();
+
+    let annotations = annotations_between_dates(&mut conn, date(1), date(3)).unwrap();
+    assert_eq!(annotations.len(), 2);
+    assert_eq!(
+        annotations[&date(1)],
+        Annotation {
+            note: Some(String::from("after holiday")),
+            tags: vec![String::from("holiday"), String::from("travel")],
+        }
+    );
+
+    let tagged =
+        rs_weight_tracker::weights_between_dates_with_tag(&mut conn, date(1), date(3), "HOLIDAY")
+            .unwrap();
+    let tagged_dates: Vec<NaiveDate> = tagged
+        .iter()
+        .map(|weight| weight.measurement_date)
+        .collect();
+    assert_eq!(tagged_dates, vec![date(1), date(2)]);
+}
+
+#[test]
+fn annotations_are_replaced_only_when_given() {
+    let database = TempDatabase::new("annotation-updates");
+    let mut conn = database.connect();
+
+    let store = |conn: &mut _, measurement: NewMeasurement| {
+        rs_weight_tracker::upsert_measurement(conn, &measurement, &origin()).unwrap()
+    };
+    store(&mut conn, annotated(1, Some("sick"), Some(&["sick"])));
+    // Same weight, no annotation: nothing changes.
+    assert_eq!(
+        store(&mut conn, annotated(1, None, None)),
+        UpsertOutcome::Unchanged
+    );
+    assert_eq!(
+        annotations_between_dates(&mut conn, date(1), date(1)).unwrap()[&date(1)].tags,
+        vec![String::from("sick")]
+    );
+
+    store(&mut conn, annotated(1, Some(""), Some(&[])));
+    assert!(annotations_between_dates(&mut conn, date(1), date(1))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn deleted_weights_lose_their_annotations_when_stored_again_or_purged() {
+    let database = TempDatabase::new("annotation-trash");
+    let mut conn = database.connect();
+
+    rs_weight_tracker::upsert_measurement(
+        &mut conn,
+        &annotated(1, Some("new scale"), Some(&["scale"])),
+        &origin(),
+    )
+    .unwrap();
+    rs_weight_tracker::delete_weight(&mut conn, date(1), &origin()).unwrap();
+    assert!(annotations_between_dates(&mut conn, date(1), date(1))
+        .unwrap()
+        .is_empty());
+
+    rs_weight_tracker::upsert_weight(&mut conn, 81.0, date(1), &origin()).unwrap();
+    assert!(annotations_between_dates(&mut conn, date(1), date(1))
+        .unwrap()
+        .is_empty());
+
+    rs_weight_tracker::upsert_measurement(
+        &mut conn,
+        &annotated(2, None, Some(&["scale"])),
+        &origin(),
+    )
+    .unwrap();
+    rs_weight_tracker::delete_weight(&mut conn, date(2), &origin()).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert_eq!(
+        purge_deleted(&mut conn, chrono::Duration::zero(), &origin()).unwrap(),
+        1
+    );
+    assert!(rs_weight_tracker::weights_between_dates_with_tag(
+        &mut conn,
+        date(1),
+        date(2),
+        "scale"
+    )
+    .unwrap()
+    .is_empty());
+}
+
+#[test]
+fn rejects_empty_and_long_tags() {
+    assert_eq!(normalize_tag(" Creatine "), Ok(String::from("creatine")));
+    assert!(normalize_tag("  ").is_err());
+    assert!(normalize_tag(&"x".repeat(33)).is_err());
+}
+
+#[test]
+fn an_invalid_tag_is_reported_and_nothing_is_stored() {
+    let database = TempDatabase::new("annotation-invalid-tag");
+    let mut conn = database.connect();
+    let long_tag = "t".repeat(33);
+
+    let result = rs_weight_tracker::upsert_weights(
+        &mut conn,
+        &[
+            annotated(1, None, Some(&["scale"])),
+            annotated(2, Some("after holiday"), Some(&["holiday", &long_tag])),
+        ],
+        &origin(),
+    );
+    match result {
+        Err(AnnotationError::InvalidTag { tag, .. }) => assert_eq!(tag, long_tag),
+        other => panic!("expected an invalid tag, got {:?}", other),
+    }
+    assert!(
+        rs_weight_tracker::weights_between_dates(&mut conn, date(1), date(2))
+            .unwrap()
+            .is_empty()
+    );
+    assert!(annotations_between_dates(&mut conn, date(1), date(2))
+        .unwrap()
+        .is_empty());
+}
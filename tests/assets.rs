@@ -0,0 +1,79 @@
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use rs_weight_tracker::assets::Assets;
+
+fn get(
+    assets: &Assets,
+    path: &str,
+    headers: &[(header::HeaderName, &str)],
+) -> axum::response::Response {
+    let mut request_headers = HeaderMap::new();
+    for (name, value) in headers {
+        request_headers.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+    }
+    assets.respond(
+        &Method::GET,
+        &path.parse::<Uri>().unwrap(),
+        &request_headers,
+    )
+}
+
+#[test]
+fn pages_link_to_the_hashed_names_of_the_files() {
+    let assets = Assets::embedded();
+    let (index, cache_control) = assets.get("/").unwrap();
+    assert_eq!(cache_control, "no-cache");
+
+    let page = String::from_utf8(index.content.to_vec()).unwrap();
+    let style = assets
+        .paths()
+        .find(|path| path.starts_with("style.") && path.len() == "style.01234567.css".len())
+        .unwrap();
+    assert!(page.contains(&format!("href=\"./{}\"", style)));
+
+    let (hashed, cache_control) = assets.get(style).unwrap();
+    assert!(cache_control.contains("immutable"));
+    assert_eq!(hashed.content, assets.get("style.css").unwrap().0.content);
+    assert_eq!(hashed.content_type, "text/css");
+}
+
+#[test]
+fn files_are_sent_compressed_and_revalidated_with_their_etag() {
+    let assets = Assets::embedded();
+
+    let response = get(
+        &assets,
+        "/index.html",
+        &[(header::ACCEPT_ENCODING, "gzip, br")],
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_owned();
+
+    let response = get(
+        &assets,
+        "/index.html",
+        &[(header::ACCEPT_ENCODING, "gzip, br;q=0")],
+    );
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+    let response = get(&assets, "/index.html", &[]);
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+
+    let response = get(
+        &assets,
+        "/index.html",
+        &[
+            (header::ACCEPT_ENCODING, "br"),
+            (header::IF_NONE_MATCH, &etag),
+        ],
+    );
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    assert_eq!(
+        get(&assets, "/missing.js", &[]).status(),
+        StatusCode::NOT_FOUND
+    );
+}
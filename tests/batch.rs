@@ -0,0 +1,133 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::{Extension, Router};
+use chrono::NaiveDate;
+use common::TempDatabase;
+use rs_weight_tracker::api::idempotency::{KeyRetention, PendingTimeout};
+use rs_weight_tracker::api::v1::{self, MAX_BATCH_SIZE};
+use rs_weight_tracker::validation::ValidationRules;
+use rs_weight_tracker::Origin;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+async fn post_batch(app: &Router, batch: Value) -> (StatusCode, Value) {
+    let request = Request::post("/api/v1/weights/batch")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(batch.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn stores_the_valid_entries_and_reports_the_invalid_ones() {
+    let database = TempDatabase::new("batch");
+    rs_weight_tracker::config::init(&mut vec![
+        String::from("test"),
+        String::from("--database-url"),
+        database.url(),
+    ])
+    .unwrap();
+    let app = Router::new()
+        .nest("/api/v1", v1::router())
+        .layer(Extension(ValidationRules::default()))
+        .layer(Extension(KeyRetention(chrono::Duration::hours(1))))
+        .layer(Extension(PendingTimeout(chrono::Duration::minutes(1))));
+
+    let origin = Origin::new("test", "tester");
+    let mut conn = database.connect();
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 80.5, date(2), &origin).unwrap();
+
+    let (status, body) = post_batch(
+        &app,
+        json!([
+            {"date": "2023-01-01", "weight_kg": 80.0},
+            {"date": "2023-01-02", "weight_kg": 80.2},
+            {"date": "2023-01-03", "weight_kg": 80.1},
+            {"date": "2023-13-04", "weight_kg": 80.0},
+            {"date": "2023-01-05", "weight_kg": 8.0},
+            {"date": "2023-01-03", "weight_kg": 79.9}
+        ]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let data = &body["data"];
+    assert_eq!(
+        (
+            &data["inserted"],
+            &data["updated"],
+            &data["unchanged"],
+            &data["rejected"]
+        ),
+        (&json!(1), &json!(1), &json!(1), &json!(3))
+    );
+    let statuses: Vec<(u64, &str)> = data["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            (
+                item["index"].as_u64().unwrap(),
+                item["status"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (0, "unchanged"),
+            (1, "updated"),
+            (2, "inserted"),
+            (3, "rejected"),
+            (4, "rejected"),
+            (5, "rejected")
+        ]
+    );
+    let results = &data["results"];
+    assert!(results[3]["error"].is_string());
+    assert!(!results[4]["details"].as_array().unwrap().is_empty());
+    assert_eq!(results[5]["error"], json!("duplicate date in the batch"));
+    assert!(results[2].get("error").is_none());
+
+    let stored: Vec<(NaiveDate, f64)> =
+        rs_weight_tracker::weights_between_dates(&mut conn, date(1), date(31))
+            .unwrap()
+            .into_iter()
+            .map(|weight| (weight.measurement_date, weight.weight_value))
+            .collect();
+    assert_eq!(
+        stored,
+        vec![(date(1), 80.0), (date(2), 80.2), (date(3), 80.1)]
+    );
+
+    // The entries are checked against the ones of the batch accepted before them, not only
+    // against the stored weights.
+    let (status, body) = post_batch(
+        &app,
+        json!([
+            {"date": "2023-01-20", "weight_kg": 80.0},
+            {"date": "2023-01-21", "weight_kg": 120.0}
+        ]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let results = &body["data"]["results"];
+    assert_eq!(results[0]["status"], json!("inserted"));
+    assert_eq!(results[1]["status"], json!("rejected"));
+    assert_eq!(results[1]["details"][0]["code"], json!("change_too_large"));
+
+    let too_large = vec![json!({"date": "2023-01-06", "weight_kg": 80.0}); MAX_BATCH_SIZE + 1];
+    let (status, body) = post_batch(&app, Value::Array(too_large)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], json!("batch_too_large"));
+}
@@ -0,0 +1,93 @@
+//! Helpers shared by the integration tests.
+//!
+//! The tests run on SQLite databases in the temporary directory. With `TEST_DATABASE_URL` set to
+//! the URL of a PostgreSQL server, e.g. `postgres://localhost/postgres`, and the `postgres`
+//! feature, they run on PostgreSQL databases created on that server instead.
+
+use diesel::connection::SimpleConnection;
+use diesel_migrations::MigrationHarness;
+use rs_weight_tracker::db::{is_postgres_url, DbConnection};
+use rs_weight_tracker::migrations::migrations_for;
+use std::fs;
+use std::path::PathBuf;
+
+/// A database with the schema applied, deleted when dropped.
+pub enum TempDatabase {
+    Sqlite(PathBuf),
+    /// The URL of the server, to create and drop the database, and the name of the database.
+    Postgres(String, String),
+}
+
+impl TempDatabase {
+    pub fn new(name: &str) -> Self {
+        let database = match std::env::var("TEST_DATABASE_URL") {
+            Ok(server_url) if is_postgres_url(&server_url) => {
+                let database_name = format!(
+                    "rs_weight_tracker_{}_{}",
+                    name.replace('-', "_"),
+                    std::process::id()
+                );
+                let mut conn = rs_weight_tracker::connect(&server_url).unwrap();
+                for statement in ["DROP DATABASE IF EXISTS", "CREATE DATABASE"] {
+                    conn.batch_execute(&format!("{} {}", statement, database_name))
+                        .unwrap();
+                }
+                TempDatabase::Postgres(server_url, database_name)
+            }
+            _ => {
+                let path = std::env::temp_dir().join(format!(
+                    "rs-weight-tracker-{}-{}.sqlite",
+                    name,
+                    std::process::id()
+                ));
+                let _ = fs::remove_file(&path);
+                TempDatabase::Sqlite(path)
+            }
+        };
+
+        let mut conn = database.connect();
+        let migrations = migrations_for(&conn);
+        conn.run_pending_migrations(migrations).unwrap();
+        database
+    }
+
+    pub fn url(&self) -> String {
+        match self {
+            TempDatabase::Sqlite(path) => path.to_str().unwrap().to_owned(),
+            TempDatabase::Postgres(server_url, database_name) => {
+                let (server, query) = server_url
+                    .split_once('?')
+                    .map_or((server_url.as_str(), None), |(server, query)| {
+                        (server, Some(query))
+                    });
+                let server = server.rsplit_once('/').map_or(server, |(server, _)| server);
+                match query {
+                    Some(query) => format!("{}/{}?{}", server, database_name, query),
+                    None => format!("{}/{}", server, database_name),
+                }
+            }
+        }
+    }
+
+    pub fn connect(&self) -> DbConnection {
+        rs_weight_tracker::connect(&self.url()).unwrap()
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        match self {
+            TempDatabase::Sqlite(path) => {
+                let _ = fs::remove_file(path);
+            }
+            TempDatabase::Postgres(server_url, database_name) => {
+                if let Ok(mut conn) = rs_weight_tracker::connect(server_url) {
+                    let _ = conn.batch_execute(&format!(
+                        "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+                        database_name
+                    ));
+                }
+            }
+        }
+    }
+}
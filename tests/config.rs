@@ -0,0 +1,168 @@
+use rs_weight_tracker::config::{Config, ConfigError};
+use std::collections::HashMap;
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn environment_overrides_the_file_and_flags_override_both() {
+    let mut config = Config::from_toml(
+        r#"
+        database_url = "file.sqlite"
+        goal_kg = 75.0
+
+        [server]
+        backend_port = 8080
+        cors_allowed_origins = ["https://weight.example"]
+
+        [validation]
+        max_kg = 200.0
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.server.frontend_port, 14280);
+    assert_eq!(config.validation.min_kg, 30.0);
+
+    let env = HashMap::from([
+        ("DATABASE_URL", "env.sqlite"),
+        ("SERVER_BACKEND_PORT_NUM", "9090"),
+        ("CORS_ALLOWED_ORIGINS", "http://a.example, http://b.example"),
+    ]);
+    config
+        .apply_env(|name| env.get(name).map(|value| value.to_string()))
+        .unwrap();
+
+    let mut arguments = args(&[
+        "wt",
+        "chart",
+        "--database-url",
+        "flag.sqlite",
+        "--days=7",
+        "--max-kg=150",
+    ]);
+    config.apply_flags(&mut arguments).unwrap();
+    assert_eq!(arguments, args(&["wt", "chart", "--days=7"]));
+
+    assert_eq!(config.database_url, "flag.sqlite");
+    assert_eq!(config.goal_kg, Some(75.0));
+    assert_eq!(config.server.backend_port, 9090);
+    assert_eq!(
+        config.server.cors_allowed_origins,
+        vec!["http://a.example", "http://b.example"]
+    );
+    assert_eq!(config.validation.max_kg, 150.0);
+    assert_eq!(config.validate(), Ok(()));
+}
+
+#[test]
+fn invalid_settings_are_reported_with_their_source() {
+    assert!(Config::from_toml("[server]\nbackend_prot = 1")
+        .unwrap_err()
+        .contains("unknown field `backend_prot`"));
+    assert!(Config::from_toml("trash_retention_days = \"a month\"").is_err());
+
+    let mut config = Config::default();
+    let error = config
+        .apply_env(|name| (name == "SERVER_FRONTEND_PORT_NUM").then(|| String::from("80800")))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid SERVER_FRONTEND_PORT_NUM (server.frontend_port): \"80800\" is not a port number"
+    );
+    assert!(matches!(
+        config.apply_flags(&mut args(&["wt", "--goal-kg"])),
+        Err(ConfigError::Invalid { .. })
+    ));
+
+    let mut config = Config::default();
+    config.server.frontend_port = config.server.backend_port;
+    assert!(config
+        .validate()
+        .unwrap_err()
+        .to_string()
+        .contains("both use port"));
+
+    let mut config = Config::default();
+    config.server.cors_allowed_origins = vec![String::from("localhost:14280")];
+    assert!(config
+        .validate()
+        .unwrap_err()
+        .to_string()
+        .starts_with("Invalid server.cors_allowed_origins"));
+
+    let mut config = Config::default();
+    config.validation.min_kg = 400.0;
+    assert!(config.validate().is_err());
+
+    for days in [-1, 36_501, i64::MAX] {
+        let config = Config {
+            trash_retention_days: days,
+            ..Config::default()
+        };
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid trash_retention_days"));
+    }
+}
+
+#[test]
+fn server_settings_accept_ipv6_a_switch_and_a_base_path() {
+    let mut config =
+        Config::from_toml("[server]\nbind_address = \"::\"\nbase_path = \"/wt/\"").unwrap();
+    assert!(config.server.bind_address.is_ipv6());
+    assert_eq!(config.server.base_path(), "/wt");
+
+    let mut arguments = args(&[
+        "rs-weight-tracker",
+        "--single-port",
+        "--frontend-port=15280",
+    ]);
+    config.apply_flags(&mut arguments).unwrap();
+    assert_eq!(arguments, args(&["rs-weight-tracker"]));
+    assert!(config.server.single_port);
+    // A single port leaves the frontend port unused, so it may equal the backend one.
+    assert_eq!(config.validate(), Ok(()));
+
+    for base_path in ["weight-tracker", "/a//b", "/a b", "/a?b"] {
+        config.server.base_path = String::from(base_path);
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid server.base_path"));
+    }
+}
+
+#[test]
+fn tls_requires_a_certificate_and_a_key() {
+    let mut config = Config::default();
+    config
+        .apply_flags(&mut args(&["rs-weight-tracker", "--tls-cert", "cert.pem"]))
+        .unwrap();
+    assert!(config
+        .validate()
+        .unwrap_err()
+        .to_string()
+        .contains("must be set together"));
+
+    config.server.https_redirect_port = Some(8080);
+    config.server.tls_cert_path = None;
+    assert!(config
+        .validate()
+        .unwrap_err()
+        .to_string()
+        .starts_with("Invalid server.https_redirect_port"));
+
+    config
+        .apply_env(|name| match name {
+            "SERVER_TLS_CERT_PATH" => Some(String::from("cert.pem")),
+            "SERVER_TLS_KEY_PATH" => Some(String::from("key.pem")),
+            _ => None,
+        })
+        .unwrap();
+    assert!(config.server.tls().is_some());
+    assert_eq!(config.validate(), Ok(()));
+}
@@ -0,0 +1,41 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::{Extension, Router};
+use rs_weight_tracker::api::idempotency::{KeyRetention, PendingTimeout, IDEMPOTENCY_KEY_HEADER};
+use rs_weight_tracker::api::v1;
+use rs_weight_tracker::validation::ValidationRules;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn an_unreachable_database_is_answered_with_503() {
+    let missing = std::env::temp_dir()
+        .join(format!("rs-weight-tracker-missing-{}", std::process::id()))
+        .join("weight.sqlite");
+    rs_weight_tracker::config::init(&mut vec![
+        String::from("test"),
+        String::from("--database-url"),
+        missing.to_string_lossy().into_owned(),
+    ])
+    .unwrap();
+    let app = Router::new()
+        .nest("/api/v1", v1::router())
+        .layer(Extension(ValidationRules::default()))
+        .layer(Extension(KeyRetention(chrono::Duration::hours(1))))
+        .layer(Extension(PendingTimeout(chrono::Duration::minutes(1))));
+
+    let requests = [
+        Request::get("/api/v1/weights/2023-01-01").body(Body::empty()),
+        Request::post("/api/v1/weights")
+            .header("content-type", "application/json")
+            .header(IDEMPOTENCY_KEY_HEADER, "key")
+            .body(Body::from(r#"{"date": "2023-01-01", "weight_kg": 80.0}"#)),
+    ];
+    for request in requests {
+        let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], json!("database_unavailable"));
+    }
+}
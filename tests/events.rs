@@ -0,0 +1,76 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::TempDatabase;
+use rs_weight_tracker::events::{
+    add_event, delete_event, events_between_dates, phase_summaries, EventKind,
+};
+use rs_weight_tracker::{NewEvent, Origin};
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+#[test]
+fn summarizes_the_weights_of_each_phase() {
+    let database = TempDatabase::new("events");
+    let mut conn = database.connect();
+
+    let origin = Origin::new("test", "tester");
+    for day in 1..=28 {
+        let value = 90.0 - 0.1 * day as f64;
+        rs_weight_tracker::upsert_weight(&mut conn, value, date(day), &origin).unwrap();
+    }
+
+    let cut = add_event(
+        &mut conn,
+        &NewEvent::new(EventKind::Cut, "Cut", date(8), Some(date(21))),
+    )
+    .unwrap();
+    let marker = add_event(
+        &mut conn,
+        &NewEvent::new(EventKind::Medication, "New pills", date(15), Some(date(15))),
+    )
+    .unwrap();
+    let ongoing = add_event(
+        &mut conn,
+        &NewEvent::new(EventKind::Diet, "Keto", date(25), None),
+    )
+    .unwrap();
+
+    let events = events_between_dates(&mut conn, date(1), date(10)).unwrap();
+    assert_eq!(events, vec![cut.clone()]);
+    let events = events_between_dates(&mut conn, date(15), date(31)).unwrap();
+    assert_eq!(events, vec![cut.clone(), marker.clone(), ongoing.clone()]);
+
+    let summaries = phase_summaries(&mut conn, &events, date(31)).unwrap();
+    let cut_summary = summaries[0].as_ref().unwrap();
+    assert_eq!(cut_summary.duration_days, 14);
+    assert_eq!(cut_summary.count, 14);
+    assert!((cut_summary.start_weight - 89.2).abs() < 1e-9);
+    assert!((cut_summary.end_weight - 87.9).abs() < 1e-9);
+    assert!((cut_summary.rate_per_week.unwrap() + 0.7).abs() < 1e-9);
+
+    let marker_summary = summaries[1].as_ref().unwrap();
+    assert_eq!((marker_summary.duration_days, marker_summary.count), (1, 1));
+    assert_eq!(marker_summary.rate_per_week, None);
+
+    // The ongoing diet runs until "today", without weights after the 28th.
+    let diet_summary = summaries[2].as_ref().unwrap();
+    assert_eq!((diet_summary.duration_days, diet_summary.count), (7, 4));
+
+    assert_eq!(delete_event(&mut conn, marker.id).unwrap(), 1);
+    assert_eq!(delete_event(&mut conn, marker.id).unwrap(), 0);
+}
+
+#[test]
+fn rejects_invalid_events() {
+    assert!("Bulk".parse::<EventKind>().is_ok());
+    assert!("holiday".parse::<EventKind>().is_err());
+    assert!(NewEvent::new(EventKind::Cut, " ", date(1), None)
+        .validate()
+        .is_err());
+    assert!(NewEvent::new(EventKind::Cut, "Cut", date(2), Some(date(1)))
+        .validate()
+        .is_err());
+}
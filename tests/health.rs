@@ -0,0 +1,52 @@
+use axum::http::StatusCode;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel_migrations::MigrationHarness;
+use rs_weight_tracker::db::DbConnection;
+use rs_weight_tracker::health::readyz;
+use rs_weight_tracker::migrations::MIGRATIONS;
+use std::fs;
+
+#[tokio::test]
+async fn not_ready_until_every_migration_is_applied() {
+    let path = std::env::temp_dir().join(format!(
+        "rs-weight-tracker-health-{}.sqlite",
+        std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+    let url = path.to_str().unwrap().to_owned();
+    rs_weight_tracker::config::init(&mut vec![
+        String::from("test"),
+        String::from("--database-url"),
+        url.clone(),
+    ])
+    .unwrap();
+    let mut conn: DbConnection = rs_weight_tracker::connect(&url).unwrap();
+
+    let (status, body) = readyz().await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body
+        .reason
+        .as_ref()
+        .unwrap()
+        .starts_with("Pending migrations"));
+    // The probe does not create the table of the applied migrations.
+    let has_migrations_table = diesel::select(sql::<Bool>(
+        "EXISTS (SELECT 1 FROM sqlite_master WHERE name = '__diesel_schema_migrations')",
+    ))
+    .get_result::<bool>(&mut conn)
+    .unwrap();
+    assert!(!has_migrations_table);
+
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+    assert_eq!(readyz().await.0, StatusCode::OK);
+
+    conn.revert_last_migration(MIGRATIONS).unwrap();
+    let (status, body) = readyz().await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body.reason.as_ref().unwrap().contains("20261019150000"));
+
+    drop(conn);
+    let _ = fs::remove_file(&path);
+}
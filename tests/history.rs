@@ -0,0 +1,97 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::TempDatabase;
+use rs_weight_tracker::history::{history_for_date, revert_to_change};
+use rs_weight_tracker::Origin;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+#[test]
+fn records_every_change_with_its_origin() {
+    let database = TempDatabase::new("history");
+    let mut conn = database.connect();
+    let api = Origin::api("127.0.0.1");
+    let cli = Origin::new("cli:add_weight", "tester");
+
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &api).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &api).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 8.0, date(1), &cli).unwrap();
+    rs_weight_tracker::delete_weight(&mut conn, date(1), &cli).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 70.0, date(2), &api).unwrap();
+
+    let changes = history_for_date(&mut conn, date(1)).unwrap();
+    let summary: Vec<(&str, Option<f64>, Option<f64>, &str)> = changes
+        .iter()
+        .map(|change| {
+            (
+                change.action.as_str(),
+                change.old_value,
+                change.new_value,
+                change.source.as_str(),
+            )
+        })
+        .collect();
+    // Storing the same weight again is not a change.
+    assert_eq!(
+        summary,
+        vec![
+            ("insert", None, Some(80.0), "api"),
+            ("update", Some(80.0), Some(8.0), "cli:add_weight"),
+            ("delete", Some(8.0), None, "cli:add_weight"),
+        ]
+    );
+    assert_eq!(changes[0].actor, "127.0.0.1");
+}
+
+#[test]
+fn reverts_to_a_previous_version() {
+    let database = TempDatabase::new("revert");
+    let mut conn = database.connect();
+    let origin = Origin::new("test", "tester");
+
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 8.0, date(1), &origin).unwrap();
+    let first = history_for_date(&mut conn, date(1)).unwrap()[0].id;
+
+    let reverted = revert_to_change(&mut conn, date(1), first, &origin).unwrap();
+    assert_eq!(reverted.unwrap().new_value, Some(80.0));
+    let stored = rs_weight_tracker::weights_between_dates(&mut conn, date(1), date(1)).unwrap();
+    assert_eq!(stored[0].weight_value, 80.0);
+
+    let changes = history_for_date(&mut conn, date(1)).unwrap();
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[2].old_value, Some(8.0));
+
+    // A change of another day cannot be used.
+    assert!(revert_to_change(&mut conn, date(2), first, &origin)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn reverting_to_the_current_version_records_nothing() {
+    let database = TempDatabase::new("revert-unchanged");
+    let mut conn = database.connect();
+    let origin = Origin::new("test", "tester");
+
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 81.0, date(1), &origin).unwrap();
+    rs_weight_tracker::delete_weight(&mut conn, date(1), &origin).unwrap();
+    let changes = history_for_date(&mut conn, date(1)).unwrap();
+    let (updated, deleted) = (changes[1].id, changes[2].id);
+
+    // The weight is deleted already.
+    assert!(revert_to_change(&mut conn, date(1), deleted, &origin)
+        .unwrap()
+        .is_some());
+    assert_eq!(history_for_date(&mut conn, date(1)).unwrap().len(), 3);
+
+    revert_to_change(&mut conn, date(1), updated, &origin).unwrap();
+    revert_to_change(&mut conn, date(1), updated, &origin).unwrap();
+    let changes = history_for_date(&mut conn, date(1)).unwrap();
+    assert_eq!(changes.len(), 4);
+    assert_eq!(changes[3].new_value, Some(81.0));
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::routing::post;
+use axum::{middleware, Extension, Router};
+use chrono::Duration;
+use common::TempDatabase;
+use rs_weight_tracker::api::idempotency::{
+    idempotent, KeyRetention, PendingTimeout, IDEMPOTENCY_KEY_HEADER, REPLAYED_HEADER,
+};
+use rs_weight_tracker::idempotency::{
+    complete, is_valid_key, purge_expired, release, request_hash, reserve, Reservation,
+    StoredResponse,
+};
+
+#[test]
+fn replays_the_response_of_a_completed_request() {
+    let database = TempDatabase::new("idempotency");
+    let mut conn = database.connect();
+    let retention = Duration::hours(24);
+    let pending = Duration::minutes(1);
+
+    let hash = request_hash("POST", "/api/weights/batch", br#"[{"date":"2023-01-20"}]"#);
+    let other_hash = request_hash("POST", "/api/weights/batch", br#"[{"date":"2023-01-21"}]"#);
+    assert_ne!(hash, other_hash);
+
+    let mut attempt = |key, hash| reserve(&mut conn, key, hash, retention, pending).unwrap();
+    assert_eq!(attempt("key-1", &hash), Reservation::Reserved);
+    assert_eq!(attempt("key-1", &hash), Reservation::InProgress);
+    assert_eq!(attempt("key-1", &other_hash), Reservation::Mismatch);
+
+    let response = StoredResponse {
+        status_code: 201,
+        body: String::from(r#"{"rows":1}"#),
+        headers: vec![(String::from("etag"), String::from("\"1-1\""))],
+    };
+    complete(&mut conn, "key-1", &response).unwrap();
+    assert_eq!(
+        reserve(&mut conn, "key-1", &hash, retention, pending).unwrap(),
+        Reservation::Completed(response)
+    );
+    assert_eq!(
+        reserve(&mut conn, "key-1", &other_hash, retention, pending).unwrap(),
+        Reservation::Mismatch
+    );
+
+    // A failed request frees its key for the retry.
+    assert_eq!(
+        reserve(&mut conn, "key-2", &hash, retention, pending).unwrap(),
+        Reservation::Reserved
+    );
+    release(&mut conn, "key-2").unwrap();
+    assert_eq!(
+        reserve(&mut conn, "key-2", &other_hash, retention, pending).unwrap(),
+        Reservation::Reserved
+    );
+}
+
+#[test]
+fn expired_keys_can_be_used_again() {
+    let database = TempDatabase::new("idempotency-expiry");
+    let mut conn = database.connect();
+    let pending = Duration::minutes(1);
+
+    let hash = request_hash("POST", "/api/add_weight", b"{}");
+    let response = StoredResponse {
+        status_code: 201,
+        body: String::from("{}"),
+        headers: Vec::new(),
+    };
+    reserve(&mut conn, "key", &hash, Duration::hours(1), pending).unwrap();
+    complete(&mut conn, "key", &response).unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let other_hash = request_hash("POST", "/api/add_weight", b"{ }");
+    assert_eq!(
+        reserve(&mut conn, "key", &other_hash, Duration::zero(), pending).unwrap(),
+        Reservation::Reserved
+    );
+
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert_eq!(purge_expired(&mut conn, Duration::hours(1)).unwrap(), 0);
+    assert_eq!(purge_expired(&mut conn, Duration::zero()).unwrap(), 1);
+}
+
+#[test]
+fn abandoned_reservations_are_taken_over_after_the_pending_timeout() {
+    let database = TempDatabase::new("idempotency-abandoned");
+    let mut conn = database.connect();
+    let retention = Duration::hours(1);
+
+    let hash = request_hash("POST", "/api/add_weight", b"{}");
+    reserve(&mut conn, "key", &hash, retention, Duration::minutes(1)).unwrap();
+    assert_eq!(
+        reserve(&mut conn, "key", &hash, retention, Duration::minutes(1)).unwrap(),
+        Reservation::InProgress
+    );
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert_eq!(
+        reserve(&mut conn, "key", &hash, retention, Duration::zero()).unwrap(),
+        Reservation::Reserved
+    );
+}
+
+#[tokio::test]
+async fn a_key_stays_reserved_while_its_request_runs() {
+    let database = TempDatabase::new("idempotency-in-flight");
+    rs_weight_tracker::config::init(&mut vec![
+        String::from("test"),
+        String::from("--database-url"),
+        database.url(),
+    ])
+    .unwrap();
+    // A slow handler, and a pending timeout that every reservation outlives.
+    let app = Router::new()
+        .route(
+            "/slow",
+            post(|| async {
+                tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+                (
+                    StatusCode::CREATED,
+                    [
+                        (header::CONTENT_TYPE, "text/plain"),
+                        (header::ETAG, "\"1-1\""),
+                    ],
+                    "{}",
+                )
+            }),
+        )
+        .layer(middleware::from_fn(idempotent))
+        .layer(Extension(KeyRetention(Duration::hours(1))))
+        .layer(Extension(PendingTimeout(Duration::zero())));
+    let send = |app: Router| async move {
+        let request = Request::post("/slow")
+            .header(IDEMPOTENCY_KEY_HEADER, "slow-key")
+            .body(Body::empty())
+            .unwrap();
+        tower::ServiceExt::oneshot(app, request).await.unwrap()
+    };
+
+    let first = tokio::spawn(send(app.clone()));
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(send(app.clone()).await.status(), StatusCode::CONFLICT);
+
+    assert_eq!(first.await.unwrap().status(), StatusCode::CREATED);
+    let replayed = send(app).await;
+    assert_eq!(replayed.status(), StatusCode::CREATED);
+    let headers = replayed.headers();
+    assert!(headers.contains_key(REPLAYED_HEADER));
+    assert_eq!(headers[header::CONTENT_TYPE], "text/plain");
+    assert_eq!(headers[header::ETAG], "\"1-1\"");
+}
+
+#[test]
+fn accepts_printable_keys_only() {
+    assert!(is_valid_key("5b4c7a3e-1f0e-4c8e-9a59-3f1f7e0b2d11"));
+    assert!(!is_valid_key(""));
+    assert!(!is_valid_key("new\nline"));
+    assert!(!is_valid_key(&"k".repeat(256)));
+}
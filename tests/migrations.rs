@@ -0,0 +1,26 @@
+mod common;
+
+use common::TempDatabase;
+use rs_weight_tracker::migrations;
+
+#[test]
+fn every_migration_reverts_and_applies_again() {
+    let database = TempDatabase::new("migrations");
+    let mut conn = database.connect();
+    let names: Vec<String> = migrations::status(&mut conn)
+        .unwrap()
+        .into_iter()
+        .map(|migration| migration.name)
+        .collect();
+
+    for name in names.iter().rev() {
+        assert_eq!(&migrations::rollback(&mut conn).unwrap(), name);
+    }
+    assert!(migrations::status(&mut conn)
+        .unwrap()
+        .iter()
+        .all(|migration| !migration.applied));
+
+    assert_eq!(migrations::migrate(&mut conn).unwrap(), names);
+    assert!(migrations::pending(&mut conn).unwrap().is_empty());
+}
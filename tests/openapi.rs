@@ -0,0 +1,174 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// The server binary, listening on a free port with a database of its own.
+struct Server {
+    child: Child,
+    port: u16,
+    database: std::path::PathBuf,
+}
+
+impl Server {
+    fn start() -> Self {
+        let free_port = || {
+            TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port()
+        };
+        let port = free_port();
+        let database = std::env::temp_dir().join(format!(
+            "rs-weight-tracker-openapi-{}-{}.sqlite",
+            std::process::id(),
+            port
+        ));
+        let child = Command::new(env!("CARGO_BIN_EXE_rs-weight-tracker"))
+            .args(["--single-port", "--database-url"])
+            .arg(&database)
+            .args(["--backend-port", &port.to_string()])
+            .args(["--frontend-port", &free_port().to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let server = Server {
+            child,
+            port,
+            database,
+        };
+        for _ in 0..100 {
+            if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return server;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        panic!("The server did not start");
+    }
+
+    /// The status line, headers and body of a `GET` request.
+    fn get(&self, path: &str) -> (String, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.to_owned(), body.to_owned())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_file(&self.database);
+    }
+}
+
+#[test]
+fn the_openapi_document_lists_the_endpoints() {
+    let server = Server::start();
+    let (head, body) = server.get("/api/openapi.json");
+    assert!(head.starts_with("HTTP/1.1 200"));
+
+    let doc: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    let paths = doc["paths"].as_object().unwrap();
+    for path in [
+        "/api/v1/weights",
+        "/api/v1/weights/batch",
+        "/api/v1/weights/{date}",
+        "/api/chart.svg",
+        "/api/chart.png",
+        "/api/report",
+    ] {
+        assert!(paths.contains_key(path), "{} is not documented", path);
+    }
+    assert!(doc["components"]["schemas"]
+        .as_object()
+        .unwrap()
+        .contains_key("ErrorBody"));
+}
+
+#[test]
+fn swagger_ui_is_served_from_the_binary() {
+    let server = Server::start();
+
+    let (head, _) = server.get("/api/docs");
+    assert!(head.starts_with("HTTP/1.1 308"));
+    assert!(head.to_lowercase().contains("location: docs/"));
+
+    let (head, page) = server.get("/api/docs/");
+    assert!(head.starts_with("HTTP/1.1 200"));
+    assert!(page.contains("swagger-ui"));
+    assert!(!page.contains("https://"));
+
+    let (head, initializer) = server.get("/api/docs/swagger-initializer.js");
+    assert!(head.starts_with("HTTP/1.1 200"));
+    assert!(initializer.contains("\"url\": \"../openapi.json\""));
+}
+
+#[test]
+fn the_rolling_average_rejects_invalid_parameters() {
+    let server = Server::start();
+
+    let (head, body) =
+        server.get("/api/rolling_average?start_date=2023-01-01&end_date=2023-01-07&days=7");
+    assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+    assert_eq!(body, "[]");
+
+    for (query, message) in [
+        (
+            "start_date=01/01/2023&end_date=2023-01-07&days=7",
+            "Invalid start_date or end_date",
+        ),
+        (
+            "start_date=2023-01-01&end_date=2023-02-30&days=7",
+            "Invalid start_date or end_date",
+        ),
+        (
+            "start_date=2023-01-01&end_date=2023-01-07&days=0",
+            "days must be between 1 and 7",
+        ),
+        (
+            "start_date=2023-01-01&end_date=2023-01-07&days=30",
+            "days must be between 1 and 7",
+        ),
+    ] {
+        let (head, body) = server.get(&format!("/api/rolling_average?{}", query));
+        assert!(head.starts_with("HTTP/1.1 400"), "{}: {}", query, head);
+        assert_eq!(body, message, "{}", query);
+    }
+}
+
+#[test]
+fn only_the_json_of_the_api_is_tagged() {
+    let server = Server::start();
+
+    for path in [
+        "/api/v1/weights?start_date=2023-01-01&end_date=2023-01-07",
+        "/api/rolling_average?start_date=2023-01-01&end_date=2023-01-07&days=7",
+    ] {
+        let (head, _) = server.get(path);
+        assert!(head.starts_with("HTTP/1.1 200"), "{}: {}", path, head);
+        assert!(head.to_lowercase().contains("etag: "), "{}: {}", path, head);
+    }
+    for path in ["/healthz", "/api/docs/", "/api/openapi.json"] {
+        let (head, _) = server.get(path);
+        assert!(head.starts_with("HTTP/1.1 200"), "{}: {}", path, head);
+        assert!(
+            !head.to_lowercase().contains("etag: "),
+            "{}: {}",
+            path,
+            head
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::NaiveDate;
+use rs_weight_tracker::output::{
+    interval_listing, take_format_arg, weights_listing, Listing, OutputFormat,
+};
+use rs_weight_tracker::Weight;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn weights() -> Vec<Weight> {
+    vec![
+        Weight {
+            id: 1,
+            weight_value: 80.4,
+            measurement_date: date(1),
+        },
+        Weight {
+            id: 12,
+            weight_value: 79.5,
+            measurement_date: date(3),
+        },
+    ]
+}
+
+fn render(listing: &Listing, format: OutputFormat) -> String {
+    let mut out = Vec::new();
+    listing.write(format, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn weights_have_the_same_columns_in_every_format() {
+    let listing = weights_listing(&weights());
+
+    assert_eq!(
+        render(&listing, OutputFormat::Csv),
+        "id,date,weight_kg\n1,2023-01-01,80.4\n12,2023-01-03,79.5\n"
+    );
+    assert_eq!(
+        render(&listing, OutputFormat::Tsv),
+        "id\tdate\tweight_kg\n1\t2023-01-01\t80.4\n12\t2023-01-03\t79.5\n"
+    );
+    assert_eq!(
+        render(&listing, OutputFormat::Table),
+        "id  date        weight_kg\n\
+         --  ----------  ---------\n \
+          1  2023-01-01       80.4\n\
+         12  2023-01-03       79.5\n"
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&render(&listing, OutputFormat::Json)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {"id": 1, "date": "2023-01-01", "weight_kg": 80.4},
+            {"id": 12, "date": "2023-01-03", "weight_kg": 79.5},
+        ])
+    );
+}
+
+#[test]
+fn interval_rows_keep_empty_averages_as_empty_cells() {
+    let weights: Vec<(Weight, bool)> = weights().into_iter().zip([false, true]).collect();
+    let listing = interval_listing(&weights, &[(date(3), 79.75)]);
+
+    assert_eq!(
+        render(&listing, OutputFormat::Csv),
+        "date,weight_kg,interpolated,average_kg\n\
+         2023-01-01,80.4,false,\n\
+         2023-01-03,79.5,true,79.75\n"
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&render(&listing, OutputFormat::Json)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {"date": "2023-01-01", "weight_kg": 80.4, "interpolated": false, "average_kg": null},
+            {"date": "2023-01-03", "weight_kg": 79.5, "interpolated": true, "average_kg": 79.75},
+        ])
+    );
+}
+
+#[test]
+fn the_format_flag_is_taken_from_the_arguments() {
+    let mut args: Vec<String> = ["show", "--format", "CSV", "2023-01-01"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    assert_eq!(take_format_arg(&mut args), Ok(Some(OutputFormat::Csv)));
+    assert_eq!(args, ["show", "2023-01-01"]);
+
+    let mut args = vec![String::from("--format=xml")];
+    assert!(take_format_arg(&mut args).is_err());
+}
@@ -0,0 +1,80 @@
+use chrono::NaiveDate;
+use rs_weight_tracker::chart::{render_png, ChartData, ImageOptions, Theme};
+use rs_weight_tracker::Weight;
+use tiny_skia::Pixmap;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn chart_data() -> ChartData {
+    let weights = [82.0, 81.0, 80.5]
+        .into_iter()
+        .zip(1..)
+        .map(|(weight_value, day)| {
+            let weight = Weight {
+                id: 0,
+                weight_value,
+                measurement_date: date(day),
+            };
+            (weight, false)
+        })
+        .collect();
+    ChartData {
+        start_date: date(1),
+        end_date: date(3),
+        weights,
+        averages: vec![(date(2), 81.5), (date(3), 80.75)],
+        average_days: 2,
+        goal: Some(79.0),
+        outliers: Vec::new(),
+    }
+}
+
+fn decode(png: &[u8]) -> Pixmap {
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    Pixmap::decode_png(png).unwrap()
+}
+
+#[test]
+fn renders_an_image_of_the_requested_size_in_the_theme_colors() {
+    for (theme, background) in [
+        (Theme::Light, (0xff, 0xff, 0xff)),
+        (Theme::Dark, (0x1e, 0x1e, 0x24)),
+    ] {
+        let options = ImageOptions {
+            width: 640,
+            height: 320,
+            theme,
+        };
+        let image = decode(&render_png(&chart_data(), &options).unwrap());
+
+        assert_eq!((image.width(), image.height()), (640, 320));
+        let corner = image.pixel(0, 0).unwrap();
+        assert_eq!((corner.red(), corner.green(), corner.blue()), background);
+        let drawn = image
+            .pixels()
+            .iter()
+            .filter(|pixel| (pixel.red(), pixel.green(), pixel.blue()) != background)
+            .count();
+        assert!(drawn > 1000, "only {} pixels drawn", drawn);
+    }
+}
+
+#[test]
+fn clamps_the_size_and_draws_an_empty_chart() {
+    let options = ImageOptions {
+        width: 10,
+        height: 10,
+        theme: Theme::Light,
+    };
+    let data = ChartData {
+        weights: Vec::new(),
+        averages: Vec::new(),
+        goal: None,
+        ..chart_data()
+    };
+    let image = decode(&render_png(&data, &options).unwrap());
+
+    assert_eq!((image.width(), image.height()), (200, 120));
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::TempDatabase;
+use rs_weight_tracker::report::{render_html, render_pdf, Report};
+use rs_weight_tracker::stats::GoalProgress;
+use rs_weight_tracker::Origin;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn report(database: &TempDatabase) -> Report {
+    let mut conn = database.connect();
+    let origin = Origin::new("test", "tester");
+    for (day, weight) in [(2, 82.0), (5, 81.2), (9, 80.6), (12, 80.0)] {
+        rs_weight_tracker::upsert_weight(&mut conn, weight, date(day), &origin).unwrap();
+    }
+    let mut report = Report::load(&mut conn, date(1), date(14), 7).unwrap();
+    report.goal = Some(GoalProgress::new(78.0, 82.0, 80.0));
+    report
+}
+
+#[test]
+fn summarizes_the_interval_by_week() {
+    let database = TempDatabase::new("report");
+    let report = report(&database);
+
+    let summary = report.summary.as_ref().unwrap();
+    assert_eq!((summary.count, summary.change()), (4, -2.0));
+    // Monday 2 to Sunday 8, then Monday 9 to Sunday 15.
+    let weeks: Vec<(NaiveDate, usize)> = report
+        .weekly
+        .iter()
+        .map(|week| (week.week_start, week.count))
+        .collect();
+    assert_eq!(weeks, vec![(date(2), 2), (date(9), 2)]);
+    assert!(report.rate_per_week.unwrap() < 0.0);
+}
+
+#[test]
+fn renders_a_self_contained_html_page() {
+    let database = TempDatabase::new("report-html");
+    let html = render_html(&report(&database));
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Weight progress report 2023-01-01 to 2023-01-14</title>"));
+    assert!(html.contains("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(html.contains("82.0 kg (2023-01-02)"));
+    assert!(html.contains("78.0 kg (50% done, 2.0 kg to go)"));
+    assert!(!html.contains("<script"));
+    assert!(!html.contains("src=\"http"));
+    assert!(html.trim_end().ends_with("</html>"));
+}
+
+#[test]
+fn renders_a_pdf_with_a_valid_cross_reference_table() {
+    let database = TempDatabase::new("report-pdf");
+    let pdf = render_pdf(&report(&database));
+    let text = String::from_utf8_lossy(&pdf);
+
+    assert!(text.starts_with("%PDF-1.4\n"));
+    assert!(text.ends_with("%%EOF\n"));
+    assert!(text.contains("(Weight progress report 2023-01-01 to 2023-01-14) Tj"));
+
+    let startxref: usize = text
+        .rsplit("startxref\n")
+        .next()
+        .unwrap()
+        .lines()
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(text[startxref..].starts_with("xref\n"));
+    let offsets: Vec<usize> = text[startxref..]
+        .lines()
+        .skip(3)
+        .take_while(|line| line.ends_with(" n "))
+        .map(|line| line[..10].parse().unwrap())
+        .collect();
+    assert!(offsets.len() >= 6);
+    for (index, offset) in offsets.iter().enumerate() {
+        assert!(text[*offset..].starts_with(&format!("{} 0 obj\n", index + 1)));
+    }
+}
@@ -0,0 +1,76 @@
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use rs_weight_tracker::server::{https_redirect, with_base_path};
+use tower::ServiceExt;
+
+async fn call(app: &Router, uri: &str) -> (StatusCode, String) {
+    let response = app
+        .clone()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let location = response.headers().get(header::LOCATION).cloned();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    match location {
+        Some(location) => (status, location.to_str().unwrap().to_owned()),
+        None => (status, String::from_utf8(body.to_vec()).unwrap()),
+    }
+}
+
+#[tokio::test]
+async fn the_app_is_served_under_the_base_path_only() {
+    let app = Router::new()
+        .route("/", get(|| async { "index" }))
+        .route("/api/weights", get(|| async { "weights" }));
+    let app = with_base_path(app, "/weight-tracker");
+
+    assert_eq!(
+        call(&app, "/weight-tracker/api/weights?limit=1").await,
+        (StatusCode::OK, String::from("weights"))
+    );
+    assert_eq!(
+        call(&app, "/weight-tracker/").await,
+        (StatusCode::OK, String::from("index"))
+    );
+    assert_eq!(
+        call(&app, "/weight-tracker?x=1").await,
+        (
+            StatusCode::PERMANENT_REDIRECT,
+            String::from("/weight-tracker/?x=1")
+        )
+    );
+    assert_eq!(call(&app, "/api/weights").await.0, StatusCode::NOT_FOUND);
+    assert_eq!(
+        call(&app, "/weight-trackers/").await.0,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn plain_http_is_redirected_to_the_https_port() {
+    let redirect = |host: &'static str, uri: &'static str| async move {
+        let response = https_redirect(8443)
+            .oneshot(
+                Request::get(uri)
+                    .header(header::HOST, host)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        response.headers()[header::LOCATION]
+            .to_str()
+            .unwrap()
+            .to_owned()
+    };
+
+    assert_eq!(
+        redirect("weight.example:8080", "/api/weights?limit=1").await,
+        "https://weight.example:8443/api/weights?limit=1"
+    );
+    assert_eq!(redirect("[::1]:8080", "/").await, "https://[::1]:8443/");
+}
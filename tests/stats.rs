@@ -0,0 +1,44 @@
+use chrono::NaiveDate;
+use rs_weight_tracker::stats::{GoalProgress, Summary};
+use rs_weight_tracker::Weight;
+
+fn weight(weight_value: f64, day: u32) -> Weight {
+    Weight {
+        id: 0,
+        weight_value,
+        measurement_date: NaiveDate::from_ymd_opt(2023, 1, day).unwrap(),
+    }
+}
+
+#[test]
+fn summarizes_the_weights_in_any_order() {
+    let weights = [weight(79.0, 3), weight(82.0, 1), weight(80.0, 2)];
+    let summary = Summary::from_weights(&weights).unwrap();
+
+    assert_eq!(summary.count, 3);
+    assert_eq!(summary.first_date, weights[1].measurement_date);
+    assert_eq!(summary.last_date, weights[0].measurement_date);
+    assert_eq!(summary.first_weight, 82.0);
+    assert_eq!(summary.last_weight, 79.0);
+    assert_eq!(summary.min_weight, 79.0);
+    assert_eq!(summary.max_weight, 82.0);
+    assert_eq!(summary.mean_weight, 80.333_333_333_333_33);
+    assert_eq!(summary.change(), -3.0);
+    assert_eq!(Summary::from_weights(&[]), None);
+}
+
+#[test]
+fn goal_progress_is_the_fraction_of_the_way_done() {
+    let losing = GoalProgress::new(75.0, 85.0, 80.0);
+    assert_eq!(losing.remaining(), 5.0);
+    assert_eq!(losing.fraction(), 0.5);
+
+    let gaining = GoalProgress::new(70.0, 60.0, 62.5);
+    assert_eq!(gaining.remaining(), -7.5);
+    assert_eq!(gaining.fraction(), 0.25);
+
+    // Overshooting or going the wrong way stays within the gauge.
+    assert_eq!(GoalProgress::new(75.0, 85.0, 74.0).fraction(), 1.0);
+    assert_eq!(GoalProgress::new(75.0, 85.0, 86.0).fraction(), 0.0);
+    assert_eq!(GoalProgress::new(75.0, 75.0, 75.0).fraction(), 1.0);
+}
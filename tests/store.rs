@@ -0,0 +1,131 @@
+mod common;
+
+use chrono::{Datelike, NaiveDate};
+use common::TempDatabase;
+use rs_weight_tracker::stats::{interpolate, rolling_averages};
+use rs_weight_tracker::store::{InMemoryStore, WeightStore};
+use rs_weight_tracker::{NewWeight, Origin, UpsertOutcome, Weight};
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn origin() -> Origin {
+    Origin::new("test", "tester")
+}
+
+fn weight(weight_value: f64, day: u32) -> Weight {
+    Weight {
+        id: 0,
+        weight_value,
+        measurement_date: date(day),
+    }
+}
+
+/// Runs the same changes on a store and returns what it reported and what it holds.
+fn exercise<S: WeightStore>(store: &mut S) -> (Vec<UpsertOutcome>, Vec<usize>, Vec<(u32, f64)>) {
+    let outcomes = vec![
+        store.upsert(80.0, date(1), &origin()).unwrap(),
+        store.upsert(81.0, date(3), &origin()).unwrap(),
+        store.upsert(80.5, date(1), &origin()).unwrap(),
+        store.upsert(80.5, date(1), &origin()).unwrap(),
+        store.upsert(82.0, date(2), &origin()).unwrap(),
+    ];
+    let deleted = vec![
+        store.delete(date(2), &origin()).unwrap(),
+        store.delete(date(2), &origin()).unwrap(),
+    ];
+    let between = store.weights_between(date(1), date(2)).unwrap();
+    assert_eq!(between.len(), 1);
+    let listed = store
+        .list()
+        .unwrap()
+        .iter()
+        .map(|weight| (weight.measurement_date.day(), weight.weight_value))
+        .collect();
+    (outcomes, deleted, listed)
+}
+
+#[test]
+fn the_in_memory_store_behaves_like_the_database() {
+    let database = TempDatabase::new("store");
+    let in_database = exercise(&mut database.connect());
+    let in_memory = exercise(&mut InMemoryStore::new());
+
+    assert_eq!(in_memory, in_database);
+    assert_eq!(
+        in_memory.0,
+        vec![
+            UpsertOutcome::Inserted,
+            UpsertOutcome::Inserted,
+            UpsertOutcome::Updated,
+            UpsertOutcome::Unchanged,
+            UpsertOutcome::Inserted,
+        ]
+    );
+    assert_eq!(in_memory.1, vec![1, 0]);
+    assert_eq!(in_memory.2, vec![(3, 81.0), (1, 80.5)]);
+}
+
+#[test]
+fn interpolates_between_adjacent_weights() {
+    let weights = [weight(80.0, 3), weight(78.0, 7)];
+    let series = interpolate(&weights, date(1), date(9));
+
+    let values: Vec<(u32, f64, bool)> = series
+        .iter()
+        .map(|(weight, is_interpolated)| {
+            (
+                weight.measurement_date.day(),
+                weight.weight_value,
+                *is_interpolated,
+            )
+        })
+        .collect();
+    // Nothing before the first weight, a line up to the next one, the last weight afterwards.
+    assert_eq!(
+        values,
+        vec![
+            (3, 80.0, false),
+            (4, 79.5, true),
+            (5, 79.0, true),
+            (6, 78.5, true),
+            (7, 78.0, false),
+            (8, 78.0, true),
+            (9, 78.0, true),
+        ]
+    );
+    assert!(interpolate(&[], date(1), date(9)).is_empty());
+}
+
+#[test]
+fn rolling_averages_start_at_the_first_day_asked() {
+    let weights: Vec<(Weight, bool)> = [80.0, 81.0, 82.0, 83.0, 84.0]
+        .iter()
+        .zip(1..)
+        .map(|(value, day)| (weight(*value, day), false))
+        .collect();
+
+    assert_eq!(
+        rolling_averages(&weights, date(3), 3),
+        vec![(date(3), 81.0), (date(4), 82.0), (date(5), 83.0)]
+    );
+    assert_eq!(
+        rolling_averages(&weights, date(5), 1),
+        vec![(date(5), 84.0)]
+    );
+}
+
+#[test]
+fn the_analytics_run_on_any_store() {
+    let mut store: InMemoryStore = [NewWeight::new(80.0, date(1)), NewWeight::new(82.0, date(3))]
+        .into_iter()
+        .collect();
+
+    let averages =
+        rs_weight_tracker::rolling_average_between_dates(&mut store, date(2), date(4), 2).unwrap();
+    assert_eq!(
+        averages,
+        vec![(date(2), 80.5), (date(3), 81.5), (date(4), 82.0)]
+    );
+}
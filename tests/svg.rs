@@ -0,0 +1,102 @@
+use chrono::NaiveDate;
+use rs_weight_tracker::chart::{render_svg, ChartData, ImageOptions, Theme};
+use rs_weight_tracker::Weight;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn chart_data() -> ChartData {
+    let weights = [(82.0, false), (81.5, true), (81.0, false), (80.0, false)]
+        .into_iter()
+        .zip(1..)
+        .map(|((weight_value, is_interpolated), day)| {
+            let weight = Weight {
+                id: 0,
+                weight_value,
+                measurement_date: date(day),
+            };
+            (weight, is_interpolated)
+        })
+        .collect();
+    ChartData {
+        start_date: date(1),
+        end_date: date(4),
+        weights,
+        averages: vec![(date(2), 81.75), (date(3), 81.5), (date(4), 81.0)],
+        average_days: 2,
+        goal: Some(78.0),
+        outliers: vec![date(4)],
+    }
+}
+
+/// The names of the elements, checking that every element is closed in order.
+fn elements(svg: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut open: Vec<String> = Vec::new();
+    for tag in svg.split('<').skip(1) {
+        let tag = &tag[..tag.find('>').expect("unterminated tag")];
+        if let Some(name) = tag.strip_prefix('/') {
+            assert_eq!(open.pop().as_deref(), Some(name), "mismatched </{}>", name);
+            continue;
+        }
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap()
+            .to_owned();
+        if !tag.ends_with('/') {
+            open.push(name.clone());
+        }
+        names.push(name);
+    }
+    assert!(open.is_empty(), "unclosed elements: {:?}", open);
+    names
+}
+
+#[test]
+fn renders_a_well_formed_document_of_the_requested_size() {
+    let options = ImageOptions {
+        width: 640,
+        height: 320,
+        theme: Theme::Light,
+    };
+    let svg = render_svg(&chart_data(), &options);
+
+    assert!(svg.starts_with(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"640\" height=\"320\" \
+         viewBox=\"0 0 640 320\""
+    ));
+    assert!(svg.contains("<title>Weights from 2023-01-01 to 2023-01-04</title>"));
+    assert!(svg.contains("fill=\"#ffffff\""));
+
+    let names = elements(&svg);
+    assert_eq!(names[0], "svg");
+    // One dot per actual weight, plus the circle around the outlier.
+    let circles = names.iter().filter(|name| *name == "circle").count();
+    assert_eq!(circles, 3 + 1);
+    assert!(svg.contains("goal 78.0 kg"));
+    assert!(svg.contains("2-day rolling average"));
+    assert!(svg.contains("stroke-dasharray=\"4 3\""));
+}
+
+#[test]
+fn follows_the_theme_and_handles_an_empty_chart() {
+    let dark = ImageOptions {
+        theme: Theme::Dark,
+        ..ImageOptions::default()
+    };
+    let data = ChartData {
+        weights: Vec::new(),
+        averages: Vec::new(),
+        goal: None,
+        outliers: Vec::new(),
+        ..chart_data()
+    };
+    let svg = render_svg(&data, &dark);
+
+    elements(&svg);
+    assert!(svg.contains("width=\"800\" height=\"400\""));
+    assert!(svg.contains("fill=\"#1e1e24\""));
+    assert!(svg.contains("No weights to display"));
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDate;
+use rs_weight_tracker::chart::{render_chart, ChartOptions, ChartStyle};
+use rs_weight_tracker::Weight;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn weight(weight_value: f64, day: u32, is_interpolated: bool) -> (Weight, bool) {
+    let weight = Weight {
+        id: 0,
+        weight_value,
+        measurement_date: date(day),
+    };
+    (weight, is_interpolated)
+}
+
+fn ascii(width: usize, height: usize) -> ChartOptions {
+    ChartOptions {
+        width,
+        height,
+        style: ChartStyle::Ascii,
+    }
+}
+
+#[test]
+fn draws_the_weights_within_the_requested_size() {
+    let weights = [
+        weight(82.0, 1, false),
+        weight(81.0, 2, true),
+        weight(80.0, 3, false),
+    ];
+    let averages = [(date(2), 81.5), (date(3), 81.0)];
+    let chart = render_chart(&weights, &averages, &ascii(40, 8));
+    let lines: Vec<&str> = chart.lines().collect();
+
+    // The plot rows, the horizontal axis, the dates and the legend.
+    assert_eq!(lines.len(), 8 + 3);
+    assert!(lines[..10].iter().all(|line| line.chars().count() == 40));
+    assert!(lines[0].starts_with("82.0 |"));
+    assert!(lines[7].starts_with("80.0 |"));
+    assert!(lines[8].starts_with("     +---"));
+    assert!(lines[9].contains("2023-01-01") && lines[9].ends_with("2023-01-03"));
+    assert!(lines[10].contains("* actual  . interpolated  - rolling average (kg)"));
+
+    let plot: String = lines[..8]
+        .iter()
+        .map(|line| line.split_once('|').unwrap().1)
+        .collect();
+    assert_eq!(plot.matches('*').count(), 2);
+    assert_eq!(plot.matches('.').count(), 1);
+    assert!(plot.contains('-'));
+}
+
+#[test]
+fn braille_charts_use_braille_markers() {
+    let weights = [weight(82.0, 1, false), weight(80.0, 5, false)];
+    let chart = render_chart(&weights, &[], &ChartOptions::default());
+
+    assert!(chart.contains('●'));
+    assert!(chart.lines().next().unwrap().contains('┤'));
+    assert!(chart
+        .lines()
+        .take(16)
+        .all(|line| line.chars().count() == 80));
+}
+
+#[test]
+fn says_so_when_there_is_nothing_to_draw() {
+    assert_eq!(
+        render_chart(&[], &[], &ascii(40, 8)),
+        "No weights to display\n"
+    );
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use chrono::{Duration, NaiveDate};
+use common::TempDatabase;
+use diesel::prelude::*;
+use rs_weight_tracker::db::DbConnection;
+use rs_weight_tracker::schema::weight_tags;
+use rs_weight_tracker::trash::{
+    deleted_weights, purge_deleted, restore_last_deleted, restore_weight,
+};
+use rs_weight_tracker::{NewMeasurement, Origin, UpsertOutcome, Weight};
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn origin() -> Origin {
+    Origin::new("test", "tester")
+}
+
+fn stored_dates(conn: &mut DbConnection) -> Vec<NaiveDate> {
+    Weight::all()
+        .load::<Weight>(conn)
+        .unwrap()
+        .into_iter()
+        .map(|weight| weight.measurement_date)
+        .collect()
+}
+
+#[test]
+fn deleted_weights_are_hidden_until_restored() {
+    let database = TempDatabase::new("soft-delete");
+    let mut conn = database.connect();
+    for day in 1..=3 {
+        rs_weight_tracker::upsert_weight(&mut conn, 80.0 + day as f64, date(day), &origin())
+            .unwrap();
+    }
+
+    assert_eq!(
+        rs_weight_tracker::delete_weight(&mut conn, date(2), &origin()).unwrap(),
+        1
+    );
+    assert_eq!(
+        rs_weight_tracker::delete_weight(&mut conn, date(2), &origin()).unwrap(),
+        0
+    );
+
+    assert_eq!(stored_dates(&mut conn), vec![date(3), date(1)]);
+    let between = rs_weight_tracker::weights_between_dates(&mut conn, date(1), date(3)).unwrap();
+    assert_eq!(between.len(), 2);
+    // The deleted day is interpolated like any other missing day.
+    let interpolated =
+        rs_weight_tracker::weights_between_dates_with_interpolation(&mut conn, date(1), date(3))
+            .unwrap();
+    assert!(interpolated[1].1);
+    assert_eq!(deleted_weights(&mut conn).unwrap().len(), 1);
+
+    let restored = restore_weight(&mut conn, date(2), &origin())
+        .unwrap()
+        .unwrap();
+    assert_eq!(restored.weight_value, 82.0);
+    assert_eq!(stored_dates(&mut conn), vec![date(3), date(2), date(1)]);
+    assert!(restore_weight(&mut conn, date(2), &origin())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn storing_a_deleted_day_inserts_it_again() {
+    let database = TempDatabase::new("reinsert");
+    let mut conn = database.connect();
+
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin()).unwrap();
+    rs_weight_tracker::delete_weight(&mut conn, date(1), &origin()).unwrap();
+    let outcome =
+        rs_weight_tracker::upsert_weight_with_outcome(&mut conn, 80.0, date(1), &origin()).unwrap();
+
+    assert_eq!(outcome, UpsertOutcome::Inserted);
+    assert_eq!(stored_dates(&mut conn), vec![date(1)]);
+    assert!(deleted_weights(&mut conn).unwrap().is_empty());
+}
+
+#[test]
+fn undo_restores_the_last_delete_and_purge_empties_the_trash() {
+    let database = TempDatabase::new("undo-purge");
+    let mut conn = database.connect();
+    for day in 1..=3 {
+        rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(day), &origin()).unwrap();
+    }
+    let mut tagged = NewMeasurement::new(80.0, date(1));
+    tagged.tags = Some(vec![String::from("holiday")]);
+    rs_weight_tracker::upsert_measurement(&mut conn, &tagged, &origin()).unwrap();
+    rs_weight_tracker::delete_weight(&mut conn, date(1), &origin()).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    rs_weight_tracker::delete_weight(&mut conn, date(3), &origin()).unwrap();
+
+    let restored = restore_last_deleted(&mut conn, &origin()).unwrap().unwrap();
+    assert_eq!(restored.measurement_date, date(3));
+
+    // Nothing has been in the trash for a day yet, let alone for longer than time goes back.
+    assert_eq!(
+        purge_deleted(&mut conn, Duration::days(1), &origin()).unwrap(),
+        0
+    );
+    assert_eq!(
+        purge_deleted(&mut conn, Duration::days(u32::MAX.into()), &origin()).unwrap(),
+        0
+    );
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert_eq!(
+        purge_deleted(&mut conn, Duration::zero(), &origin()).unwrap(),
+        1
+    );
+    assert!(deleted_weights(&mut conn).unwrap().is_empty());
+    // The tags of the purged weight went with it.
+    let tag_links: i64 = weight_tags::table.count().get_result(&mut conn).unwrap();
+    assert_eq!(tag_links, 0);
+    assert!(restore_last_deleted(&mut conn, &origin())
+        .unwrap()
+        .is_none());
+
+    let history = rs_weight_tracker::history::history_for_date(&mut conn, date(1)).unwrap();
+    let actions: Vec<&str> = history
+        .iter()
+        .map(|change| change.action.as_str())
+        .collect();
+    assert_eq!(actions, vec!["insert", "delete", "purge"]);
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::TempDatabase;
+use diesel::prelude::*;
+use rs_weight_tracker::db::DbConnection;
+use rs_weight_tracker::history::history_for_date;
+use rs_weight_tracker::versions::current_version;
+use rs_weight_tracker::{NewMeasurement, Origin, UpsertOutcome, Weight};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+fn all_weights(conn: &mut DbConnection) -> Vec<Weight> {
+    use rs_weight_tracker::schema::weights::dsl::*;
+    weights
+        .select(Weight::as_select())
+        .order(measurement_date.asc())
+        .load::<Weight>(conn)
+        .unwrap()
+}
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn origin() -> Origin {
+    Origin::new("test", "tester")
+}
+
+/// The weight a writer stores for the shared day on its write at the given index.
+fn shared_value(writer: u32, index: u32) -> f64 {
+    70.0 + (writer * 10 + index) as f64
+}
+
+#[test]
+fn reports_inserted_updated_and_unchanged() {
+    let database = TempDatabase::new("outcomes");
+    let mut conn = database.connect();
+
+    let upsert = |conn: &mut DbConnection, value| {
+        rs_weight_tracker::upsert_weight_with_outcome(conn, value, date(1), &origin()).unwrap()
+    };
+    assert_eq!(upsert(&mut conn, 80.0), UpsertOutcome::Inserted);
+    assert_eq!(upsert(&mut conn, 80.0), UpsertOutcome::Unchanged);
+    assert_eq!(upsert(&mut conn, 79.5), UpsertOutcome::Updated);
+
+    let stored = all_weights(&mut conn);
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].weight_value, 79.5);
+}
+
+#[test]
+fn model_upsert_only_touches_its_own_date() {
+    let database = TempDatabase::new("model");
+    let mut conn = database.connect();
+
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin()).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 81.0, date(2), &origin()).unwrap();
+
+    let weight = Weight {
+        id: 0,
+        weight_value: 79.0,
+        measurement_date: date(1),
+    };
+    assert_eq!(weight.upsert(&mut conn, &origin()).unwrap(), 1);
+
+    let stored = all_weights(&mut conn);
+    let values: Vec<f64> = stored.iter().map(|weight| weight.weight_value).collect();
+    assert_eq!(values, vec![79.0, 81.0]);
+}
+
+#[test]
+fn concurrent_upserts_of_the_same_day_do_not_conflict() {
+    const WRITERS: usize = 8;
+    let database = TempDatabase::new("same-day");
+    let barrier = Arc::new(Barrier::new(WRITERS));
+
+    let outcomes: Vec<UpsertOutcome> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                let barrier = Arc::clone(&barrier);
+                let database = &database;
+                scope.spawn(move || {
+                    let mut conn = database.connect();
+                    barrier.wait();
+                    rs_weight_tracker::upsert_weight_with_outcome(
+                        &mut conn,
+                        70.0 + writer as f64,
+                        date(1),
+                        &origin(),
+                    )
+                    .unwrap()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    let inserted = outcomes
+        .iter()
+        .filter(|outcome| **outcome == UpsertOutcome::Inserted)
+        .count();
+    assert_eq!(inserted, 1);
+    assert_eq!(all_weights(&mut database.connect()).len(), 1);
+}
+
+#[test]
+fn concurrent_writers_do_not_lose_updates() {
+    const WRITERS: u32 = 4;
+    const DAYS_PER_WRITER: u32 = 6;
+    let database = TempDatabase::new("lost-updates");
+    let barrier = Arc::new(Barrier::new(WRITERS as usize));
+
+    // Every writer stores its own days, alternating single weights and batches that also write
+    // the shared first day, each time with a weight of its own. Every write that succeeded must be
+    // in the database afterwards.
+    thread::scope(|scope| {
+        for writer in 0..WRITERS {
+            let barrier = Arc::clone(&barrier);
+            let database = &database;
+            scope.spawn(move || {
+                let mut conn = database.connect();
+                barrier.wait();
+                for index in 0..DAYS_PER_WRITER {
+                    let day = date(2 + writer * DAYS_PER_WRITER + index);
+                    let value = 60.0 + writer as f64;
+                    if index % 2 == 0 {
+                        rs_weight_tracker::upsert_weight(&mut conn, value, day, &origin()).unwrap();
+                    } else {
+                        let batch = [
+                            NewMeasurement::new(value, day),
+                            NewMeasurement::new(shared_value(writer, index), date(1)),
+                        ];
+                        rs_weight_tracker::upsert_weights(&mut conn, &batch, &origin()).unwrap();
+                    }
+                }
+            });
+        }
+    });
+
+    let stored = all_weights(&mut database.connect());
+    assert_eq!(stored.len(), (1 + WRITERS * DAYS_PER_WRITER) as usize);
+    for weight in &stored[1..] {
+        let writer = (weight.measurement_date - date(2)).num_days() as u32 / DAYS_PER_WRITER;
+        assert_eq!(weight.weight_value, 60.0 + writer as f64);
+    }
+
+    // Each write of the shared day changed it: none was lost, so each one is in its version and
+    // its history.
+    let shared_writes: Vec<f64> = (0..WRITERS)
+        .flat_map(|writer| {
+            (0..DAYS_PER_WRITER)
+                .filter(|index| index % 2 == 1)
+                .map(move |index| shared_value(writer, index))
+        })
+        .collect();
+    let mut conn = database.connect();
+    assert!(shared_writes.contains(&stored[0].weight_value));
+    let version = current_version(&mut conn, date(1)).unwrap().unwrap();
+    assert_eq!(version.version as usize, shared_writes.len());
+    let history = history_for_date(&mut conn, date(1)).unwrap();
+    assert_eq!(history.len(), shared_writes.len());
+    assert_eq!(
+        history.last().unwrap().new_value,
+        Some(stored[0].weight_value)
+    );
+}
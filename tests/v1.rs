@@ -0,0 +1,181 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::{Extension, Router};
+use common::TempDatabase;
+use rs_weight_tracker::api::idempotency::{KeyRetention, PendingTimeout};
+use rs_weight_tracker::api::v1;
+use rs_weight_tracker::validation::ValidationRules;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+fn app(database: &TempDatabase) -> Router {
+    rs_weight_tracker::config::init(&mut vec![
+        String::from("test"),
+        String::from("--database-url"),
+        database.url(),
+    ])
+    .unwrap();
+    Router::new()
+        .nest("/api/v1", v1::router())
+        .layer(Extension(ValidationRules::default()))
+        .layer(Extension(KeyRetention(chrono::Duration::hours(1))))
+        .layer(Extension(PendingTimeout(chrono::Duration::minutes(1))))
+}
+
+async fn call(app: &Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn responses_are_enveloped_and_errors_have_stable_codes() {
+    let database = TempDatabase::new("v1");
+    let app = app(&database);
+
+    let (status, body) = call(
+        &app,
+        Method::POST,
+        "/api/v1/weights",
+        Some(json!({"date": "2023-01-01", "weight_kg": 80.0})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        body,
+        json!({
+            "data": {"date": "2023-01-01", "weight_kg": 80.0, "rows": 1},
+            "meta": {"api_version": "v1", "unit": "kg", "interpolated": false}
+        })
+    );
+    call(
+        &app,
+        Method::POST,
+        "/api/v1/weights",
+        Some(json!({"date": "2023-01-03", "weight_kg": 81.0})),
+    )
+    .await;
+
+    let request = Request::get("/api/v1/weights/2023-01-01")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    assert!(etag.starts_with('"') && etag.ends_with("-1\""), "{}", etag);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["data"]["weight_kg"], json!(80.0));
+
+    let (status, body) = call(
+        &app,
+        Method::GET,
+        "/api/v1/weights?start_date=2023-01-01&end_date=2023-01-03&interpolate=true",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["meta"]["interpolated"], json!(true));
+    let days: Vec<(&str, bool)> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| {
+            (
+                entry["date"].as_str().unwrap(),
+                entry["interpolated"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        days,
+        vec![
+            ("2023-01-01", false),
+            ("2023-01-02", true),
+            ("2023-01-03", false)
+        ]
+    );
+
+    let (status, body) = call(
+        &app,
+        Method::GET,
+        "/api/v1/rolling_average?start_date=2023-01-02&end_date=2023-01-03&days=2",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["meta"]["window_days"], json!(2));
+    assert_eq!(
+        body["data"][0],
+        json!({"date": "2023-01-02", "average_kg": 80.25})
+    );
+
+    let errors = [
+        (
+            Method::GET,
+            "/api/v1/weights?end_date=2023-01-03",
+            None,
+            StatusCode::BAD_REQUEST,
+            "invalid_parameter",
+        ),
+        (
+            Method::GET,
+            "/api/v1/weights?start_date=01/01/2023&end_date=2023-01-03",
+            None,
+            StatusCode::BAD_REQUEST,
+            "invalid_parameter",
+        ),
+        (
+            Method::POST,
+            "/api/v1/weights",
+            Some(json!({"date": "2023-01-04"})),
+            StatusCode::BAD_REQUEST,
+            "invalid_body",
+        ),
+        (
+            Method::POST,
+            "/api/v1/weights",
+            Some(json!({"date": "2023-01-04", "weight_kg": 8.0})),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "validation_failed",
+        ),
+        (
+            Method::GET,
+            "/api/v1/rolling_average?start_date=2023-01-01&end_date=2023-01-03&days=8",
+            None,
+            StatusCode::BAD_REQUEST,
+            "invalid_parameter",
+        ),
+        (
+            Method::GET,
+            "/api/v1/weights/2023-01-02",
+            None,
+            StatusCode::NOT_FOUND,
+            "not_found",
+        ),
+    ];
+    for (method, uri, payload, expected_status, expected_code) in errors {
+        let (status, body) = call(&app, method, uri, payload).await;
+        assert_eq!(
+            (status, body["error"]["code"].as_str()),
+            (expected_status, Some(expected_code)),
+            "{}",
+            uri
+        );
+        assert!(body["error"]["message"].is_string());
+        assert!(body.get("data").is_none());
+    }
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::TempDatabase;
+use rs_weight_tracker::validation::{ValidationError, ValidationRules};
+use rs_weight_tracker::Origin;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn codes(errors: &[ValidationError]) -> Vec<&'static str> {
+    errors.iter().map(ValidationError::code).collect()
+}
+
+#[test]
+fn checks_bounds_and_future_dates() {
+    let database = TempDatabase::new("validation-bounds");
+    let mut conn = database.connect();
+    let rules = ValidationRules::default();
+    let today = date(10);
+
+    let mut check = |value, day| rules.check_on(&mut conn, value, date(day), today).unwrap();
+    assert!(check(81.4, 10).is_empty());
+    assert!(check(81.4, 11).is_empty());
+    assert_eq!(codes(&check(19.0, 5)), vec!["weight_too_low"]);
+    assert_eq!(codes(&check(-80.0, 5)), vec!["weight_too_low"]);
+    assert_eq!(codes(&check(812.0, 5)), vec!["weight_too_high"]);
+    assert_eq!(codes(&check(f64::NAN, 5)), vec!["weight_not_finite"]);
+    assert_eq!(
+        codes(&check(19.0, 20)),
+        vec!["weight_too_low", "date_in_future"]
+    );
+}
+
+#[test]
+fn checks_the_change_from_the_closest_weights() {
+    let database = TempDatabase::new("validation-change");
+    let mut conn = database.connect();
+    let origin = Origin::new("test", "tester");
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 90.0, date(20), &origin).unwrap();
+    rs_weight_tracker::upsert_weight(&mut conn, 50.0, date(25), &origin).unwrap();
+    rs_weight_tracker::delete_weight(&mut conn, date(25), &origin).unwrap();
+
+    let rules = ValidationRules {
+        max_daily_change_kg: 1.0,
+        ..ValidationRules::default()
+    };
+    let mut check = |value, day| {
+        rules
+            .check_on(&mut conn, value, date(day), date(31))
+            .unwrap()
+    };
+
+    // At most 1 kg from the 1st and 18 kg from the 20th.
+    assert!(check(81.0, 2).is_empty());
+    assert_eq!(
+        check(83.0, 2),
+        vec![ValidationError::ChangeTooLarge {
+            value: 83.0,
+            date: date(2),
+            other_value: 80.0,
+            other_date: date(1),
+            max_change_kg: 1.0,
+        }]
+    );
+    // Storing a day again is compared with the other days only, and weights in the trash are
+    // left out.
+    assert!(check(80.5, 1).is_empty());
+    assert_eq!(codes(&check(70.0, 21)), vec!["change_too_large"]);
+    assert!(check(90.0, 26).is_empty());
+}
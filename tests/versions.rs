@@ -0,0 +1,161 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::TempDatabase;
+use rs_weight_tracker::validation::ValidationRules;
+use rs_weight_tracker::versions::{
+    current_version, delete_weight_if, upsert_measurement_if, ConditionalError, Precondition,
+    WeightVersion,
+};
+use rs_weight_tracker::{NewMeasurement, Origin, UpsertOutcome};
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+}
+
+fn origin() -> Origin {
+    Origin::new("test", "tester")
+}
+
+#[test]
+fn every_change_of_a_weight_increments_its_version() {
+    let database = TempDatabase::new("versions");
+    let mut conn = database.connect();
+    assert_eq!(current_version(&mut conn, date(1)).unwrap(), None);
+
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin()).unwrap();
+    let first = current_version(&mut conn, date(1)).unwrap().unwrap();
+    assert_eq!(first.version, 1);
+
+    // Storing the same weight again changes nothing.
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin()).unwrap();
+    assert_eq!(current_version(&mut conn, date(1)).unwrap(), Some(first));
+
+    let mut measurement = NewMeasurement::new(80.0, date(1));
+    measurement.note = Some(String::from("after holiday"));
+    rs_weight_tracker::upsert_measurement(&mut conn, &measurement, &origin()).unwrap();
+    let annotated = current_version(&mut conn, date(1)).unwrap().unwrap();
+    assert_eq!(annotated, WeightVersion::new(first.id, 2));
+
+    // Storing the note and tags the day already has changes nothing either.
+    rs_weight_tracker::upsert_measurement(&mut conn, &measurement, &origin()).unwrap();
+    measurement.note = Some(String::from(" after holiday "));
+    measurement.tags = Some(vec![String::from("Scale"), String::from("holiday")]);
+    rs_weight_tracker::upsert_measurement(&mut conn, &measurement, &origin()).unwrap();
+    let tagged = current_version(&mut conn, date(1)).unwrap().unwrap();
+    assert_eq!(tagged, WeightVersion::new(first.id, 3));
+    measurement.tags = Some(vec![String::from("holiday"), String::from("scale ")]);
+    rs_weight_tracker::upsert_measurement(&mut conn, &measurement, &origin()).unwrap();
+    assert_eq!(current_version(&mut conn, date(1)).unwrap(), Some(tagged));
+
+    rs_weight_tracker::delete_weight(&mut conn, date(1), &origin()).unwrap();
+    assert_eq!(current_version(&mut conn, date(1)).unwrap(), None);
+    rs_weight_tracker::trash::restore_weight(&mut conn, date(1), &origin()).unwrap();
+    assert_eq!(
+        current_version(&mut conn, date(1)).unwrap(),
+        Some(WeightVersion::new(first.id, 5))
+    );
+
+    let etag = annotated.etag();
+    assert_eq!(etag, format!("\"{}-2\"", first.id));
+    assert_eq!(WeightVersion::from_etag(&etag), Some(annotated));
+    assert_eq!(
+        WeightVersion::from_etag(&format!("W/{}", etag)),
+        Some(annotated)
+    );
+    assert_eq!(WeightVersion::from_etag("\"5c64ceada267\""), None);
+}
+
+#[test]
+fn conditional_writes_are_refused_when_the_weight_changed() {
+    let database = TempDatabase::new("conditional-writes");
+    let mut conn = database.connect();
+
+    let (outcome, created) = upsert_measurement_if(
+        &mut conn,
+        &NewMeasurement::new(80.0, date(1)),
+        &origin(),
+        Some(&Precondition::Absent),
+        None,
+    )
+    .unwrap();
+    assert_eq!(outcome, UpsertOutcome::Inserted);
+
+    // Another client already stored a weight for the day.
+    let result = upsert_measurement_if(
+        &mut conn,
+        &NewMeasurement::new(81.0, date(1)),
+        &origin(),
+        Some(&Precondition::Absent),
+        None,
+    );
+    assert!(matches!(
+        result,
+        Err(ConditionalError::PreconditionFailed(Some(current))) if current == created
+    ));
+
+    let (outcome, updated) = upsert_measurement_if(
+        &mut conn,
+        &NewMeasurement::new(81.0, date(1)),
+        &origin(),
+        Some(&Precondition::OneOf(vec![created])),
+        None,
+    )
+    .unwrap();
+    assert_eq!(outcome, UpsertOutcome::Updated);
+    assert_eq!(updated.version, created.version + 1);
+
+    // A client that read the weight before the update cannot overwrite or delete it.
+    let stale = Precondition::OneOf(vec![created]);
+    assert!(matches!(
+        upsert_measurement_if(
+            &mut conn,
+            &NewMeasurement::new(79.0, date(1)),
+            &origin(),
+            Some(&stale),
+            None,
+        ),
+        Err(ConditionalError::PreconditionFailed(_))
+    ));
+    assert!(matches!(
+        delete_weight_if(&mut conn, date(1), &origin(), Some(&stale)),
+        Err(ConditionalError::PreconditionFailed(_))
+    ));
+    let weights = rs_weight_tracker::weights_between_dates(&mut conn, date(1), date(1)).unwrap();
+    assert_eq!(weights[0].weight_value, 81.0);
+
+    assert_eq!(
+        delete_weight_if(
+            &mut conn,
+            date(1),
+            &origin(),
+            Some(&Precondition::OneOf(vec![updated]))
+        )
+        .unwrap(),
+        1
+    );
+    assert!(matches!(
+        delete_weight_if(&mut conn, date(1), &origin(), Some(&Precondition::Exists)),
+        Err(ConditionalError::PreconditionFailed(None))
+    ));
+}
+
+#[test]
+fn implausible_weights_are_refused_when_rules_are_given() {
+    let database = TempDatabase::new("conditional-rules");
+    let mut conn = database.connect();
+    let rules = ValidationRules::default();
+    rs_weight_tracker::upsert_weight(&mut conn, 80.0, date(1), &origin()).unwrap();
+
+    let jump = NewMeasurement::new(90.0, date(2));
+    let result = upsert_measurement_if(&mut conn, &jump, &origin(), None, Some(&rules));
+    assert!(matches!(
+        result,
+        Err(ConditionalError::Implausible(errors)) if errors[0].code() == "change_too_large"
+    ));
+    assert_eq!(current_version(&mut conn, date(2)).unwrap(), None);
+
+    // Without rules, as for a forced weight, it is stored.
+    let (outcome, _) = upsert_measurement_if(&mut conn, &jump, &origin(), None, None).unwrap();
+    assert_eq!(outcome, UpsertOutcome::Inserted);
+}